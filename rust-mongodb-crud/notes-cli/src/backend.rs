@@ -0,0 +1,124 @@
+use crate::Result;
+use futures::TryStreamExt;
+use notes_client::{
+    CreateNoteSchema, FilterOptions, NoteResponse, NotesClient, SingleNoteResponse,
+    UpdateNoteSchema,
+};
+use rust_mongodb_crud::db::DB;
+
+/// Number of notes fetched per request when reading every note.
+const PAGE_SIZE: usize = 100;
+
+/// Where the CLI reads and writes notes.
+pub enum Backend {
+    /// Talks to a running API server over HTTP.
+    Http(NotesClient),
+    /// Talks to the database directly, without a running server (offline admin mode).
+    Database {
+        /// Connection to the notes database, scoped to the selected workspace.
+        db: DB,
+        /// User acting on the notes, who owns the created notes.
+        user: Option<String>,
+    },
+}
+
+impl Backend {
+    /// Fetches a page of notes, optionally matching a full-text query.
+    pub async fn list(&self, options: &FilterOptions) -> Result<Vec<NoteResponse>> {
+        let notes = match self {
+            Backend::Http(client) => client.list_notes(options).await?.notes,
+            Backend::Database { db, user } => {
+                let limit = options.limit.unwrap_or(10) as i64;
+                let page = options.page.unwrap_or(1) as i64;
+                db.fetch_notes(limit, page, options.q.as_deref(), user.as_deref())
+                    .await?
+                    .notes
+            }
+        };
+
+        Ok(notes)
+    }
+
+    /// Fetches every note, optionally matching a full-text query.
+    pub async fn all(&self, query: Option<&str>) -> Result<Vec<NoteResponse>> {
+        if let Backend::Http(client) = self {
+            let pages = match query {
+                Some(query) => client.search_pages(query, PAGE_SIZE),
+                None => client.pages(PAGE_SIZE),
+            };
+            return Ok(pages.into_stream().try_collect().await?);
+        }
+
+        // Page through the database until a short page is returned
+        let mut notes = Vec::new();
+        for page in 1.. {
+            let options = FilterOptions {
+                page: Some(page),
+                limit: Some(PAGE_SIZE),
+                q: query.map(str::to_string),
+                render: None,
+            };
+            let batch = self.list(&options).await?;
+            let done = batch.len() < PAGE_SIZE;
+            notes.extend(batch);
+            if done {
+                break;
+            }
+        }
+
+        Ok(notes)
+    }
+
+    /// Fetches a note by ID.
+    pub async fn get(&self, id: &str) -> Result<NoteResponse> {
+        match self {
+            Backend::Http(client) => Ok(client.get_note(id).await?),
+            Backend::Database { db, user } => found(id, db.get_note(id, user.as_deref()).await?),
+        }
+    }
+
+    /// Creates a note.
+    pub async fn create(&self, body: &CreateNoteSchema) -> Result<NoteResponse> {
+        match self {
+            Backend::Http(client) => Ok(client.create_note(body).await?),
+            Backend::Database { db, user } => {
+                let note = db.create_note(body, user.as_deref()).await?;
+                note.map(|note| note.data.note)
+                    .ok_or_else(|| "Note was created but could not be read back".into())
+            }
+        }
+    }
+
+    /// Updates the fields of a note present in `body`.
+    pub async fn update(&self, id: &str, body: &UpdateNoteSchema) -> Result<NoteResponse> {
+        match self {
+            Backend::Http(client) => Ok(client.update_note(id, body).await?),
+            Backend::Database { db, user } => {
+                found(id, db.edit_note(id, body, user.as_deref()).await?)
+            }
+        }
+    }
+
+    /// Deletes a note.
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        match self {
+            Backend::Http(client) => Ok(client.delete_note(id).await?),
+            Backend::Database { db, user } => match db.delete_note(id, user.as_deref()).await? {
+                Some(()) => Ok(()),
+                None => Err(not_found(id)),
+            },
+        }
+    }
+}
+
+/// Extracts the note of a database response, treating a missing note as an error.
+fn found(id: &str, response: Option<SingleNoteResponse>) -> Result<NoteResponse> {
+    response
+        .map(|response| response.data.note)
+        .ok_or_else(|| not_found(id))
+}
+
+/// Error reported when a note does not exist.
+fn not_found(id: &str) -> Box<dyn std::error::Error> {
+    format!("Note with ID: {} not found", id).into()
+}
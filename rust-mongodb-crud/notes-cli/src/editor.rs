@@ -0,0 +1,37 @@
+use crate::Result;
+use std::io::Write;
+use std::process::Command;
+
+/// Editor used when neither `$VISUAL` nor `$EDITOR` is set.
+const DEFAULT_EDITOR: &str = "vi";
+
+/// Opens the user's editor on `initial` and returns the edited text.
+///
+/// The editor is taken from `$VISUAL`, then `$EDITOR`, and may include arguments,
+/// e.g. `code --wait`.
+pub fn edit(initial: &str) -> Result<String> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| DEFAULT_EDITOR.to_string());
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or(DEFAULT_EDITOR);
+
+    // Write the current content to a temporary Markdown file
+    let mut file = tempfile::Builder::new()
+        .prefix("note-")
+        .suffix(".md")
+        .tempfile()?;
+    file.write_all(initial.as_bytes())?;
+    file.flush()?;
+
+    let status = Command::new(program)
+        .args(parts)
+        .arg(file.path())
+        .status()
+        .map_err(|e| format!("could not start editor {}: {}", program, e))?;
+    if !status.success() {
+        return Err(format!("editor {} exited with {}", program, status).into());
+    }
+
+    Ok(std::fs::read_to_string(file.path())?)
+}
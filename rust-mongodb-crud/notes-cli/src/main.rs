@@ -0,0 +1,232 @@
+mod backend;
+mod editor;
+mod output;
+
+use backend::Backend;
+use clap::{Parser, Subcommand};
+use dotenv::dotenv;
+use notes_client::{CreateNoteSchema, FilterOptions, NotesClient, UpdateNoteSchema};
+use output::{ExportFormat, OutputFormat};
+use rust_mongodb_crud::db::DB;
+use std::path::PathBuf;
+
+/// Result type used by the command line interface.
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+/// Manage notes from the command line.
+#[derive(Debug, Parser)]
+#[command(name = "notes", version, about)]
+struct Cli {
+    /// Base URL of the notes API.
+    #[arg(long, env = "NOTES_API_URL", default_value = "http://localhost:8080")]
+    url: String,
+
+    /// User the requests are made for, sent in the `X-User-Id` header.
+    #[arg(long, env = "NOTES_USER")]
+    user: Option<String>,
+
+    /// Workspace holding the notes, sent in the `X-Workspace-Id` header.
+    #[arg(long, env = "NOTES_WORKSPACE")]
+    workspace: Option<String>,
+
+    /// Talk to the database directly instead of the API (configured through `.env`).
+    #[arg(long)]
+    offline: bool,
+
+    /// Output format.
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Table)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+/// Available subcommands.
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// List a page of notes.
+    List {
+        /// Page to list, starting at 1.
+        #[arg(long, default_value_t = 1)]
+        page: usize,
+        /// Number of notes per page.
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Show a note.
+    Get {
+        /// ID of the note.
+        id: String,
+    },
+    /// Create a note, opening `$EDITOR` for the content unless `--content` is given.
+    Create {
+        /// Title of the note.
+        #[arg(long)]
+        title: String,
+        /// Content of the note.
+        #[arg(long)]
+        content: Option<String>,
+        /// Category of the note.
+        #[arg(long)]
+        category: Option<String>,
+        /// Publish the note.
+        #[arg(long)]
+        published: bool,
+    },
+    /// Edit a note, opening `$EDITOR` on its content unless `--content` or `--no-editor` is given.
+    Edit {
+        /// ID of the note.
+        id: String,
+        /// New title of the note.
+        #[arg(long)]
+        title: Option<String>,
+        /// New content of the note.
+        #[arg(long)]
+        content: Option<String>,
+        /// New category of the note.
+        #[arg(long)]
+        category: Option<String>,
+        /// Whether the note is published.
+        #[arg(long)]
+        published: Option<bool>,
+        /// Only update the fields given as flags.
+        #[arg(long)]
+        no_editor: bool,
+    },
+    /// Delete a note.
+    Rm {
+        /// ID of the note.
+        id: String,
+    },
+    /// Search notes by title and content.
+    Search {
+        /// Full-text query.
+        query: String,
+    },
+    /// Export every note.
+    Export {
+        /// Export format.
+        #[arg(short, long, value_enum, default_value_t = ExportFormat::Json)]
+        format: ExportFormat,
+        /// File to write to instead of standard output.
+        #[arg(long)]
+        file: Option<PathBuf>,
+    },
+}
+
+#[tokio::main]
+async fn main() {
+    // Load environment variables from a .env file if present
+    dotenv().ok();
+
+    let cli = Cli::parse();
+    if let Err(err) = run(cli).await {
+        eprintln!("error: {}", err);
+        std::process::exit(1);
+    }
+}
+
+/// Executes the parsed command.
+async fn run(cli: Cli) -> Result<()> {
+    let backend = if cli.offline {
+        let db = DB::init().await?;
+        Backend::Database {
+            db: match &cli.workspace {
+                Some(workspace) => db.workspace(workspace).await?,
+                None => db,
+            },
+            user: cli.user,
+        }
+    } else {
+        let mut client = NotesClient::new(cli.url);
+        if let Some(user) = cli.user {
+            client = client.with_user(user);
+        }
+        if let Some(workspace) = cli.workspace {
+            client = client.with_workspace(workspace);
+        }
+        Backend::Http(client)
+    };
+
+    match cli.command {
+        Command::List { page, limit } => {
+            let options = FilterOptions {
+                page: Some(page),
+                limit: Some(limit),
+                q: None,
+                render: None,
+            };
+            output::print_notes(&backend.list(&options).await?, cli.output)?;
+        }
+        Command::Get { id } => {
+            output::print_note(&backend.get(&id).await?, cli.output)?;
+        }
+        Command::Create {
+            title,
+            content,
+            category,
+            published,
+        } => {
+            let content = match content {
+                Some(content) => content,
+                None => editor::edit("")?,
+            };
+            if content.trim().is_empty() {
+                return Err("Aborting, the note content is empty".into());
+            }
+
+            let body = CreateNoteSchema {
+                title,
+                content,
+                category,
+                published: Some(published),
+                publish_at: None,
+            };
+            output::print_note(&backend.create(&body).await?, cli.output)?;
+        }
+        Command::Edit {
+            id,
+            title,
+            content,
+            category,
+            published,
+            no_editor,
+        } => {
+            // Open the editor on the current content unless it is given or not wanted
+            let content = match content {
+                Some(content) => Some(content),
+                None if no_editor => None,
+                None => {
+                    let current = backend.get(&id).await?.content;
+                    let edited = editor::edit(&current)?;
+                    (edited != current).then_some(edited)
+                }
+            };
+
+            let body = UpdateNoteSchema {
+                title,
+                content,
+                category,
+                published,
+                publish_at: None,
+            };
+            output::print_note(&backend.update(&id, &body).await?, cli.output)?;
+        }
+        Command::Rm { id } => {
+            backend.delete(&id).await?;
+            println!("Deleted note {}", id);
+        }
+        Command::Search { query } => {
+            output::print_notes(&backend.all(Some(&query)).await?, cli.output)?;
+        }
+        Command::Export { format, file } => {
+            let exported = output::export(&backend.all(None).await?, format)?;
+            match file {
+                Some(file) => std::fs::write(file, exported)?,
+                None => print!("{}", exported),
+            }
+        }
+    }
+
+    Ok(())
+}
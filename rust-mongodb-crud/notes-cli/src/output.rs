@@ -0,0 +1,123 @@
+use crate::Result;
+use clap::ValueEnum;
+use notes_client::NoteResponse;
+
+/// Format in which notes are printed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable table (single notes are printed with their content).
+    Table,
+    /// Pretty printed JSON.
+    Json,
+}
+
+/// Format in which notes are exported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// JSON array of notes.
+    Json,
+    /// One JSON note per line.
+    Jsonl,
+    /// Markdown document with a section per note.
+    Markdown,
+}
+
+/// Prints a list of notes.
+pub fn print_notes(notes: &[NoteResponse], format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(notes)?),
+        OutputFormat::Table => print!("{}", table(notes)),
+    }
+
+    Ok(())
+}
+
+/// Prints a single note, including its content.
+pub fn print_note(note: &NoteResponse, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(note)?),
+        OutputFormat::Table => {
+            print!("{}", table(std::slice::from_ref(note)));
+            println!();
+            println!("{}", note.content);
+        }
+    }
+
+    Ok(())
+}
+
+/// Renders notes in the requested export format.
+pub fn export(notes: &[NoteResponse], format: ExportFormat) -> Result<String> {
+    let output = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(notes)? + "\n",
+        ExportFormat::Jsonl => {
+            let mut output = String::new();
+            for note in notes {
+                output.push_str(&serde_json::to_string(note)?);
+                output.push('\n');
+            }
+            output
+        }
+        ExportFormat::Markdown => {
+            let mut output = String::new();
+            for note in notes {
+                output.push_str(&format!("# {}\n\n", note.title));
+                output.push_str(&format!(
+                    "- id: {}\n- category: {}\n- published: {}\n- updated: {}\n\n",
+                    note.id,
+                    note.category,
+                    note.published,
+                    note.updatedAt.to_rfc3339()
+                ));
+                output.push_str(note.content.trim_end());
+                output.push_str("\n\n");
+            }
+            output
+        }
+    };
+
+    Ok(output)
+}
+
+/// Renders notes as a table with aligned columns.
+fn table(notes: &[NoteResponse]) -> String {
+    let header = ["ID", "TITLE", "CATEGORY", "PUBLISHED", "UPDATED"];
+    let rows: Vec<[String; 5]> = notes
+        .iter()
+        .map(|note| {
+            [
+                note.id.clone(),
+                note.title.clone(),
+                note.category.clone(),
+                note.published.to_string(),
+                note.updatedAt.format("%Y-%m-%d %H:%M").to_string(),
+            ]
+        })
+        .collect();
+
+    // Size each column to its widest cell
+    let mut widths = header.map(|title| title.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut output = String::new();
+    let mut push_row = |cells: Vec<&str>| {
+        let line: Vec<String> = cells
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+            .collect();
+        output.push_str(line.join("  ").trim_end());
+        output.push('\n');
+    };
+
+    push_row(header.to_vec());
+    for row in &rows {
+        push_row(row.iter().map(String::as_str).collect());
+    }
+
+    output
+}
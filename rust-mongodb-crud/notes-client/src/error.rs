@@ -0,0 +1,60 @@
+use reqwest::StatusCode;
+use thiserror::Error;
+
+/// Errors returned by the notes API client.
+#[derive(Error, Debug)]
+pub enum Error {
+    /// The request could not be sent or the response could not be read.
+    #[error("http error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// The request was malformed, e.g. an invalid note ID or body (`400`).
+    #[error("bad request: {0}")]
+    BadRequest(String),
+
+    /// The note or route does not exist (`404`).
+    #[error("not found: {0}")]
+    NotFound(String),
+
+    /// The note conflicts with an existing one, e.g. a duplicate title (`409`).
+    #[error("conflict: {0}")]
+    Conflict(String),
+
+    /// The client exceeded its rate limit (`429`).
+    #[error("rate limited: {message}")]
+    RateLimited {
+        /// Message returned by the API.
+        message: String,
+        /// Seconds to wait before retrying, from the `Retry-After` header.
+        retry_after: Option<u64>,
+    },
+
+    /// Any other error response returned by the API.
+    #[error("api error ({status}): {message}")]
+    Api {
+        /// HTTP status of the response.
+        status: StatusCode,
+        /// Message returned by the API.
+        message: String,
+    },
+}
+
+impl Error {
+    /// Maps an error response of the API to the matching error variant.
+    pub(crate) fn from_response(
+        status: StatusCode,
+        message: String,
+        retry_after: Option<u64>,
+    ) -> Self {
+        match status {
+            StatusCode::BAD_REQUEST => Error::BadRequest(message),
+            StatusCode::NOT_FOUND => Error::NotFound(message),
+            StatusCode::CONFLICT => Error::Conflict(message),
+            StatusCode::TOO_MANY_REQUESTS => Error::RateLimited {
+                message,
+                retry_after,
+            },
+            status => Error::Api { status, message },
+        }
+    }
+}
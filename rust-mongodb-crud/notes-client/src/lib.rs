@@ -0,0 +1,306 @@
+//! Typed async client for the notes API served by `rust-mongodb-crud`.
+//!
+//! The request and response types are shared with the server crate, so they never drift apart.
+//!
+//! ```no_run
+//! use futures::TryStreamExt;
+//! use notes_client::{CreateNoteSchema, NotesClient};
+//!
+//! # async fn example() -> notes_client::Result<()> {
+//! let client = NotesClient::new("http://localhost:8080").with_user("user-1");
+//!
+//! let note = client
+//!     .create_note(&CreateNoteSchema {
+//!         title: "TODO".to_string(),
+//!         content: "Write the client".to_string(),
+//!         ..Default::default()
+//!     })
+//!     .await?;
+//!
+//! let all: Vec<_> = client.notes(50).try_collect().await?;
+//! client.delete_note(&note.id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+
+pub use error::Error;
+pub use rust_mongodb_crud::response::{
+    GenericResponse, NoteData, NoteListResponse, NoteResponse, SingleNoteResponse,
+};
+pub use rust_mongodb_crud::schema::{
+    CreateNoteSchema, FilterOptions, RenderFormat, UpdateNoteSchema,
+};
+
+use futures::{stream, Stream};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Method, RequestBuilder, Response, StatusCode};
+use rust_mongodb_crud::{USER_HEADER, WORKSPACE_HEADER};
+use serde::de::DeserializeOwned;
+use std::collections::VecDeque;
+
+/// Result type returned by the client.
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Async client for the notes API.
+#[derive(Clone, Debug)]
+pub struct NotesClient {
+    /// Base URL of the API, e.g. `http://localhost:8080`.
+    base_url: String,
+    /// Identifier of the user sent in the `X-User-Id` header, if any.
+    user: Option<String>,
+    /// Identifier of the workspace sent in the `X-Workspace-Id` header, if any.
+    workspace: Option<String>,
+    /// Underlying HTTP client.
+    http: reqwest::Client,
+}
+
+impl NotesClient {
+    /// Creates a client for the API served at `base_url`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Creates a client for the API served at `base_url` using a preconfigured HTTP client.
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        NotesClient {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            user: None,
+            workspace: None,
+            http,
+        }
+    }
+
+    /// Sends every request on behalf of `user` through the `X-User-Id` header.
+    pub fn with_user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Works on the notes of `workspace` through the `X-Workspace-Id` header.
+    pub fn with_workspace(mut self, workspace: impl Into<String>) -> Self {
+        self.workspace = Some(workspace.into());
+        self
+    }
+
+    /// Checks the health of the API (`GET /api/healthchecker`).
+    pub async fn health(&self) -> Result<GenericResponse> {
+        self.send(self.request(Method::GET, "/api/healthchecker"))
+            .await
+    }
+
+    /// Fetches the OpenAPI specification of the API (`GET /api/openapi.json`).
+    pub async fn openapi(&self) -> Result<serde_json::Value> {
+        self.send(self.request(Method::GET, "/api/openapi.json"))
+            .await
+    }
+
+    /// Fetches a single page of notes (`GET /api/notes`).
+    pub async fn list_notes(&self, options: &FilterOptions) -> Result<NoteListResponse> {
+        self.send(self.request(Method::GET, "/api/notes").query(options))
+            .await
+    }
+
+    /// Creates a note (`POST /api/notes`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::Conflict` if the title is already taken within its uniqueness scope.
+    pub async fn create_note(&self, body: &CreateNoteSchema) -> Result<NoteResponse> {
+        let response: SingleNoteResponse = self
+            .send(self.request(Method::POST, "/api/notes").json(body))
+            .await?;
+        Ok(response.data.note)
+    }
+
+    /// Fetches a note by ID (`GET /api/notes/:id`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the note does not exist.
+    pub async fn get_note(&self, id: &str) -> Result<NoteResponse> {
+        let response: SingleNoteResponse = self
+            .send(self.request(Method::GET, &format!("/api/notes/{}", id)))
+            .await?;
+        Ok(response.data.note)
+    }
+
+    /// Updates the fields of a note present in `body` (`PATCH /api/notes/:id`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the note does not exist.
+    pub async fn update_note(&self, id: &str, body: &UpdateNoteSchema) -> Result<NoteResponse> {
+        let response: SingleNoteResponse = self
+            .send(
+                self.request(Method::PATCH, &format!("/api/notes/{}", id))
+                    .json(body),
+            )
+            .await?;
+        Ok(response.data.note)
+    }
+
+    /// Deletes a note (`DELETE /api/notes/:id`).
+    ///
+    /// # Errors
+    ///
+    /// Returns `Error::NotFound` if the note does not exist.
+    pub async fn delete_note(&self, id: &str) -> Result<()> {
+        let response = self
+            .request(Method::DELETE, &format!("/api/notes/{}", id))
+            .send()
+            .await?;
+        Self::check(response).await?;
+        Ok(())
+    }
+
+    /// Returns an iterator over the pages of notes, `limit` notes per page.
+    pub fn pages(&self, limit: usize) -> Pages<'_> {
+        Pages {
+            client: self,
+            limit,
+            query: None,
+            page: 1,
+            done: false,
+        }
+    }
+
+    /// Returns an iterator over the pages of notes matching the full-text `query`.
+    pub fn search_pages(&self, query: impl Into<String>, limit: usize) -> Pages<'_> {
+        Pages {
+            query: Some(query.into()),
+            ..self.pages(limit)
+        }
+    }
+
+    /// Returns a stream of every note, fetched lazily `limit` notes per page.
+    pub fn notes(&self, limit: usize) -> impl Stream<Item = Result<NoteResponse>> + '_ {
+        self.pages(limit).into_stream()
+    }
+
+    /// Returns a stream of every note matching the full-text `query`.
+    pub fn search(
+        &self,
+        query: impl Into<String>,
+        limit: usize,
+    ) -> impl Stream<Item = Result<NoteResponse>> + '_ {
+        self.search_pages(query, limit).into_stream()
+    }
+
+    /// Builds a request to `path` carrying the user and workspace headers.
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut request = self
+            .http
+            .request(method, format!("{}{}", self.base_url, path));
+
+        if let Some(user) = &self.user {
+            request = request.header(USER_HEADER, user);
+        }
+        if let Some(workspace) = &self.workspace {
+            request = request.header(WORKSPACE_HEADER, workspace);
+        }
+
+        request
+    }
+
+    /// Sends a request and deserializes the successful response body.
+    async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T> {
+        let response = Self::check(request.send().await?).await?;
+        Ok(response.json().await?)
+    }
+
+    /// Turns error responses into typed errors.
+    async fn check(response: Response) -> Result<Response> {
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok());
+
+        // Error responses use the `GenericResponse` envelope; fall back to the status text
+        let message = match response.json::<GenericResponse>().await {
+            Ok(body) => body.message,
+            Err(_) => status
+                .canonical_reason()
+                .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR.as_str())
+                .to_string(),
+        };
+
+        Err(Error::from_response(status, message, retry_after))
+    }
+}
+
+/// Iterator over the pages of notes returned by [`NotesClient::pages`].
+#[derive(Debug)]
+pub struct Pages<'a> {
+    /// Client fetching the pages.
+    client: &'a NotesClient,
+    /// Number of notes per page.
+    limit: usize,
+    /// Full-text query the notes must match, if any.
+    query: Option<String>,
+    /// Next page to fetch, starting at 1.
+    page: usize,
+    /// Whether the last page was reached.
+    done: bool,
+}
+
+impl<'a> Pages<'a> {
+    /// Fetches the next page of notes, or returns `None` once every page was fetched.
+    pub async fn next_page(&mut self) -> Option<Result<Vec<NoteResponse>>> {
+        if self.done {
+            return None;
+        }
+
+        let options = FilterOptions {
+            page: Some(self.page),
+            limit: Some(self.limit),
+            q: self.query.clone(),
+            render: None,
+        };
+        let notes = match self.client.list_notes(&options).await {
+            Ok(response) => response.notes,
+            Err(e) => return Some(Err(e)),
+        };
+
+        // A short page is the last one
+        self.page += 1;
+        if notes.len() < self.limit {
+            self.done = true;
+        }
+        if notes.is_empty() {
+            return None;
+        }
+
+        Some(Ok(notes))
+    }
+
+    /// Turns the remaining pages into a stream of individual notes.
+    pub fn into_stream(self) -> impl Stream<Item = Result<NoteResponse>> + 'a {
+        stream::unfold(
+            (self, VecDeque::new()),
+            |(mut pages, mut buffer)| async move {
+                loop {
+                    if let Some(note) = buffer.pop_front() {
+                        return Some((Ok(note), (pages, buffer)));
+                    }
+
+                    match pages.next_page().await? {
+                        Ok(notes) => buffer.extend(notes),
+                        Err(e) => {
+                            // Stop after reporting the error
+                            pages.done = true;
+                            return Some((Err(e), (pages, buffer)));
+                        }
+                    }
+                }
+            },
+        )
+    }
+}
@@ -0,0 +1,148 @@
+use sha2::{Digest, Sha256};
+
+/// Name of the GridFS bucket storing attachments, i.e. the `attachments.files` and
+/// `attachments.chunks` collections.
+pub const ATTACHMENT_BUCKET: &str = "attachments";
+
+/// Collection of the GridFS bucket describing the stored files.
+pub const ATTACHMENT_FILES_COLLECTION: &str = "attachments.files";
+
+/// Collection of the GridFS bucket holding the content of the stored files.
+pub const ATTACHMENT_CHUNKS_COLLECTION: &str = "attachments.chunks";
+
+/// Size of the GridFS chunks attachments are split into, in bytes.
+pub const ATTACHMENT_CHUNK_SIZE: u32 = 255 * 1024;
+
+/// Largest accepted attachment, in bytes.
+pub const MAX_ATTACHMENT_SIZE: u64 = 16 * 1024 * 1024;
+
+/// Content type recorded when the upload does not name one.
+pub const DEFAULT_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// File uploaded to be attached to a note.
+#[derive(Debug, Clone)]
+pub struct NewAttachment {
+    /// Name of the uploaded file.
+    pub filename: String,
+    /// Content type of the file.
+    pub content_type: String,
+    /// Content of the file.
+    pub data: Vec<u8>,
+    /// Hex encoded SHA-256 checksum of the content.
+    pub sha256: String,
+}
+
+impl NewAttachment {
+    /// Prepares an upload, computing the checksum of its content.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rust_mongodb_crud::attachments::NewAttachment;
+    /// let attachment = NewAttachment::new("hello.txt", None, b"hello".to_vec());
+    ///
+    /// assert_eq!(attachment.content_type, "application/octet-stream");
+    /// assert_eq!(
+    ///     attachment.sha256,
+    ///     "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    /// );
+    /// ```
+    pub fn new(filename: &str, content_type: Option<&str>, data: Vec<u8>) -> Self {
+        NewAttachment {
+            filename: filename.to_string(),
+            content_type: content_type.unwrap_or(DEFAULT_CONTENT_TYPE).to_string(),
+            sha256: hex::encode(Sha256::digest(&data)),
+            data,
+        }
+    }
+}
+
+/// Inclusive range of bytes of an attachment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    /// Offset of the first byte.
+    pub start: u64,
+    /// Offset of the last byte.
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Number of bytes in the range.
+    pub fn size(&self) -> u64 {
+        self.end - self.start + 1
+    }
+}
+
+/// Part of an attachment requested by the `Range` header of a download.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// The whole attachment.
+    Full,
+    /// A single range of bytes.
+    Partial(ByteRange),
+    /// A range starting after the end of the attachment.
+    Unsatisfiable,
+}
+
+impl RangeRequest {
+    /// Interprets the `Range` header of a request for an attachment of `size` bytes.
+    ///
+    /// Only single `bytes` ranges are supported. Other ranges, including multiple ranges and
+    /// malformed headers, are ignored and the whole attachment is served, as RFC 9110 allows.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rust_mongodb_crud::attachments::{ByteRange, RangeRequest};
+    /// assert_eq!(
+    ///     RangeRequest::parse(Some("bytes=-10"), 100),
+    ///     RangeRequest::Partial(ByteRange { start: 90, end: 99 })
+    /// );
+    /// assert_eq!(RangeRequest::parse(Some("bytes=100-"), 100), RangeRequest::Unsatisfiable);
+    /// assert_eq!(RangeRequest::parse(None, 100), RangeRequest::Full);
+    /// ```
+    pub fn parse(header: Option<&str>, size: u64) -> Self {
+        let Some(spec) = header.and_then(|header| header.trim().strip_prefix("bytes=")) else {
+            return RangeRequest::Full;
+        };
+        let Some((start, end)) = spec.trim().split_once('-') else {
+            return RangeRequest::Full;
+        };
+        if end.contains(',') {
+            return RangeRequest::Full;
+        }
+
+        let range = match (start.trim(), end.trim()) {
+            // Suffix range: the last `end` bytes
+            ("", suffix) => match suffix.parse::<u64>() {
+                Ok(0) | Err(_) => return RangeRequest::Full,
+                Ok(_) if size == 0 => return RangeRequest::Unsatisfiable,
+                Ok(suffix) => ByteRange {
+                    start: size.saturating_sub(suffix),
+                    end: size - 1,
+                },
+            },
+            (start, end) => {
+                let Ok(start) = start.parse::<u64>() else {
+                    return RangeRequest::Full;
+                };
+                let end = match end {
+                    "" => u64::MAX,
+                    end => match end.parse::<u64>() {
+                        Ok(end) if end >= start => end,
+                        _ => return RangeRequest::Full,
+                    },
+                };
+                if start >= size {
+                    return RangeRequest::Unsatisfiable;
+                }
+                ByteRange {
+                    start,
+                    end: end.min(size - 1),
+                }
+            }
+        };
+
+        RangeRequest::Partial(range)
+    }
+}
@@ -0,0 +1,140 @@
+use crate::errors::Error::{MongoDeserializeBsonError, MongoSerializeBsonError};
+use crate::model::{AuditRecordModel, NoteModel};
+use crate::response::AuditRecordResponse;
+use crate::schema::AuditAction;
+use crate::Result;
+use chrono::{DateTime, Utc};
+use mongodb::bson::{self, oid::ObjectId, Bson, Document};
+
+/// Name of the collection holding the audit log.
+pub const AUDIT_COLLECTION: &str = "audit_log";
+
+/// Note fields left out of audit records: the ID is recorded separately and the update time
+/// changes with every write.
+const UNAUDITED_FIELDS: [&str; 2] = ["_id", "updatedAt"];
+
+/// Generates an identifier for a request that did not bring its own `X-Request-Id`.
+pub fn new_request_id() -> String {
+    ObjectId::new().to_hex()
+}
+
+/// Returns the audited fields of `note` that are set.
+///
+/// # Errors
+///
+/// Returns a `MongoSerializeBsonError` if the note cannot be serialized.
+pub fn snapshot(note: &NoteModel) -> Result<Document> {
+    let document = bson::to_document(note).map_err(MongoSerializeBsonError)?;
+
+    Ok(document
+        .into_iter()
+        .filter(|(key, value)| *value != Bson::Null && !UNAUDITED_FIELDS.contains(&key.as_str()))
+        .collect())
+}
+
+/// Computes the fields that differ between two versions of a note.
+///
+/// A created note has no `before` version and a deleted note no `after` version, in which case
+/// all the fields of the other version are returned. Otherwise only the changed fields are
+/// returned, with `null` standing for a field that is not set.
+///
+/// # Errors
+///
+/// Returns a `MongoSerializeBsonError` if a note cannot be serialized.
+pub fn diff(before: Option<&NoteModel>, after: Option<&NoteModel>) -> Result<(Document, Document)> {
+    let before = before.map(snapshot).transpose()?.unwrap_or_default();
+    let after = after.map(snapshot).transpose()?.unwrap_or_default();
+    if before.is_empty() || after.is_empty() {
+        return Ok((before, after));
+    }
+
+    let mut old = Document::new();
+    let mut new = Document::new();
+    for key in before.keys().chain(after.keys()) {
+        let old_value = before.get(key).cloned().unwrap_or(Bson::Null);
+        let new_value = after.get(key).cloned().unwrap_or(Bson::Null);
+        if old_value != new_value && !old.contains_key(key) {
+            old.insert(key, old_value);
+            new.insert(key, new_value);
+        }
+    }
+
+    Ok((old, new))
+}
+
+/// Builds the audit record of a change to a note.
+///
+/// # Arguments
+///
+/// * `action` - Kind of change.
+/// * `actor` - Identifier of the user who made the change, if known.
+/// * `request_id` - Identifier of the request that made the change.
+/// * `workspace` - Workspace of the note, `None` for the default workspace.
+/// * `before` - The note before the change, `None` if it was created or restored.
+/// * `after` - The note after the change, `None` if it was deleted.
+///
+/// # Errors
+///
+/// Returns a `MongoSerializeBsonError` if a note cannot be serialized.
+///
+/// # Panics
+///
+/// Panics if neither `before` nor `after` is given.
+pub fn record(
+    action: AuditAction,
+    actor: Option<&str>,
+    request_id: Option<&str>,
+    workspace: Option<&str>,
+    before: Option<&NoteModel>,
+    after: Option<&NoteModel>,
+) -> Result<AuditRecordModel> {
+    let note_id = before
+        .or(after)
+        .map(|note| note.id)
+        .expect("an audited change has a before or after version");
+    let (before, after) = diff(before, after)?;
+
+    Ok(AuditRecordModel {
+        id: ObjectId::new(),
+        action,
+        actor: actor.map(str::to_string),
+        noteId: note_id,
+        workspaceId: workspace.map(str::to_string),
+        requestId: request_id.map(str::to_string),
+        before,
+        after,
+        timestamp: Utc::now(),
+    })
+}
+
+/// Rebuilds a deleted note from the audit record of its deletion.
+///
+/// # Arguments
+///
+/// * `record` - Record of a `delete` action, holding every field of the deleted note.
+/// * `now` - Time of the restoration, which becomes the update time of the note.
+///
+/// # Errors
+///
+/// Returns a `MongoDeserializeBsonError` if the recorded fields do not make a valid note.
+pub fn restored_note(record: &AuditRecordModel, now: DateTime<Utc>) -> Result<NoteModel> {
+    let mut document = record.before.clone();
+    document.insert("_id", record.noteId);
+    document.insert("updatedAt", now);
+
+    bson::from_document(document).map_err(MongoDeserializeBsonError)
+}
+
+/// Converts a stored audit record into its API representation.
+pub fn record_response(record: &AuditRecordModel) -> AuditRecordResponse {
+    AuditRecordResponse {
+        id: record.id.to_hex(),
+        action: record.action,
+        actor: record.actor.to_owned(),
+        noteId: record.noteId.to_hex(),
+        requestId: record.requestId.to_owned(),
+        before: Bson::Document(record.before.clone()).into_relaxed_extjson(),
+        after: Bson::Document(record.after.clone()).into_relaxed_extjson(),
+        timestamp: record.timestamp,
+    }
+}
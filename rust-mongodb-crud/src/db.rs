@@ -1,22 +1,83 @@
-use crate::response::{NoteData, NoteListResponse, NoteResponse, SingleNoteResponse};
+use crate::attachments::{
+    ByteRange, NewAttachment, ATTACHMENT_BUCKET, ATTACHMENT_CHUNKS_COLLECTION,
+    ATTACHMENT_CHUNK_SIZE,
+};
+use crate::audit::{self, AUDIT_COLLECTION};
+use crate::migrations::Migrator;
+use crate::model::{Access, AuditRecordModel, ShareLinkModel, TenancyMode, WorkspaceModel};
+use crate::response::{
+    AccessResponse, AttachmentData, AttachmentListResponse, AttachmentResponse, AuditListResponse,
+    NoteData, NoteListResponse, NoteResponse, ShareLinkData, ShareLinkResponse,
+    SingleAttachmentResponse, SingleNoteResponse, SingleShareLinkResponse, SingleWorkspaceResponse,
+    WorkspaceData, WorkspaceListResponse,
+};
+use crate::schema::{AuditAction, AuditQuery, CreateWorkspaceSchema, ShareRole};
+use crate::transactions::{self, Session};
+use crate::workspaces::{self, WORKSPACES_COLLECTION};
 use crate::{
-    errors::Error::*, model::NoteModel, schema::CreateNoteSchema, schema::UpdateNoteSchema, Result,
+    errors::Error::*,
+    errors::{self, Error},
+    model::NoteModel,
+    model::TitleScope,
+    patch::NotePatch,
+    publishing,
+    schema::CreateNoteSchema,
+    schema::UpdateNoteSchema,
+    sharing, store, Result,
 };
 use chrono::prelude::*;
-use futures::StreamExt;
-use mongodb::bson::{doc, oid::ObjectId, Document};
-use mongodb::options::{FindOneAndUpdateOptions, FindOptions, IndexOptions, ReturnDocument};
-use mongodb::{bson, options::ClientOptions, Client, Collection, IndexModel};
+use futures::future::BoxFuture;
+use futures::{StreamExt, TryStreamExt};
+use mongodb::bson::{doc, oid::ObjectId, Bson, Document};
+use mongodb::gridfs::{FilesCollectionDocument, GridFsBucket};
+use mongodb::options::{
+    FindOneAndUpdateOptions, FindOptions, GridFsBucketOptions, GridFsFindOptions,
+    GridFsUploadOptions, IndexOptions, ReturnDocument,
+};
+use mongodb::{bson, options::ClientOptions, Client, Collection, Database, IndexModel};
 use std::str::FromStr;
 
+/// Number of times a patch is re-applied when the note changes while it is being patched.
+const PATCH_ATTEMPTS: usize = 5;
+
 /// Represents a structure to manage different MongoDB collections.
 #[derive(Clone, Debug)]
 pub struct DB {
+    /// Client connected to the deployment, used to start sessions.
+    pub client: Client,
+
+    /// Database holding the note collection and bookkeeping collections.
+    pub database: Database,
+
     /// Collection for handling NoteModel data.
     pub note_collection: Collection<NoteModel>,
 
     /// Collection for handling generic BSON documents.
     pub collection: Collection<Document>,
+
+    /// Scope within which note titles must be unique.
+    pub title_scope: TitleScope,
+
+    /// GridFS bucket storing the files attached to notes.
+    pub attachments: GridFsBucket,
+
+    /// How the notes of different workspaces are kept apart.
+    pub tenancy: TenancyMode,
+
+    /// Workspace the notes are scoped to, `None` for the default workspace.
+    pub workspace: Option<String>,
+
+    /// Collection registering the workspaces.
+    pub workspaces: Collection<WorkspaceModel>,
+
+    /// Collection holding the audit log of the notes of every workspace.
+    pub audit_log: Collection<AuditRecordModel>,
+
+    /// Identifier of the request the database is used for, recorded in the audit log.
+    pub request_id: Option<String>,
+
+    /// Whether the deployment supports multi-document transactions.
+    pub transactions: bool,
 }
 
 impl DB {
@@ -28,8 +89,8 @@ impl DB {
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::DB;
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// let db = DB::init().await?;
     /// # Ok(())
@@ -42,6 +103,12 @@ impl DB {
             std::env::var("MONGO_INITDB_DATABASE").expect("MONGO_INITDB_DATABASE must be set.");
         let mongodb_note_collection: String =
             std::env::var("MONGODB_NOTE_COLLECTION").expect("MONGODB_NOTE_COLLECTION must be set.");
+        let title_scope: TitleScope = std::env::var("NOTE_TITLE_SCOPE")
+            .map(|scope| scope.parse().expect("NOTE_TITLE_SCOPE is invalid."))
+            .unwrap_or_default();
+        let tenancy: TenancyMode = std::env::var("TENANCY_MODE")
+            .map(|mode| mode.parse().expect("TENANCY_MODE is invalid."))
+            .unwrap_or_default();
 
         // Parse MongoDB client options from the URL and set the database name
         let mut client_options = ClientOptions::parse(mongodb_uri).await?;
@@ -55,361 +122,2408 @@ impl DB {
         let note_collection = database.collection(mongodb_note_collection.as_str());
         let collection = database.collection::<Document>(mongodb_note_collection.as_str());
 
+        // Access the GridFS bucket holding attachments
+        let bucket_options = GridFsBucketOptions::builder()
+            .bucket_name(ATTACHMENT_BUCKET.to_string())
+            .chunk_size_bytes(ATTACHMENT_CHUNK_SIZE)
+            .build();
+        let attachments = database.gridfs_bucket(bucket_options);
+
+        // Access the registry of workspaces and the audit log
+        let workspaces = database.collection(WORKSPACES_COLLECTION);
+        let audit_log = database.collection(AUDIT_COLLECTION);
+
         println!("Database connected successfully");
 
+        // Standalone servers apply the steps of an operation one at a time
+        let transactions = transactions::supported(&database).await;
+        if !transactions {
+            println!("Transactions are not supported by this deployment, multi-document operations are not atomic");
+        }
+
         // Return an instance of the DB structure with the obtained collections
         Ok(Self {
+            client,
+            database,
             note_collection,
             collection,
+            title_scope,
+            attachments,
+            tenancy,
+            workspace: None,
+            workspaces,
+            audit_log,
+            request_id: None,
+            transactions,
         })
     }
 
-    /// Fetches a list of notes based on provided pagination parameters.
+    /// Returns a view of the database recording `request_id` in the audit records of the
+    /// changes it makes.
     ///
     /// # Arguments
     ///
-    /// * `limit` - The maximum number of notes to retrieve.
-    /// * `page` - The specific page of notes to retrieve.
+    /// * `request_id` - Identifier of the request, e.g. from the `X-Request-Id` header.
+    pub fn for_request(&self, request_id: &str) -> DB {
+        DB {
+            request_id: Some(request_id.to_string()),
+            ..self.clone()
+        }
+    }
+
+    /// Runs `work` as a unit of work, committing all of its writes or none.
+    ///
+    /// The operations `work` runs through the provided [`Session`] are part of a transaction.
+    /// When the transaction fails with a transient error, such as a write conflict with another
+    /// transaction, `work` is run again, up to 5 times in total. `context` holds the data `work`
+    /// needs, since the returned future may only borrow from its arguments.
+    ///
+    /// On a standalone server without a replica set, the operations are applied as they run and
+    /// `work` is run once.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - Data passed to every run of `work`.
+    /// * `work` - Runs the operations of the unit of work.
     ///
     /// # Errors
     ///
-    /// Returns an error if the query fails or encounters issues during retrieval.
+    /// Returns the error `work` fails with, or an error if the transaction cannot be committed.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::DB;
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, errors::Error::MongoQueryError};
+    /// # use mongodb::bson::doc;
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let db = DB::init().await?;
-    /// let notes = db.fetch_notes(10, 1).await?;
+    /// // Archive the notes of a category and count them in one transaction
+    /// let archived = db
+    ///     .transaction("drafts", |db, category, session| {
+    ///         Box::pin(async move {
+    ///             let filter = doc! {"category": category};
+    ///             let update = doc! {"$set": {"category": "archive"}};
+    ///             let result = session
+    ///                 .update_many(&db.note_collection, filter, update)
+    ///                 .await
+    ///                 .map_err(MongoQueryError)?;
+    ///             Ok(result.modified_count)
+    ///         })
+    ///     })
+    ///     .await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn fetch_notes(&self, limit: i64, page: i64) -> Result<NoteListResponse> {
-        // Define find options based on provided limit and page values
-        let find_options = FindOptions::builder()
-            .limit(limit)
-            .skip(u64::try_from((page - 1) * limit).unwrap())
-            .build();
+    pub async fn transaction<C, R, F>(&self, context: &C, mut work: F) -> Result<R>
+    where
+        C: Sync + ?Sized,
+        F: for<'a> FnMut(&'a DB, &'a C, &'a mut Session) -> BoxFuture<'a, Result<R>>,
+    {
+        let inner = match self.transactions {
+            true => Some(
+                self.client
+                    .start_session(None)
+                    .await
+                    .map_err(MongoQueryError)?,
+            ),
+            false => None,
+        };
+        let mut session = Session::new(inner);
 
-        // Query the note collection and retrieve a cursor
-        let mut cursor = self
-            .note_collection
-            .find(None, find_options)
-            .await
-            .map_err(MongoQueryError)?;
+        transactions::run(&mut session, &(self, context), |(db, context), session| {
+            work(db, context, session)
+        })
+        .await
+    }
 
-        // Initialize an empty vector to store note responses
-        let mut json_result: Vec<NoteResponse> = Vec::new();
+    /// Returns a view of the database restricted to the notes of a workspace.
+    ///
+    /// The workspace is not looked up, use [`DB::workspace`] to resolve the workspace of a
+    /// request. Must be called on the default workspace.
+    ///
+    /// # Arguments
+    ///
+    /// * `workspace` - Identifier of the workspace.
+    pub fn scoped(&self, workspace: &str) -> DB {
+        let mut db = self.clone();
 
-        // Iterate through the cursor and collect note responses
-        while let Some(doc) = cursor.next().await {
-            json_result.push(self.doc_to_note(&doc.unwrap())?);
+        // Switch to the note collection of the workspace
+        if self.tenancy == TenancyMode::Collection {
+            let name = workspaces::collection_name(self.note_collection.name(), workspace);
+            db.note_collection = self.database.collection(&name);
+            db.collection = self.database.collection(&name);
         }
+        db.workspace = Some(workspace.to_string());
 
-        // Create a NoteListResponse based on the collected note responses
-        let json_note_list = NoteListResponse {
-            status: "success".to_string(),
-            results: json_result.len(),
-            notes: json_result,
-        };
+        db
+    }
 
-        Ok(json_note_list)
+    /// Resolves a workspace to a view of its notes, whoever is asking.
+    ///
+    /// Only serves the routes open to non-members, such as published notes and share links.
+    /// Use [`DB::member_workspace`] for the notes of the members.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Identifier of the workspace.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `WorkspaceNotFoundError` if the workspace does not exist, and a
+    /// `WorkspaceArchivedError` if it was archived.
+    pub async fn workspace(&self, id: &str) -> Result<DB> {
+        self.find_workspace(id).await?;
+
+        Ok(self.scoped(id))
     }
 
-    /// Creates a new note based on the provided data.
+    /// Resolves the workspace of a request to a view of its notes, checking that the user is
+    /// one of its members.
     ///
     /// # Arguments
     ///
-    /// * `body` - The data representing the note to be created.
+    /// * `id` - Identifier of the workspace.
+    /// * `user` - The user making the request, if any.
     ///
     /// # Errors
     ///
-    /// Returns an error if the note creation fails due to various reasons like duplicate keys or other errors.
+    /// Returns a `WorkspaceNotFoundError` if the workspace does not exist, a
+    /// `WorkspaceArchivedError` if it was archived, and a `WorkspaceForbiddenError` if the user
+    /// is not a member.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::{DB, CreateNoteSchema};
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let db = DB::init().await?;
-    /// let new_note = CreateNoteSchema {
-    ///     title: "New Note".to_string(),
-    ///     content: "This is a new note!".to_string(),
-    ///     category: Some("General".to_string()),
-    ///     published: None,
-    /// };
-    ///
-    /// let created_note = db.create_note(&new_note).await?;
+    /// let acme = db.member_workspace("acme", Some("user-1")).await?;
+    /// let notes = acme.fetch_notes(10, 1, None, Some("user-1")).await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn create_note(&self, body: &CreateNoteSchema) -> Result<Option<SingleNoteResponse>> {
-        // Retrieve or set default values for 'published' and 'category'
-        let published = body.published.to_owned().unwrap_or(false);
-        let category = body.category.to_owned().unwrap_or("".to_string());
-
-        // Serialize 'body' into BSON document
-        let serialized_data = bson::to_bson(&body).map_err(MongoSerializeBsonError)?;
-        let document = serialized_data.as_document().unwrap();
-
-        // Define options and create an index for 'title' field
-        let options = IndexOptions::builder().unique(true).build();
-        let index = IndexModel::builder()
-            .keys(doc! {"title": 1})
-            .options(options)
-            .build();
-
-        // Create index for 'title' field in the note collection
-        self.note_collection
-            .create_index(index, None)
-            .await
-            .expect("error creating index!");
+    pub async fn member_workspace(&self, id: &str, user: Option<&str>) -> Result<DB> {
+        let workspace = self.find_workspace(id).await?;
+        workspaces::check_member(&workspace, user)?;
 
-        // Prepare document with dates and merge 'document'
-        let datetime = Utc::now();
-        let mut doc_with_dates = doc! {"createdAt": datetime, "updatedAt": datetime, "published": published, "category": category};
-        doc_with_dates.extend(document.clone());
-
-        // Insert the new document into the collection
-        let insert_result = self
-            .collection
-            .insert_one(&doc_with_dates, None)
-            .await
-            .map_err(|e| {
-                if e.to_string()
-                    .contains("E11000 duplicate key error collection")
-                {
-                    return MongoDuplicateError(e);
-                }
-                return MongoQueryError(e);
-            })?;
+        Ok(self.scoped(id))
+    }
 
-        // Retrieve the ID of the inserted document
-        let new_id = insert_result
-            .inserted_id
-            .as_object_id()
-            .expect("issue with new _id");
+    /// Fetches a workspace, failing if it does not exist or was archived.
+    async fn find_workspace(&self, id: &str) -> Result<WorkspaceModel> {
+        workspaces::validate_id(id)?;
 
-        // Find the newly created note by ID
-        let note_doc = self
-            .note_collection
-            .find_one(doc! {"_id":new_id }, None)
+        let workspace = self
+            .workspaces
+            .find_one(doc! {"_id": id}, None)
             .await
             .map_err(MongoQueryError)?;
 
-        // Return None if the note document is not found
-        if note_doc.is_none() {
-            return Ok(None);
+        match workspace {
+            Some(workspace) if workspace.archivedAt.is_some() => {
+                Err(WorkspaceArchivedError(id.to_owned()))
+            }
+            Some(workspace) => Ok(workspace),
+            None => Err(WorkspaceNotFoundError(id.to_owned())),
         }
-
-        // Prepare and return the response containing the newly created note
-        let note_response = SingleNoteResponse {
-            status: "success".to_string(),
-            data: NoteData {
-                note: self.doc_to_note(&note_doc.unwrap()).unwrap(),
-            },
-        };
-
-        Ok(Some(note_response))
     }
 
-    /// Retrieves a note based on the provided ID.
+    /// Creates a workspace.
+    ///
+    /// In collection-per-tenant mode, the note collection of the workspace is given the indexes
+    /// of the default note collection.
     ///
     /// # Arguments
     ///
-    /// * `id` - A string slice representing the ID of the note to be retrieved.
+    /// * `body` - The identifier, name and members of the workspace.
     ///
     /// # Errors
     ///
-    /// Returns an error if the retrieval of the note fails due to an invalid ID or a query error.
+    /// Returns an `InvalidWorkspaceError` if the identifier is malformed, a
+    /// `WorkspaceExistsError` if it is taken, or an error if creating the indexes fails.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::{DB, CreateNoteSchema};
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::CreateWorkspaceSchema};
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let db = DB::init().await?;
-    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to retrieve
+    /// let workspace = CreateWorkspaceSchema {
+    ///     id: "acme".to_string(),
+    ///     name: Some("Acme Corp".to_string()),
+    ///     members: vec!["user-1".to_string()],
+    /// };
     ///
-    /// let retrieved_note = db.get_note(note_id).await?;
+    /// let created = db.create_workspace(&workspace).await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn get_note(&self, id: &str) -> Result<Option<SingleNoteResponse>> {
-        // Parse the string ID into an `ObjectId`
-        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+    pub async fn create_workspace(
+        &self,
+        body: &CreateWorkspaceSchema,
+    ) -> Result<SingleWorkspaceResponse> {
+        workspaces::validate_id(&body.id)?;
 
-        // Find the note document by its ID
-        let note_doc = self
-            .note_collection
-            .find_one(doc! {"_id":oid }, None)
+        let workspace = WorkspaceModel {
+            id: body.id.to_owned(),
+            name: body.name.to_owned().unwrap_or_else(|| body.id.to_owned()),
+            members: body.members.to_owned(),
+            createdAt: Utc::now(),
+            archivedAt: None,
+        };
+        self.workspaces
+            .insert_one(&workspace, None)
             .await
-            .map_err(MongoQueryError)?;
+            .map_err(|e| match self.write_error(e) {
+                MongoDuplicateError(_) => WorkspaceExistsError(body.id.to_owned()),
+                e => e,
+            })?;
 
-        // Return None if the note document is not found
-        if note_doc.is_none() {
-            return Ok(None);
+        // Give a dedicated note collection the same indexes as the default one
+        if self.tenancy == TenancyMode::Collection {
+            self.prepare_workspace(&workspace.id).await?;
         }
 
-        // Prepare and return the response containing the retrieved note
-        let note_response = SingleNoteResponse {
+        Ok(SingleWorkspaceResponse {
             status: "success".to_string(),
-            data: NoteData {
-                note: self.doc_to_note(&note_doc.unwrap()).unwrap(),
+            data: WorkspaceData {
+                workspace: workspaces::workspace_response(&workspace),
             },
-        };
+        })
+    }
 
-        Ok(Some(note_response))
+    /// Lists every workspace, including archived ones.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let workspaces = db.list_workspaces().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_workspaces(&self) -> Result<WorkspaceListResponse> {
+        let workspaces: Vec<WorkspaceModel> = self
+            .workspaces
+            .find(None, FindOptions::builder().sort(doc! {"_id": 1}).build())
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect()
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(WorkspaceListResponse {
+            status: "success".to_string(),
+            results: workspaces.len(),
+            workspaces: workspaces
+                .iter()
+                .map(workspaces::workspace_response)
+                .collect(),
+        })
     }
 
-    /// Edits a note based on the provided ID and update schema.
+    /// Archives a workspace, making its notes inaccessible while keeping them stored.
+    ///
+    /// Archiving an archived workspace leaves it unchanged.
     ///
     /// # Arguments
     ///
-    /// * `id` - A string slice representing the ID of the note to be edited.
-    /// * `body` - An `UpdateNoteSchema` object containing the updated note information.
+    /// * `id` - Identifier of the workspace.
     ///
     /// # Errors
     ///
-    /// Returns an error if editing the note fails due to an invalid ID, serialization error,
-    /// or a query error.
+    /// Returns an error if the update fails.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::{DB, UpdateNoteSchema};
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let db = DB::init().await?;
-    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to edit
-    /// let updated_info = UpdateNoteSchema {
-    ///     title: Some("Updated Title".to_string()),
-    ///     content: Some("Updated Content".to_string()),
-    ///     category: None,
-    ///     published: None,
-    /// };
-    ///
-    /// let edited_note = db.edit_note(note_id, &updated_info).await?;
+    /// let archived = db.archive_workspace("acme").await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn edit_note(
-        &self,
-        id: &str,
-        body: &UpdateNoteSchema,
-    ) -> Result<Option<SingleNoteResponse>> {
-        // Parse the string ID into an `ObjectId`
-        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
-        let query = doc! {
-            "_id": oid,
-        };
-
-        // Prepare options for the find and update operation
-        let find_one_and_update_options = FindOneAndUpdateOptions::builder()
+    pub async fn archive_workspace(&self, id: &str) -> Result<Option<SingleWorkspaceResponse>> {
+        let options = FindOneAndUpdateOptions::builder()
             .return_document(ReturnDocument::After)
             .build();
-
-        // Serialize the update body to BSON document
-        let serialized_data = bson::to_bson(body).map_err(MongoSerializeBsonError)?;
-        let document = serialized_data.as_document().unwrap();
-        let update = doc! {"$set": document};
-
-        // Find and update the note based on the provided ID and update information
-        let note_doc = self
-            .note_collection
-            .find_one_and_update(query, update, find_one_and_update_options)
+        let archived = self
+            .workspaces
+            .find_one_and_update(
+                doc! {"_id": id, "archivedAt": null},
+                doc! {"$set": {"archivedAt": Utc::now()}},
+                options,
+            )
             .await
             .map_err(MongoQueryError)?;
 
-        // Return None if the note document is not found
-        if note_doc.is_none() {
-            return Ok(None);
-        }
+        // Fall back to the current state of workspaces that were archived already
+        let workspace = match archived {
+            Some(workspace) => Some(workspace),
+            None => self
+                .workspaces
+                .find_one(doc! {"_id": id}, None)
+                .await
+                .map_err(MongoQueryError)?,
+        };
 
-        // Prepare and return the response containing the updated note
-        let note_response = SingleNoteResponse {
+        Ok(workspace.map(|workspace| SingleWorkspaceResponse {
             status: "success".to_string(),
-            data: NoteData {
-                note: self.doc_to_note(&note_doc.unwrap()).unwrap(),
+            data: WorkspaceData {
+                workspace: workspaces::workspace_response(&workspace),
             },
-        };
+        }))
+    }
 
-        Ok(Some(note_response))
+    /// Brings the note collections of the active workspaces up to date with the applied
+    /// migrations and the title scope.
+    ///
+    /// Only needed in collection-per-tenant mode, where migrations are recorded once but each
+    /// workspace has its own note collection.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if listing the workspaces or creating indexes fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// db.sync_workspaces().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sync_workspaces(&self) -> Result<()> {
+        if self.tenancy != TenancyMode::Collection {
+            return Ok(());
+        }
+
+        for workspace in self.active_workspaces().await? {
+            self.prepare_workspace(&workspace.id).await?;
+        }
+
+        Ok(())
     }
 
-    /// Deletes a note based on the provided ID.
+    /// Creates the indexes of the note collection of a workspace.
+    async fn prepare_workspace(&self, id: &str) -> Result<()> {
+        let db = self.scoped(id);
+        db.migrator().prepare_collection().await?;
+        db.sync_title_index().await
+    }
+
+    /// Fetches the workspaces that are not archived.
+    async fn active_workspaces(&self) -> Result<Vec<WorkspaceModel>> {
+        self.workspaces
+            .find(doc! {"archivedAt": null}, None)
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect()
+            .await
+            .map_err(MongoQueryError)
+    }
+
+    /// Fetches a list of the notes visible to a user based on provided pagination parameters.
     ///
     /// # Arguments
     ///
-    /// * `id` - A string slice representing the ID of the note to be deleted.
+    /// * `limit` - The maximum number of notes to retrieve.
+    /// * `page` - The specific page of notes to retrieve.
+    /// * `search` - Optional full-text query matched against the title and content.
+    /// * `user` - Identifier of the requesting user, if known.
     ///
     /// # Errors
     ///
-    /// Returns an error if deleting the note fails due to an invalid ID or a query error.
+    /// Returns an error if the query fails or encounters issues during retrieval.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::DB;
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
     /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
     /// # let db = DB::init().await?;
-    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to delete
-    ///
-    /// // Delete the note by ID
-    /// let deletion_result = db.delete_note(note_id).await?;
+    /// let notes = db.fetch_notes(10, 1, Some("groceries"), Some("user-1")).await?;
     /// # Ok(())
     /// # }
     /// ```
-    pub async fn delete_note(&self, id: &str) -> Result<Option<()>> {
-        // Parse the string ID into an `ObjectId`
-        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
-
-        // Delete the note based on the provided ID
-        let result = self
-            .collection
-            .delete_one(doc! {"_id":oid }, None)
-            .await
-            .map_err(MongoQueryError)?;
+    pub async fn fetch_notes(
+        &self,
+        limit: i64,
+        page: i64,
+        search: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<NoteListResponse> {
+        // Only list the notes the user has access to
+        let mut filter = visible_to(user);
 
-        // Return None if the note document is not found
-        if result.deleted_count == 0 {
-            return Ok(None);
+        // Match the search query against the text index on title and content
+        if let Some(search) = search {
+            filter.insert("$text", doc! {"$search": search});
         }
 
-        Ok(Some(()))
+        self.find_notes(filter, limit, page).await
     }
 
-    /// Converts a `NoteModel` into a `NoteResponse`.
+    /// Fetches a list of the notes other users shared with a user.
     ///
     /// # Arguments
     ///
-    /// * `note` - A reference to a `NoteModel` instance.
+    /// * `limit` - The maximum number of notes to retrieve.
+    /// * `page` - The specific page of notes to retrieve.
+    /// * `user` - Identifier of the requesting user.
     ///
     /// # Errors
     ///
-    /// Returns an error if there's an issue extracting or transforming data from the `NoteModel`.
+    /// Returns an error if the query fails.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// # use my_db_handler::{DB, NoteModel};
-    /// # async fn example(db: &DB) -> Result<(), Box<dyn std::error::Error>> {
-    /// # let note = NoteModel::default(); // Placeholder for a NoteModel instance
-    /// let note_response = db.doc_to_note(&note)?;
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let notes = db.shared_notes(10, 1, "user-1").await?;
     /// # Ok(())
     /// # }
     /// ```
-    fn doc_to_note(&self, note: &NoteModel) -> Result<NoteResponse> {
-        let note_response = NoteResponse {
-            id: note.id.to_hex(),
-            title: note.title.to_owned(),
-            content: note.content.to_owned(),
-            category: note.category.to_owned().unwrap_or_default(),
-            published: note.published.unwrap_or_default(),
-            createdAt: note.createdAt,
+    pub async fn shared_notes(
+        &self,
+        limit: i64,
+        page: i64,
+        user: &str,
+    ) -> Result<NoteListResponse> {
+        self.find_notes(doc! {"acl.user": user}, limit, page).await
+    }
+
+    /// Fetches a page of the notes matching `filter`.
+    async fn find_notes(
+        &self,
+        filter: Document,
+        limit: i64,
+        page: i64,
+    ) -> Result<NoteListResponse> {
+        // Define find options based on provided limit and page values
+        let find_options = FindOptions::builder()
+            .limit(limit)
+            .skip(store::page_offset(limit, page)?)
+            .build();
+
+        // Query the notes of the workspace and retrieve a cursor
+        let mut cursor = self
+            .note_collection
+            .find(self.scope(filter), find_options)
+            .await
+            .map_err(MongoQueryError)?;
+
+        // Initialize an empty vector to store note responses
+        let mut json_result: Vec<NoteResponse> = Vec::new();
+
+        // Iterate through the cursor and collect note responses
+        while let Some(doc) = cursor.next().await {
+            json_result.push(self.doc_to_note(&doc.unwrap())?);
+        }
+
+        // Create a NoteListResponse based on the collected note responses
+        let json_note_list = NoteListResponse {
+            status: "success".to_string(),
+            results: json_result.len(),
+            notes: json_result,
+        };
+
+        Ok(json_note_list)
+    }
+
+    /// Returns a `Migrator` managing the schema migrations of this database.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let applied = db.migrator().migrate().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn migrator(&self) -> Migrator {
+        Migrator::new(&self.database, self.note_collection.name())
+    }
+
+    /// Ensures the unique title index matches the configured `title_scope`.
+    ///
+    /// Creates the compound unique index for the configured scope and drops the indexes of
+    /// every other scope, so switching `NOTE_TITLE_SCOPE` only requires a restart.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if listing, creating or dropping indexes fails, for example when
+    /// existing notes already violate the newly configured scope.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// db.sync_title_index().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn sync_title_index(&self) -> Result<()> {
+        // Titles only need to be unique within a workspace
+        let mut keys = match self.tenancy {
+            TenancyMode::Field => doc! {"workspaceId": 1},
+            TenancyMode::Collection => Document::new(),
+        };
+        keys.extend(match self.title_scope {
+            TitleScope::Global => doc! {"title": 1},
+            TitleScope::Owner => doc! {"owner": 1, "title": 1},
+            TitleScope::Category => doc! {"category": 1, "title": 1},
+        });
+
+        // Listing fails when the collection does not exist yet
+        let indexes: Vec<IndexModel> = match self.collection.list_indexes(None).await {
+            Ok(cursor) => cursor.try_collect().await.map_err(MongoQueryError)?,
+            Err(_) => Vec::new(),
+        };
+
+        // Drop the unique indexes belonging to other scopes or built from other keys
+        for index in indexes {
+            let Some(name) = index.options.as_ref().and_then(|o| o.name.clone()) else {
+                continue;
+            };
+            let is_title_index = TitleScope::ALL
+                .iter()
+                .any(|scope| scope.index_name() == name);
+            if is_title_index && (name != self.title_scope.index_name() || index.keys != keys) {
+                self.collection
+                    .drop_index(name, None)
+                    .await
+                    .map_err(MongoQueryError)?;
+            }
+        }
+
+        // Create the unique index of the configured scope
+        let options = IndexOptions::builder()
+            .name(self.title_scope.index_name().to_string())
+            .unique(true)
+            .build();
+        let index = IndexModel::builder().keys(keys).options(options).build();
+
+        self.collection
+            .create_index(index, None)
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(())
+    }
+
+    /// Creates a new note based on the provided data.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The data representing the note to be created.
+    /// * `owner` - Identifier of the user creating the note, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the note creation fails due to various reasons like duplicate keys or other errors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::CreateNoteSchema};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let new_note = CreateNoteSchema {
+    ///     title: "New Note".to_string(),
+    ///     content: "This is a new note!".to_string(),
+    ///     category: Some("General".to_string()),
+    ///     published: None,
+    ///     publish_at: None,
+    /// };
+    ///
+    /// let created_note = db.create_note(&new_note, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_note(
+        &self,
+        body: &CreateNoteSchema,
+        owner: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        // Retrieve or set default values for 'published' and 'category'
+        let published = body.published.to_owned().unwrap_or(false);
+        let category = body.category.to_owned().unwrap_or("".to_string());
+
+        // Serialize 'body' into BSON document
+        let serialized_data = bson::to_bson(&body).map_err(MongoSerializeBsonError)?;
+        let document = serialized_data.as_document().unwrap();
+
+        // Prepare document with dates and merge 'document'
+        let datetime = Utc::now();
+        let mut doc_with_dates = doc! {"createdAt": datetime, "updatedAt": datetime, "published": published, "category": category};
+        doc_with_dates.extend(document.clone());
+        if let Some(owner) = owner {
+            doc_with_dates.insert("owner", owner);
+        }
+        if let (TenancyMode::Field, Some(workspace)) = (self.tenancy, &self.workspace) {
+            doc_with_dates.insert("workspaceId", workspace);
+        }
+
+        // Store the schedule as a date rather than a string, and derive the public slug
+        if let Some(publish_at) = body.publish_at {
+            doc_with_dates.insert("publishAt", publish_at);
+        }
+
+        // A note created concurrently may take the slug first, the next one is tried then
+        let mut attempt = 1;
+        let note_doc = loop {
+            doc_with_dates.insert("slug", self.next_slug(&body.title).await?);
+
+            // Insert the note and record its creation in one transaction
+            let result = self
+                .transaction(
+                    &(doc_with_dates.clone(), owner),
+                    |db, (document, owner), session| {
+                        Box::pin(async move {
+                            // Insert the new document into the collection
+                            let insert_result = session
+                                .insert_one(&db.collection, document)
+                                .await
+                                .map_err(|e| db.write_error(e))?;
+
+                            // Retrieve the ID of the inserted document
+                            let new_id = insert_result
+                                .inserted_id
+                                .as_object_id()
+                                .expect("issue with new _id");
+
+                            // Find the newly created note by ID
+                            let note = session
+                                .find_one(&db.note_collection, doc! {"_id": new_id})
+                                .await
+                                .map_err(MongoQueryError)?;
+                            if let Some(note) = &note {
+                                db.audit(session, AuditAction::Create, *owner, None, Some(note))
+                                    .await?;
+                            }
+
+                            Ok(note)
+                        })
+                    },
+                )
+                .await;
+
+            match result {
+                Err(e)
+                    if publishing::is_slug_conflict(&e) && attempt < publishing::SLUG_ATTEMPTS =>
+                {
+                    attempt += 1
+                }
+                result => break result?,
+            }
+        };
+
+        // Return None if the note document is not found
+        let Some(note) = note_doc else {
+            return Ok(None);
+        };
+
+        // Prepare and return the response containing the newly created note
+        let note_response = SingleNoteResponse {
+            status: "success".to_string(),
+            data: NoteData {
+                note: self.doc_to_note(&note).unwrap(),
+            },
+        };
+
+        Ok(Some(note_response))
+    }
+
+    /// Retrieves a note based on the provided ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note to be retrieved.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the retrieval of the note fails due to an invalid ID or a query error.
+    /// Notes the user has no access to are reported as missing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::CreateNoteSchema};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to retrieve
+    ///
+    /// let retrieved_note = db.get_note(note_id, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_note(
+        &self,
+        id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        // Parse the string ID into an `ObjectId`
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+
+        // Find the note document by its ID, if the user may read it
+        let note_doc = self.authorize(oid, user, Access::Read).await?;
+
+        // Return None if the note document is not found
+        if note_doc.is_none() {
+            return Ok(None);
+        }
+
+        // Prepare and return the response containing the retrieved note
+        let note_response = SingleNoteResponse {
+            status: "success".to_string(),
+            data: NoteData {
+                note: self.doc_to_note(&note_doc.unwrap()).unwrap(),
+            },
+        };
+
+        Ok(Some(note_response))
+    }
+
+    /// Edits a note based on the provided ID and update schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note to be edited.
+    /// * `body` - An `UpdateNoteSchema` object containing the updated note information.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if editing the note fails due to an invalid ID, serialization error,
+    /// a duplicate title, missing write access, or a query error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::UpdateNoteSchema};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to edit
+    /// let updated_info = UpdateNoteSchema {
+    ///     title: Some("Updated Title".to_string()),
+    ///     content: Some("Updated Content".to_string()),
+    ///     category: None,
+    ///     published: None,
+    ///     publish_at: None,
+    /// };
+    ///
+    /// let edited_note = db.edit_note(note_id, &updated_info, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn edit_note(
+        &self,
+        id: &str,
+        body: &UpdateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        // Parse the string ID into an `ObjectId`
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+
+        // Serialize the update body to BSON document
+        let serialized_data = bson::to_bson(body).map_err(MongoSerializeBsonError)?;
+        let mut document = serialized_data.as_document().unwrap().clone();
+        if let Some(publish_at) = body.publish_at {
+            document.insert("publishAt", publish_at);
+        }
+
+        // Update the note and record the change in one transaction
+        let note_doc = self
+            .transaction(
+                &(oid, document, user),
+                |db, (oid, document, user), session| {
+                    Box::pin(async move {
+                        // Make sure the user may write the note
+                        let Some(before) = db
+                            .authorize_with(session, *oid, *user, Access::Write)
+                            .await?
+                        else {
+                            return Ok(None);
+                        };
+
+                        // Bump the update time, which also invalidates the rendered HTML of the note
+                        let mut document = document.clone();
+                        document.insert("updatedAt", Utc::now());
+                        let update = doc! {"$set": document};
+
+                        // Find and update the note based on the provided ID and update information
+                        let options = FindOneAndUpdateOptions::builder()
+                            .return_document(ReturnDocument::After)
+                            .build();
+                        let note = session
+                            .find_one_and_update(
+                                &db.note_collection,
+                                db.scope(doc! {"_id": *oid}),
+                                update,
+                                options,
+                            )
+                            .await
+                            .map_err(|e| db.write_error(e))?;
+                        if let Some(note) = &note {
+                            db.audit(
+                                session,
+                                AuditAction::Update,
+                                *user,
+                                Some(&before),
+                                Some(note),
+                            )
+                            .await?;
+                        }
+
+                        Ok(note)
+                    })
+                },
+            )
+            .await?;
+
+        // Return None if the note document is not found
+        let Some(note) = note_doc else {
+            return Ok(None);
+        };
+
+        // Prepare and return the response containing the updated note
+        let note_response = SingleNoteResponse {
+            status: "success".to_string(),
+            data: NoteData {
+                note: self.doc_to_note(&note).unwrap(),
+            },
+        };
+
+        Ok(Some(note_response))
+    }
+
+    /// Replaces the editable fields of a note with the provided data.
+    ///
+    /// Unlike `edit_note`, fields missing from `body` are removed from the note.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note to be replaced.
+    /// * `body` - The complete new representation of the note.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if replacing the note fails due to an invalid ID, a duplicate title,
+    /// missing write access, or a query error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::CreateNoteSchema};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to replace
+    /// let replacement = CreateNoteSchema {
+    ///     title: "Replaced Title".to_string(),
+    ///     content: "Replaced Content".to_string(),
+    ///     category: None, // Removes the category
+    ///     published: Some(true),
+    ///     publish_at: None,
+    /// };
+    ///
+    /// let replaced_note = db.replace_note(note_id, &replacement, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn replace_note(
+        &self,
+        id: &str,
+        body: &CreateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        // Parse the string ID into an `ObjectId`
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+
+        // Replace the note and record the change in one transaction
+        let note = self
+            .transaction(&(oid, body, user), |db, (oid, body, user), session| {
+                Box::pin(async move {
+                    // Make sure the user may write the note
+                    let Some(before) = db
+                        .authorize_with(session, *oid, *user, Access::Write)
+                        .await?
+                    else {
+                        return Ok(None);
+                    };
+
+                    let note = db
+                        .write_fields(session, db.scope(doc! {"_id": *oid}), body)
+                        .await?;
+                    if let Some(note) = &note {
+                        db.audit(
+                            session,
+                            AuditAction::Update,
+                            *user,
+                            Some(&before),
+                            Some(note),
+                        )
+                        .await?;
+                    }
+
+                    Ok(note)
+                })
+            })
+            .await?;
+
+        Ok(note.map(|note| self.single_note(&note)))
+    }
+
+    /// Applies a patch to a note.
+    ///
+    /// The note is read, patched in memory and written back only if it has not changed in the
+    /// meantime, so every operation of a patch is applied or none is. The patch is retried
+    /// against the latest version of the note if another write got in between.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note to be patched.
+    /// * `patch` - The patch to apply.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the user may not write the note, the patch cannot
+    /// be applied, the title is a duplicate, the note keeps changing concurrently, or a query
+    /// fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, patch::NotePatch};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to patch
+    /// let patch = NotePatch::Merge(serde_json::json!({"category": null}));
+    ///
+    /// let patched_note = db.patch_note(note_id, &patch, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn patch_note(
+        &self,
+        id: &str,
+        patch: &NotePatch,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user may write the note
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+        if self.authorize(oid, user, Access::Write).await?.is_none() {
+            return Ok(None);
+        }
+
+        for _ in 0..PATCH_ATTEMPTS {
+            // Read the current version of the note
+            let note = match self
+                .note_collection
+                .find_one(self.scope(doc! {"_id": oid}), None)
+                .await
+                .map_err(MongoQueryError)?
+            {
+                Some(note) => note,
+                None => return Ok(None),
+            };
+
+            let body = patch.apply(&note)?;
+
+            // Only write if the note still holds the values the patch was applied to
+            let guard = self.scope(doc! {
+                "_id": oid,
+                "title": &note.title,
+                "content": &note.content,
+                "category": note.category.as_deref().map_or(Bson::Null, Bson::from),
+                "published": note.published.map_or(Bson::Null, Bson::from),
+                "publishAt": note.publishAt.map_or(Bson::Null, Bson::from),
+                "updatedAt": note.updatedAt,
+            });
+            let patched = self
+                .transaction(
+                    &(guard, body, note, user),
+                    |db, (guard, body, note, user), session| {
+                        Box::pin(async move {
+                            let patched = db.write_fields(session, guard.clone(), body).await?;
+                            if let Some(patched) = &patched {
+                                db.audit(
+                                    session,
+                                    AuditAction::Update,
+                                    *user,
+                                    Some(note),
+                                    Some(patched),
+                                )
+                                .await?;
+                            }
+
+                            Ok(patched)
+                        })
+                    },
+                )
+                .await?;
+            if let Some(patched) = patched {
+                return Ok(Some(self.single_note(&patched)));
+            }
+        }
+
+        Err(ConcurrentUpdateError)
+    }
+
+    /// Sets the editable fields of the note matching `filter` to `body`, removing the fields
+    /// that are `None`, and returns the updated note.
+    async fn write_fields(
+        &self,
+        session: &mut Session,
+        filter: Document,
+        body: &CreateNoteSchema,
+    ) -> Result<Option<NoteModel>> {
+        // Split the fields into the ones to set and the ones to remove
+        let mut set =
+            doc! {"title": &body.title, "content": &body.content, "updatedAt": Utc::now()};
+        let mut unset = Document::new();
+        match &body.category {
+            Some(category) => set.insert("category", category),
+            None => unset.insert("category", ""),
+        };
+        match body.published {
+            Some(published) => set.insert("published", published),
+            None => unset.insert("published", ""),
+        };
+        match body.publish_at {
+            Some(publish_at) => set.insert("publishAt", publish_at),
+            None => unset.insert("publishAt", ""),
+        };
+
+        let mut update = doc! {"$set": set};
+        if !unset.is_empty() {
+            update.insert("$unset", unset);
+        }
+
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        session
+            .find_one_and_update(&self.note_collection, filter, update, options)
+            .await
+            .map_err(|e| self.write_error(e))
+    }
+
+    /// Moves every note of a category the user may write to another category.
+    ///
+    /// The notes are moved and their changes recorded in one transaction, so either all of
+    /// them move or none does. Notes the user may only read stay where they are.
+    ///
+    /// On a standalone server the move is not atomic. Titles taken in the target category are
+    /// checked before any note moves, but a note created in the meantime can still make the
+    /// move fail after part of the notes moved.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Category to move the notes out of.
+    /// * `to` - Category to move the notes into.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a moved note would share its title with a note of the target
+    /// category, or a query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let moved = db.move_category("drafts", "archive", Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn move_category(&self, from: &str, to: &str, user: Option<&str>) -> Result<u64> {
+        if from == to {
+            return Ok(0);
+        }
+
+        self.transaction(&(from, to, user), |db, (from, to, user), session| {
+            Box::pin(async move {
+                // Find the notes of the category the user may write
+                let mut filter = db.scope(doc! {"category": *from});
+                filter.extend(writable_by(*user));
+                let notes = session
+                    .find(&db.note_collection, filter, None)
+                    .await
+                    .map_err(MongoQueryError)?;
+                if notes.is_empty() {
+                    return Ok(0);
+                }
+
+                // Without a transaction a failing update leaves part of the notes moved, so
+                // conflicting titles are looked for before anything is written
+                if !session.is_transactional() && db.title_scope == TitleScope::Category {
+                    let titles: Vec<&str> = notes.iter().map(|note| note.title.as_str()).collect();
+                    let filter = db.scope(doc! {"category": *to, "title": {"$in": titles}});
+                    let taken = session
+                        .count_documents(&db.collection, filter)
+                        .await
+                        .map_err(MongoQueryError)?;
+                    if taken > 0 {
+                        return Err(DuplicateTitleError(TitleScope::Category));
+                    }
+                }
+
+                // Move them all at once
+                let now = Utc::now();
+                let ids: Vec<ObjectId> = notes.iter().map(|note| note.id).collect();
+                let update = doc! {"$set": {"category": *to, "updatedAt": now}};
+                session
+                    .update_many(&db.note_collection, doc! {"_id": {"$in": ids}}, update)
+                    .await
+                    .map_err(|e| db.write_error(e))?;
+
+                // Record the move of every note
+                for before in &notes {
+                    let after = NoteModel {
+                        category: Some(to.to_string()),
+                        updatedAt: now,
+                        ..before.clone()
+                    };
+                    db.audit(
+                        session,
+                        AuditAction::Update,
+                        *user,
+                        Some(before),
+                        Some(&after),
+                    )
+                    .await?;
+                }
+
+                Ok(notes.len() as u64)
+            })
+        })
+        .await
+    }
+
+    /// Retrieves a published note by its slug.
+    ///
+    /// # Arguments
+    ///
+    /// * `slug` - A string slice representing the slug of the note.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note = db.get_published_note("release-notes").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_published_note(&self, slug: &str) -> Result<Option<SingleNoteResponse>> {
+        // Unpublished notes are invisible to the public endpoint
+        let note_doc = self
+            .note_collection
+            .find_one(self.scope(doc! {"slug": slug, "published": true}), None)
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(note_doc.map(|note| SingleNoteResponse {
+            status: "success".to_string(),
+            data: NoteData {
+                note: self.doc_to_note(&note).unwrap(),
+            },
+        }))
+    }
+
+    /// Publishes every note whose `publishAt` time is at or before `now`.
+    ///
+    /// The schedule is cleared once the note is published, so unpublishing it later is not
+    /// undone by the next run. Returns the number of published notes.
+    ///
+    /// Called on the default workspace, the notes of every active workspace are published.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the update fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let published = db.publish_due_notes(chrono::Utc::now()).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn publish_due_notes(&self, now: DateTime<Utc>) -> Result<u64> {
+        let due = doc! {"publishAt": {"$lte": now}};
+        let active = match self.workspace {
+            Some(_) => Vec::new(),
+            None => self.active_workspaces().await?,
+        };
+
+        // The default workspace publishes the notes of every active workspace sharing its
+        // collection, leaving the notes of archived workspaces alone
+        let mut filter = due.clone();
+        match (&self.workspace, self.tenancy) {
+            (Some(_), _) => filter = self.scope(filter),
+            (None, TenancyMode::Field) => {
+                let mut workspaces = vec![Bson::Null];
+                workspaces.extend(active.iter().map(|workspace| Bson::from(&workspace.id)));
+                filter.insert("workspaceId", doc! {"$in": workspaces});
+            }
+            (None, TenancyMode::Collection) => {}
+        }
+        let update = doc! {
+            "$set": {"published": true, "updatedAt": now},
+            "$unset": {"publishAt": ""},
+        };
+        let result = self
+            .collection
+            .update_many(filter, update.clone(), None)
+            .await
+            .map_err(MongoQueryError)?;
+        let mut published = result.modified_count;
+
+        // Workspaces with their own collection are published one by one
+        if self.tenancy == TenancyMode::Collection {
+            for workspace in active {
+                let result = self
+                    .scoped(&workspace.id)
+                    .collection
+                    .update_many(due.clone(), update.clone(), None)
+                    .await
+                    .map_err(MongoQueryError)?;
+                published += result.modified_count;
+            }
+        }
+
+        Ok(published)
+    }
+
+    /// Assigns a slug to every note created before slugs were introduced.
+    ///
+    /// Returns the number of updated notes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a query or an update fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// db.backfill_slugs().await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn backfill_slugs(&self) -> Result<u64> {
+        let notes: Vec<NoteModel> = self
+            .note_collection
+            .find(doc! {"slug": {"$exists": false}}, None)
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect()
+            .await
+            .map_err(MongoQueryError)?;
+
+        // Slugs are picked one at a time so notes sharing a title get distinct ones
+        let mut updated = 0;
+        for note in notes {
+            let slug = self.next_slug(&note.title).await?;
+            self.collection
+                .update_one(doc! {"_id": note.id}, doc! {"$set": {"slug": slug}}, None)
+                .await
+                .map_err(MongoQueryError)?;
+            updated += 1;
+        }
+
+        Ok(updated)
+    }
+
+    /// Derives a slug from `title` that no other note uses yet.
+    ///
+    /// Slugs are unique across the note collection, even when workspaces share it.
+    async fn next_slug(&self, title: &str) -> Result<String> {
+        let base = publishing::slugify(title);
+
+        // Slugs only contain letters, digits and dashes, so the base needs no escaping
+        let taken: Vec<String> = self
+            .collection
+            .find(
+                doc! {"slug": {"$regex": format!("^{}(-[0-9]+)?$", base)}},
+                FindOptions::builder().projection(doc! {"slug": 1}).build(),
+            )
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect::<Vec<Document>>()
+            .await
+            .map_err(MongoQueryError)?
+            .iter()
+            .filter_map(|note| note.get_str("slug").ok().map(str::to_string))
+            .collect();
+
+        Ok(publishing::unique_slug(&base, &taken))
+    }
+
+    /// Deletes a note based on the provided ID.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note to be deleted.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if deleting the note fails due to an invalid ID, the user not owning
+    /// the note, or a query error.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to delete
+    ///
+    /// // Delete the note by ID
+    /// let deletion_result = db.delete_note(note_id, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn delete_note(&self, id: &str, user: Option<&str>) -> Result<Option<()>> {
+        // Parse the string ID into an `ObjectId`
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+
+        // Delete the note and record it in one transaction
+        let deleted = self
+            .transaction(&(oid, user), |db, (oid, user), session| {
+                Box::pin(async move {
+                    // Make sure the user may delete the note
+                    let Some(before) = db
+                        .authorize_with(session, *oid, *user, Access::Manage)
+                        .await?
+                    else {
+                        return Ok(false);
+                    };
+
+                    // Delete the note based on the provided ID
+                    let result = session
+                        .delete_one(&db.collection, db.scope(doc! {"_id": *oid}))
+                        .await
+                        .map_err(MongoQueryError)?;
+                    if result.deleted_count == 0 {
+                        return Ok(false);
+                    }
+
+                    // Keep every field of the note in the audit log, so it can be restored
+                    db.audit(session, AuditAction::Delete, *user, Some(&before), None)
+                        .await?;
+
+                    Ok(true)
+                })
+            })
+            .await?;
+
+        // Return None if the note document is not found
+        if !deleted {
+            return Ok(None);
+        }
+
+        // Clean up the files attached to the note, which GridFS cannot do in a transaction
+        let files: Vec<FilesCollectionDocument> = self
+            .attachments
+            .find(doc! {"metadata.noteId": oid}, None)
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect()
+            .await
+            .map_err(MongoQueryError)?;
+        for file in files {
+            self.attachments
+                .delete(file.id)
+                .await
+                .map_err(MongoQueryError)?;
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Restores a deleted note from the audit log.
+    ///
+    /// The note is recreated with its ID and the fields it had when it was deleted, except its
+    /// attachments, which are deleted with the note. It gets a new slug if another note took
+    /// its slug in the meantime. Only the owner of a note can restore it.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the deleted note.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the note was not deleted, the user does not own
+    /// the note, its title is now a duplicate, or a query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the deleted note
+    ///
+    /// let restored_note = db.restore_note(note_id, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn restore_note(
+        &self,
+        id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        // Parse the string ID into an `ObjectId`
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+
+        // Recreate the note and record it in one transaction
+        let restored = self
+            .transaction(&(oid, id, user), |db, (oid, id, user), session| {
+                Box::pin(async move {
+                    // Make sure the note is gone
+                    let existing = session
+                        .find_one(&db.note_collection, db.scope(doc! {"_id": *oid}))
+                        .await
+                        .map_err(MongoQueryError)?;
+                    if existing.is_some() {
+                        return Err(RestoreConflictError(id.to_string()));
+                    }
+
+                    // Rebuild the note from its latest deletion in this workspace
+                    let filter = doc! {
+                        "noteId": *oid,
+                        "workspaceId": db.workspace.as_deref().map_or(Bson::Null, Bson::from),
+                        "action": "delete",
+                    };
+                    let options = FindOptions::builder()
+                        .sort(doc! {"timestamp": -1})
+                        .limit(1)
+                        .build();
+                    let records = session
+                        .find(&db.audit_log, filter, options)
+                        .await
+                        .map_err(MongoQueryError)?;
+                    let Some(record) = records.first() else {
+                        return Ok(None);
+                    };
+                    let mut note = audit::restored_note(record, Utc::now())?;
+
+                    // The note keeps the access rules it had when it was deleted
+                    match note.access(*user) {
+                        Some(access) if access >= Access::Manage => {}
+                        Some(_) => return Err(ForbiddenError(Access::Manage)),
+                        None => return Ok(None),
+                    }
+
+                    // Pick a new slug if another note took it in the meantime
+                    if let Some(slug) = &note.slug {
+                        let taken = session
+                            .count_documents(&db.collection, doc! {"slug": slug})
+                            .await
+                            .map_err(MongoQueryError)?;
+                        if taken > 0 {
+                            note.slug = Some(db.next_slug(&note.title).await?);
+                        }
+                    }
+
+                    // Insert the fields that are set, as `create_note` does
+                    let mut document = audit::snapshot(&note)?;
+                    document.insert("_id", note.id);
+                    document.insert("updatedAt", note.updatedAt);
+                    session
+                        .insert_one(&db.collection, document)
+                        .await
+                        .map_err(|e| db.write_error(e))?;
+                    db.audit(session, AuditAction::Restore, *user, None, Some(&note))
+                        .await?;
+
+                    Ok(Some(note))
+                })
+            })
+            .await?;
+
+        let Some(note) = restored else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.single_note(&note)))
+    }
+
+    /// Fetches a page of the audit log of the workspace, most recent records first.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Filters on the note, the actor and the time of the records, and the page.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the note ID is invalid or the query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::AuditQuery};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let query = AuditQuery {
+    ///     actor: Some("user-1".to_string()),
+    ///     ..Default::default()
+    /// };
+    ///
+    /// let records = db.audit_log(&query).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn audit_log(&self, query: &AuditQuery) -> Result<AuditListResponse> {
+        let limit = query.limit.unwrap_or(10) as i64;
+        let page = query.page.unwrap_or(1) as i64;
+
+        // Build the filter from the provided criteria
+        let mut filter = doc! {
+            "workspaceId": self.workspace.as_deref().map_or(Bson::Null, Bson::from),
+        };
+        if let Some(note) = &query.note {
+            let oid = ObjectId::from_str(note).map_err(|_| InvalidIDError(note.to_owned()))?;
+            filter.insert("noteId", oid);
+        }
+        if let Some(actor) = &query.actor {
+            filter.insert("actor", actor);
+        }
+        let mut timestamp = Document::new();
+        if let Some(from) = query.from {
+            timestamp.insert("$gte", from);
+        }
+        if let Some(to) = query.to {
+            timestamp.insert("$lt", to);
+        }
+        if !timestamp.is_empty() {
+            filter.insert("timestamp", timestamp);
+        }
+
+        let options = FindOptions::builder()
+            .sort(doc! {"timestamp": -1})
+            .limit(limit)
+            .skip(store::page_offset(limit, page)?)
+            .build();
+        let records: Vec<AuditRecordModel> = self
+            .audit_log
+            .find(filter, options)
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect()
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(AuditListResponse {
+            status: "success".to_string(),
+            results: records.len(),
+            records: records.iter().map(audit::record_response).collect(),
+        })
+    }
+
+    /// Describes who a note is shared with.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the user does not own the note, the note has no
+    /// owner, or the query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note
+    ///
+    /// let access = db.get_access(note_id, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_access(&self, id: &str, user: Option<&str>) -> Result<Option<AccessResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user owns the note
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+        let note = self.authorize_sharing(oid, user).await?;
+
+        Ok(note.map(|note| sharing::access_response(&note, Utc::now())))
+    }
+
+    /// Shares a note with a user, replacing the role they had if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note.
+    /// * `grantee` - Identifier of the user the note is shared with.
+    /// * `role` - The role granted to `grantee`.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the user does not own the note, the note has no
+    /// owner or `grantee` is its owner, or a query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{db::DB, schema::ShareRole};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to share
+    ///
+    /// let access = db
+    ///     .grant_access(note_id, "user-2", ShareRole::Viewer, Some("user-1"))
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn grant_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        role: ShareRole,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user owns the note
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+        let Some(note) = self.authorize_sharing(oid, user).await? else {
+            return Ok(None);
+        };
+        sharing::check_grantee(&note, grantee)?;
+
+        // Change the role of an existing entry, or add an entry for the user
+        let role = bson::to_bson(&role).map_err(MongoSerializeBsonError)?;
+        let updated = self
+            .collection
+            .update_one(
+                self.scope(doc! {"_id": oid, "acl.user": grantee}),
+                doc! {"$set": {"acl.$.role": &role}},
+                None,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+        if updated.matched_count == 0 {
+            self.collection
+                .update_one(
+                    self.scope(doc! {"_id": oid, "acl.user": {"$ne": grantee}}),
+                    doc! {"$push": {"acl": {"user": grantee, "role": role}}},
+                    None,
+                )
+                .await
+                .map_err(MongoQueryError)?;
+        }
+
+        self.get_access(id, user).await
+    }
+
+    /// Stops sharing a note with a user.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note.
+    /// * `grantee` - Identifier of the user the note is no longer shared with.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the user does not own the note, the note has no
+    /// owner, or a query fails.
+    pub async fn revoke_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user owns the note
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+        if self.authorize_sharing(oid, user).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.collection
+            .update_one(
+                self.scope(doc! {"_id": oid}),
+                doc! {"$pull": {"acl": {"user": grantee}}},
+                None,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        self.get_access(id, user).await
+    }
+
+    /// Creates a link granting read-only access to a note without an account.
+    ///
+    /// Only a hash of the token is stored, so the token is returned this one time. Expired
+    /// links of the note are cleaned up on the way.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note.
+    /// * `expires_at` - Date and time after which the link no longer grants access.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the user does not own the note, the note has no
+    /// owner, or a query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to share
+    /// let expires_at = chrono::Utc::now() + chrono::Duration::days(1);
+    ///
+    /// let link = db.create_share_link(note_id, expires_at, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_share_link(
+        &self,
+        id: &str,
+        expires_at: DateTime<Utc>,
+        user: Option<&str>,
+    ) -> Result<Option<SingleShareLinkResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user owns the note
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+        if self.authorize_sharing(oid, user).await?.is_none() {
+            return Ok(None);
+        }
+
+        // Drop the links that already expired
+        self.collection
+            .update_one(
+                self.scope(doc! {"_id": oid}),
+                doc! {"$pull": {"shareLinks": {"expiresAt": {"$lte": Utc::now()}}}},
+                None,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        let token = sharing::new_token();
+        let link = ShareLinkModel {
+            id: ObjectId::new(),
+            tokenHash: sharing::hash_token(&token),
+            expiresAt: expires_at.into(),
+        };
+        let serialized_link = bson::to_bson(&link).map_err(MongoSerializeBsonError)?;
+        let result = self
+            .collection
+            .update_one(
+                self.scope(doc! {"_id": oid}),
+                doc! {"$push": {"shareLinks": serialized_link}},
+                None,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        // The note was deleted in the meantime
+        if result.matched_count == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(SingleShareLinkResponse {
+            status: "success".to_string(),
+            data: ShareLinkData {
+                link: ShareLinkResponse {
+                    id: link.id.to_hex(),
+                    token: Some(token),
+                    expiresAt: expires_at,
+                },
+            },
+        }))
+    }
+
+    /// Revokes a share link of a note.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - A string slice representing the ID of the note.
+    /// * `link_id` - A string slice representing the ID of the link.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an ID is invalid, the user does not own the note, the note has no
+    /// owner, or a query fails.
+    pub async fn revoke_share_link(
+        &self,
+        id: &str,
+        link_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>> {
+        // Parse the string IDs into `ObjectId`s and make sure the user owns the note
+        let oid = ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))?;
+        let lid = ObjectId::from_str(link_id).map_err(|_| InvalidIDError(link_id.to_owned()))?;
+        if self.authorize_sharing(oid, user).await?.is_none() {
+            return Ok(None);
+        }
+
+        let result = self
+            .collection
+            .update_one(
+                self.scope(doc! {"_id": oid}),
+                doc! {"$pull": {"shareLinks": {"id": lid}}},
+                None,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        // Return None if the link is not found
+        if result.modified_count == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(()))
+    }
+
+    /// Retrieves the note a share link grants access to.
+    ///
+    /// # Arguments
+    ///
+    /// * `token` - The secret token of the link.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails. Unknown and expired links are reported as missing.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note = db.get_shared_note("5f2b...").await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn get_shared_note(&self, token: &str) -> Result<Option<SingleNoteResponse>> {
+        let filter = doc! {
+            "shareLinks": {
+                "$elemMatch": {
+                    "tokenHash": sharing::hash_token(token),
+                    "expiresAt": {"$gt": Utc::now()},
+                },
+            },
+        };
+        let note_doc = self
+            .note_collection
+            .find_one(self.scope(filter), None)
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(note_doc.map(|note| SingleNoteResponse {
+            status: "success".to_string(),
+            data: NoteData {
+                note: self.doc_to_note(&note).unwrap(),
+            },
+        }))
+    }
+
+    /// Attaches a file to a note, storing it in GridFS.
+    ///
+    /// # Arguments
+    ///
+    /// * `note_id` - A string slice representing the ID of the note.
+    /// * `attachment` - The uploaded file.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid, the user may not write the note, or storing the
+    /// file fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::{attachments::NewAttachment, db::DB};
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note to attach the file to
+    /// let file = NewAttachment::new("app.log", Some("text/plain"), b"started".to_vec());
+    ///
+    /// let attachment = db.create_attachment(note_id, file, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn create_attachment(
+        &self,
+        note_id: &str,
+        attachment: NewAttachment,
+        user: Option<&str>,
+    ) -> Result<Option<SingleAttachmentResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user may write the note
+        let oid = ObjectId::from_str(note_id).map_err(|_| InvalidIDError(note_id.to_owned()))?;
+        if self.authorize(oid, user, Access::Write).await?.is_none() {
+            return Ok(None);
+        }
+
+        // Upload the file with the note and its checksum in the metadata
+        let id = ObjectId::new();
+        let options = GridFsUploadOptions::builder()
+            .metadata(doc! {
+                "noteId": oid,
+                "contentType": &attachment.content_type,
+                "sha256": &attachment.sha256,
+            })
+            .build();
+        self.attachments
+            .upload_from_futures_0_3_reader_with_id(
+                id.into(),
+                &attachment.filename,
+                futures::io::Cursor::new(attachment.data),
+                options,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        // Read the stored file back
+        let Some(attachment) = self.find_attachment(doc! {"_id": id}).await? else {
+            return Ok(None);
+        };
+
+        Ok(Some(SingleAttachmentResponse {
+            status: "success".to_string(),
+            data: AttachmentData { attachment },
+        }))
+    }
+
+    /// Lists the files attached to a note, oldest first.
+    ///
+    /// # Arguments
+    ///
+    /// * `note_id` - A string slice representing the ID of the note.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the ID is invalid or the query fails.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// # use rust_mongodb_crud::db::DB;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let db = DB::init().await?;
+    /// let note_id = "6021e59541a3ae69b39ecb46"; // ID of the note
+    ///
+    /// let attachments = db.list_attachments(note_id, Some("user-1")).await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub async fn list_attachments(
+        &self,
+        note_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentListResponse>> {
+        // Parse the string ID into an `ObjectId` and make sure the user may read the note
+        let oid = ObjectId::from_str(note_id).map_err(|_| InvalidIDError(note_id.to_owned()))?;
+        if self.authorize(oid, user, Access::Read).await?.is_none() {
+            return Ok(None);
+        }
+
+        let options = GridFsFindOptions::builder()
+            .sort(doc! {"uploadDate": 1})
+            .build();
+        let files: Vec<FilesCollectionDocument> = self
+            .attachments
+            .find(doc! {"metadata.noteId": oid}, options)
+            .await
+            .map_err(MongoQueryError)?
+            .try_collect()
+            .await
+            .map_err(MongoQueryError)?;
+
+        let attachments = files
+            .iter()
+            .map(|file| self.doc_to_attachment(file))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Some(AttachmentListResponse {
+            status: "success".to_string(),
+            results: attachments.len(),
+            attachments,
+        }))
+    }
+
+    /// Retrieves the description of a file attached to a note.
+    ///
+    /// # Arguments
+    ///
+    /// * `note_id` - A string slice representing the ID of the note.
+    /// * `attachment_id` - A string slice representing the ID of the attachment.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an ID is invalid or the query fails.
+    pub async fn get_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentResponse>> {
+        // Parse the string IDs into `ObjectId`s
+        let oid = ObjectId::from_str(note_id).map_err(|_| InvalidIDError(note_id.to_owned()))?;
+        let aid = ObjectId::from_str(attachment_id)
+            .map_err(|_| InvalidIDError(attachment_id.to_owned()))?;
+
+        // Make sure the user may read the note
+        if self.authorize(oid, user, Access::Read).await?.is_none() {
+            return Ok(None);
+        }
+
+        self.find_attachment(doc! {"_id": aid, "metadata.noteId": oid})
+            .await
+    }
+
+    /// Reads a range of bytes of an attachment from its GridFS chunks.
+    ///
+    /// Only the chunks overlapping the range are fetched.
+    ///
+    /// # Arguments
+    ///
+    /// * `attachment` - The attachment to read, as returned by `get_attachment`.
+    /// * `range` - The bytes to read, within the size of the attachment.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query fails or a chunk is malformed.
+    pub async fn read_attachment(
+        &self,
+        attachment: &AttachmentResponse,
+        range: ByteRange,
+    ) -> Result<Vec<u8>> {
+        let aid = ObjectId::from_str(&attachment.id)
+            .map_err(|_| InvalidIDError(attachment.id.to_owned()))?;
+
+        // Find the chunks holding the first and last byte
+        let chunk_size = ATTACHMENT_CHUNK_SIZE as u64;
+        let first = (range.start / chunk_size) as i64;
+        let last = (range.end / chunk_size) as i64;
+
+        let chunks = self
+            .database
+            .collection::<Document>(ATTACHMENT_CHUNKS_COLLECTION);
+        let options = FindOptions::builder().sort(doc! {"n": 1}).build();
+        let mut cursor = chunks
+            .find(
+                doc! {"files_id": aid, "n": {"$gte": first, "$lte": last}},
+                options,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        // Concatenate the chunks and cut the range out of them
+        let mut data = Vec::with_capacity(((last - first + 1) as u64 * chunk_size) as usize);
+        while let Some(chunk) = cursor.next().await {
+            let chunk = chunk.map_err(MongoQueryError)?;
+            data.extend_from_slice(chunk.get_binary_generic("data")?);
+        }
+
+        let offset = (range.start - first as u64 * chunk_size) as usize;
+        let end = (offset + range.size() as usize).min(data.len());
+
+        Ok(data[offset.min(end)..end].to_vec())
+    }
+
+    /// Deletes a file attached to a note.
+    ///
+    /// # Arguments
+    ///
+    /// * `note_id` - A string slice representing the ID of the note.
+    /// * `attachment_id` - A string slice representing the ID of the attachment.
+    /// * `user` - Identifier of the requesting user, if known.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an ID is invalid, the user may not write the note, or the deletion
+    /// fails.
+    pub async fn delete_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>> {
+        // Make sure the user may write the note
+        let oid = ObjectId::from_str(note_id).map_err(|_| InvalidIDError(note_id.to_owned()))?;
+        if self.authorize(oid, user, Access::Write).await?.is_none() {
+            return Ok(None);
+        }
+
+        // Make sure the file is attached to the note before deleting it
+        let Some(attachment) = self.get_attachment(note_id, attachment_id, user).await? else {
+            return Ok(None);
+        };
+        let aid = ObjectId::from_str(&attachment.id)
+            .map_err(|_| InvalidIDError(attachment.id.to_owned()))?;
+
+        self.attachments
+            .delete(aid.into())
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(Some(()))
+    }
+
+    /// Finds the note with ID `oid` if `user` has at least the `required` access to it.
+    ///
+    /// Returns `None` if the note does not exist or is hidden from the user, and a
+    /// `ForbiddenError` if the user can see the note but lacks the required access.
+    async fn authorize(
+        &self,
+        oid: ObjectId,
+        user: Option<&str>,
+        required: Access,
+    ) -> Result<Option<NoteModel>> {
+        self.authorize_with(&mut Session::detached(), oid, user, required)
+            .await
+    }
+
+    /// Finds the note with ID `oid` if `user` may manage its sharing settings.
+    ///
+    /// Fails like `authorize`, or with an `InvalidShareError` if the note has no owner.
+    async fn authorize_sharing(
+        &self,
+        oid: ObjectId,
+        user: Option<&str>,
+    ) -> Result<Option<NoteModel>> {
+        let note = self.authorize(oid, user, Access::Manage).await?;
+        if let Some(note) = &note {
+            sharing::check_owned(note)?;
+        }
+
+        Ok(note)
+    }
+
+    /// Finds the note with ID `oid` as part of `session` if `user` has at least the `required`
+    /// access to it, as `authorize` does.
+    async fn authorize_with(
+        &self,
+        session: &mut Session,
+        oid: ObjectId,
+        user: Option<&str>,
+        required: Access,
+    ) -> Result<Option<NoteModel>> {
+        let note = session
+            .find_one(&self.note_collection, self.scope(doc! {"_id": oid}))
+            .await
+            .map_err(MongoQueryError)?;
+
+        match note {
+            Some(note) => match note.access(user) {
+                Some(access) if access >= required => Ok(Some(note)),
+                Some(_) => Err(ForbiddenError(required)),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the first attachment matching `filter`.
+    async fn find_attachment(&self, filter: Document) -> Result<Option<AttachmentResponse>> {
+        let mut cursor = self
+            .attachments
+            .find(filter, None)
+            .await
+            .map_err(MongoQueryError)?;
+
+        match cursor.next().await {
+            Some(file) => Ok(Some(
+                self.doc_to_attachment(&file.map_err(MongoQueryError)?)?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Converts a GridFS file document into an `AttachmentResponse`.
+    fn doc_to_attachment(&self, file: &FilesCollectionDocument) -> Result<AttachmentResponse> {
+        let metadata = file.metadata.clone().unwrap_or_default();
+
+        Ok(AttachmentResponse {
+            id: file
+                .id
+                .as_object_id()
+                .map(|id| id.to_hex())
+                .unwrap_or_else(|| file.id.to_string()),
+            noteId: metadata.get_object_id("noteId")?.to_hex(),
+            filename: file.filename.to_owned().unwrap_or_default(),
+            contentType: metadata.get_str("contentType")?.to_string(),
+            size: file.length,
+            sha256: metadata.get_str("sha256")?.to_string(),
+            uploadedAt: file.upload_date.to_chrono(),
+        })
+    }
+
+    /// Restricts `filter` to the notes of the workspace when workspaces share the collection.
+    ///
+    /// Notes of the default workspace have no `workspaceId`, which `null` matches.
+    fn scope(&self, mut filter: Document) -> Document {
+        if self.tenancy == TenancyMode::Field {
+            let workspace = self.workspace.as_deref().map_or(Bson::Null, Bson::from);
+            filter.insert("workspaceId", workspace);
+        }
+
+        filter
+    }
+
+    /// Appends the record of a change made by `actor` to the audit log, as part of `session`.
+    ///
+    /// `before` is `None` for created and restored notes, `after` for deleted ones.
+    async fn audit(
+        &self,
+        session: &mut Session,
+        action: AuditAction,
+        actor: Option<&str>,
+        before: Option<&NoteModel>,
+        after: Option<&NoteModel>,
+    ) -> Result<()> {
+        let record = audit::record(
+            action,
+            actor,
+            self.request_id.as_deref(),
+            self.workspace.as_deref(),
+            before,
+            after,
+        )?;
+        session
+            .insert_one(&self.audit_log, record)
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(())
+    }
+
+    /// Classifies an error returned by a write operation.
+    ///
+    /// Duplicate key errors on the unique title index become a `DuplicateTitleError` naming the
+    /// violated scope, other duplicate key errors a `MongoDuplicateError`, and everything else a
+    /// `MongoQueryError`.
+    fn write_error(&self, e: mongodb::error::Error) -> Error {
+        if !errors::is_duplicate_key(&e) {
+            return MongoQueryError(e);
+        }
+
+        if errors::duplicate_key_index(&e) == Some(self.title_scope.index_name()) {
+            return DuplicateTitleError(self.title_scope);
+        }
+
+        MongoDuplicateError(e)
+    }
+
+    /// Converts a `NoteModel` into a `NoteResponse`.
+    ///
+    /// # Arguments
+    ///
+    /// * `note` - A reference to a `NoteModel` instance.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's an issue extracting or transforming data from the `NoteModel`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,ignore
+    /// # use rust_mongodb_crud::{db::DB, model::NoteModel};
+    /// # async fn example(db: &DB) -> Result<(), Box<dyn std::error::Error>> {
+    /// # let note = NoteModel::default(); // Placeholder for a NoteModel instance
+    /// let note_response = db.doc_to_note(&note)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    fn doc_to_note(&self, note: &NoteModel) -> Result<NoteResponse> {
+        let note_response = NoteResponse {
+            id: note.id.to_hex(),
+            title: note.title.to_owned(),
+            content: note.content.to_owned(),
+            contentHtml: None,
+            category: note.category.to_owned().unwrap_or_default(),
+            owner: note.owner.to_owned(),
+            published: note.published.unwrap_or_default(),
+            publishAt: note.publishAt.map(|publish_at| publish_at.to_chrono()),
+            slug: note.slug.to_owned(),
+            createdAt: note.createdAt,
             updatedAt: note.updatedAt,
         };
 
         Ok(note_response)
     }
+
+    /// Wraps a note in the single note response envelope.
+    fn single_note(&self, note: &NoteModel) -> SingleNoteResponse {
+        SingleNoteResponse {
+            status: "success".to_string(),
+            data: NoteData {
+                note: self.doc_to_note(note).unwrap(),
+            },
+        }
+    }
+}
+
+/// Builds the filter matching the notes `user` may write.
+///
+/// Notes without an owner can be written by everyone, owned notes by their owner and the users
+/// they are shared with as editors.
+fn writable_by(user: Option<&str>) -> Document {
+    match user {
+        Some(user) => doc! {"$or": [
+            {"owner": null},
+            {"owner": user},
+            {"acl": {"$elemMatch": {"user": user, "role": "editor"}}},
+        ]},
+        None => doc! {"owner": null},
+    }
+}
+
+/// Builds the filter matching the notes `user` has access to.
+///
+/// Notes without an owner are visible to everyone, owned notes to their owner and the users
+/// they are shared with.
+fn visible_to(user: Option<&str>) -> Document {
+    match user {
+        Some(user) => doc! {"$or": [{"owner": null}, {"owner": user}, {"acl.user": user}]},
+        None => doc! {"owner": null},
+    }
 }
@@ -1,11 +1,16 @@
 use mongodb::bson;
+use mongodb::error::{ErrorKind, WriteFailure};
 use std::convert::Infallible;
 use thiserror::Error;
 use warp::{http::StatusCode, reply, Rejection, Reply};
 
+use crate::model::{Access, TitleScope};
+use crate::rate_limit::RateLimitInfo;
 use crate::response::GenericResponse;
+use crate::transactions;
 
 /// Custom error types for the application.
+#[allow(clippy::enum_variant_names)]
 #[derive(Error, Debug)]
 pub enum Error {
     /// MongoDB error.
@@ -20,10 +25,18 @@ pub enum Error {
     #[error("duplicate key error occurred: {0}")]
     MongoDuplicateError(mongodb::error::Error),
 
+    /// Note title violates the configured uniqueness scope.
+    #[error("duplicate title within {0} scope")]
+    DuplicateTitleError(TitleScope),
+
     /// Serialization error while working with BSON.
     #[error("could not serialize data: {0}")]
     MongoSerializeBsonError(bson::ser::Error),
 
+    /// Deserialization error while working with BSON.
+    #[error("could not deserialize data: {0}")]
+    MongoDeserializeBsonError(bson::de::Error),
+
     /// Error accessing a field in the document.
     #[error("could not access field in document: {0}")]
     MongoDataError(#[from] bson::document::ValueAccessError),
@@ -31,10 +44,140 @@ pub enum Error {
     /// Invalid ID used.
     #[error("invalid id used: {0}")]
     InvalidIDError(String),
+
+    /// Error while applying or rolling back schema migrations.
+    #[error("migration error: {0}")]
+    MigrationError(String),
+
+    /// Client exceeded the rate limit of a route group.
+    #[error("rate limit exceeded, retry in {} seconds", .0.retry_after.unwrap_or_default())]
+    RateLimitError(RateLimitInfo),
+
+    /// Patch document could not be parsed.
+    #[error("invalid patch document: {0}")]
+    PatchParseError(String),
+
+    /// Patch could not be applied to the note.
+    #[error("could not apply patch: {0}")]
+    InvalidPatchError(String),
+
+    /// Attachment upload form is malformed.
+    #[error("invalid upload: {0}")]
+    InvalidUploadError(String),
+
+    /// Note kept changing while a patch was being applied.
+    #[error("note was modified concurrently")]
+    ConcurrentUpdateError,
+
+    /// User lacks the access to the note an operation requires.
+    #[error("{0} access to the note is required")]
+    ForbiddenError(Access),
+
+    /// Sharing request is invalid.
+    #[error("invalid sharing request: {0}")]
+    InvalidShareError(String),
+
+    /// Workspace identifier is malformed or ambiguous.
+    #[error("invalid workspace: {0}")]
+    InvalidWorkspaceError(String),
+
+    /// Workspace does not exist.
+    #[error("workspace not found: {0}")]
+    WorkspaceNotFoundError(String),
+
+    /// Workspace is archived and its notes are no longer accessible.
+    #[error("workspace is archived: {0}")]
+    WorkspaceArchivedError(String),
+
+    /// Workspace with the same identifier already exists.
+    #[error("workspace already exists: {0}")]
+    WorkspaceExistsError(String),
+
+    /// User is not a member of the workspace.
+    #[error("not a member of the workspace: {0}")]
+    WorkspaceForbiddenError(String),
+
+    /// Operation is reserved to administrators.
+    #[error("administrator access is required")]
+    AdminRequiredError,
+
+    /// Note to restore was not deleted.
+    #[error("note is not deleted: {0}")]
+    RestoreConflictError(String),
+
+    /// Query parameters are out of range.
+    #[error("invalid query: {0}")]
+    InvalidQueryError(String),
+}
+
+impl Error {
+    /// Returns whether the error aborted a transaction that may succeed if run again.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::MongoError(e) | Error::MongoQueryError(e) | Error::MongoDuplicateError(e) => {
+                transactions::is_transient(e)
+            }
+            _ => false,
+        }
+    }
 }
 
 impl warp::reject::Reject for Error {}
 
+/// Server error code of writes violating a unique index.
+pub const DUPLICATE_KEY: i32 = 11000;
+
+/// Returns whether `e` reports a write violating a unique index.
+pub fn is_duplicate_key(e: &mongodb::error::Error) -> bool {
+    duplicate_key_message(e).is_some()
+}
+
+/// Returns the name of the unique index a write violated, if `e` is a duplicate key error.
+///
+/// The driver keeps the code and the message of write errors but not their `keyPattern`, so the
+/// index is read from the `index: <name>` part of the message.
+///
+/// # Examples
+///
+/// ```rust
+/// # use mongodb::bson::{doc, from_document};
+/// # use mongodb::error::{Error, ErrorKind, WriteError, WriteFailure};
+/// # use rust_mongodb_crud::errors::duplicate_key_index;
+/// let write_error: WriteError = from_document(doc! {
+///     "code": 11000,
+///     "errmsg": "E11000 duplicate key error collection: app.notes index: slug_unique dup key: { slug: \"plan\" }",
+/// })
+/// .unwrap();
+/// let e = Error::from(ErrorKind::Write(WriteFailure::WriteError(write_error)));
+///
+/// assert_eq!(duplicate_key_index(&e), Some("slug_unique"));
+/// ```
+pub fn duplicate_key_index(e: &mongodb::error::Error) -> Option<&str> {
+    duplicate_key_message(e)?
+        .split(" index: ")
+        .nth(1)?
+        .split_whitespace()
+        .next()
+}
+
+/// Returns the message of a duplicate key error, reported by a write, a bulk write or a
+/// command such as `findAndModify`.
+fn duplicate_key_message(e: &mongodb::error::Error) -> Option<&str> {
+    match e.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) if e.code == DUPLICATE_KEY => {
+            Some(&e.message)
+        }
+        ErrorKind::BulkWrite(failure) => failure
+            .write_errors
+            .iter()
+            .flatten()
+            .find(|e| e.code == DUPLICATE_KEY)
+            .map(|e| e.message.as_str()),
+        ErrorKind::Command(e) if e.code == DUPLICATE_KEY => Some(&e.message),
+        _ => None,
+    }
+}
+
 /// Handles rejection cases and translates them to appropriate HTTP responses.
 ///
 /// This function translates various rejection types into standardized HTTP responses.
@@ -43,12 +186,16 @@ pub async fn handle_rejection(err: Rejection) -> std::result::Result<Box<dyn Rep
     let code;
     let message;
     let status;
+    let mut rate_limit = None;
 
     if err.is_not_found() {
         status = "failed";
         code = StatusCode::NOT_FOUND;
         message = "Route does not exist on the server";
-    } else if let Some(_) = err.find::<warp::filters::body::BodyDeserializeError>() {
+    } else if err
+        .find::<warp::filters::body::BodyDeserializeError>()
+        .is_some()
+    {
         status = "failed";
         code = StatusCode::BAD_REQUEST;
         message = "Invalid Body";
@@ -66,6 +213,12 @@ pub async fn handle_rejection(err: Rejection) -> std::result::Result<Box<dyn Rep
                 code = StatusCode::CONFLICT;
                 message = "Duplicate key error";
             }
+            Error::DuplicateTitleError(scope) => {
+                eprintln!("Duplicate title within {} scope", scope);
+                status = "fail";
+                code = StatusCode::CONFLICT;
+                message = scope.conflict_message();
+            }
             Error::MongoQueryError(e) => {
                 eprintln!("Error during mongodb query: {:?}", e);
                 status = "fail";
@@ -78,6 +231,12 @@ pub async fn handle_rejection(err: Rejection) -> std::result::Result<Box<dyn Rep
                 code = StatusCode::INTERNAL_SERVER_ERROR;
                 message = "Error serializing BSON";
             }
+            Error::MongoDeserializeBsonError(e) => {
+                eprintln!("Error deserializing BSON: {:?}", e);
+                status = "fail";
+                code = StatusCode::INTERNAL_SERVER_ERROR;
+                message = "Error deserializing BSON";
+            }
             Error::MongoDataError(e) => {
                 eprintln!("validation error: {:?}", e);
                 status = "fail";
@@ -89,6 +248,88 @@ pub async fn handle_rejection(err: Rejection) -> std::result::Result<Box<dyn Rep
                 status = "fail";
                 code = StatusCode::BAD_REQUEST;
                 message = e.as_str();
+            }
+            Error::MigrationError(e) => {
+                eprintln!("Migration error: {:?}", e);
+                status = "fail";
+                code = StatusCode::INTERNAL_SERVER_ERROR;
+                message = "Migration error";
+            }
+            Error::RateLimitError(info) => {
+                status = "fail";
+                code = StatusCode::TOO_MANY_REQUESTS;
+                message = "Too many requests, please try again later";
+                rate_limit = Some(info);
+            }
+            Error::PatchParseError(e) => {
+                status = "fail";
+                code = StatusCode::BAD_REQUEST;
+                message = e.as_str();
+            }
+            Error::InvalidPatchError(e) => {
+                status = "fail";
+                code = StatusCode::UNPROCESSABLE_ENTITY;
+                message = e.as_str();
+            }
+            Error::InvalidUploadError(e) => {
+                status = "fail";
+                code = StatusCode::BAD_REQUEST;
+                message = e.as_str();
+            }
+            Error::ConcurrentUpdateError => {
+                status = "fail";
+                code = StatusCode::CONFLICT;
+                message = "Note was modified concurrently, please retry";
+            }
+            Error::ForbiddenError(access) => {
+                status = "fail";
+                code = StatusCode::FORBIDDEN;
+                message = access.forbidden_message();
+            }
+            Error::InvalidShareError(e) => {
+                status = "fail";
+                code = StatusCode::BAD_REQUEST;
+                message = e.as_str();
+            }
+            Error::InvalidWorkspaceError(e) => {
+                status = "fail";
+                code = StatusCode::BAD_REQUEST;
+                message = e.as_str();
+            }
+            Error::WorkspaceNotFoundError(_) => {
+                status = "fail";
+                code = StatusCode::NOT_FOUND;
+                message = "Workspace not found";
+            }
+            Error::WorkspaceArchivedError(_) => {
+                status = "fail";
+                code = StatusCode::GONE;
+                message = "Workspace is archived";
+            }
+            Error::WorkspaceExistsError(_) => {
+                status = "fail";
+                code = StatusCode::CONFLICT;
+                message = "A workspace with this ID already exists";
+            }
+            Error::WorkspaceForbiddenError(_) => {
+                status = "fail";
+                code = StatusCode::FORBIDDEN;
+                message = "You are not a member of this workspace";
+            }
+            Error::AdminRequiredError => {
+                status = "fail";
+                code = StatusCode::FORBIDDEN;
+                message = "Administrator access is required";
+            }
+            Error::RestoreConflictError(_) => {
+                status = "fail";
+                code = StatusCode::CONFLICT;
+                message = "The note was not deleted or has been restored already";
+            }
+            Error::InvalidQueryError(e) => {
+                status = "fail";
+                code = StatusCode::BAD_REQUEST;
+                message = e.as_str();
             } // _ => {
               //     eprintln!("unhandled application error: {:?}", err);
               //     status = "error";
@@ -96,7 +337,25 @@ pub async fn handle_rejection(err: Rejection) -> std::result::Result<Box<dyn Rep
               //     message = "Internal Server Error";
               // }
         }
-    } else if let Some(_) = err.find::<warp::reject::MethodNotAllowed>() {
+    } else if err.find::<warp::reject::PayloadTooLarge>().is_some() {
+        status = "failed";
+        code = StatusCode::PAYLOAD_TOO_LARGE;
+        message = "Payload Too Large";
+    } else if err.find::<warp::reject::InvalidHeader>().is_some()
+        || err.find::<warp::reject::MissingHeader>().is_some()
+    {
+        status = "failed";
+        code = StatusCode::BAD_REQUEST;
+        message = "Invalid Header";
+    } else if err.find::<warp::reject::InvalidQuery>().is_some() {
+        status = "failed";
+        code = StatusCode::BAD_REQUEST;
+        message = "Invalid Query";
+    } else if err.find::<warp::reject::UnsupportedMediaType>().is_some() {
+        status = "failed";
+        code = StatusCode::UNSUPPORTED_MEDIA_TYPE;
+        message = "Unsupported Media Type";
+    } else if err.find::<warp::reject::MethodNotAllowed>().is_some() {
         status = "failed";
         code = StatusCode::METHOD_NOT_ALLOWED;
         message = "Method Not Allowed";
@@ -112,5 +371,12 @@ pub async fn handle_rejection(err: Rejection) -> std::result::Result<Box<dyn Rep
         message: message.into(),
     });
 
-    Ok(Box::new(reply::with_status(json, code)))
+    let mut response = reply::with_status(json, code).into_response();
+
+    // Tell rate limited clients when they may retry
+    if let Some(info) = rate_limit {
+        info.apply_headers(&mut response);
+    }
+
+    Ok(Box::new(response))
 }
@@ -1,16 +1,51 @@
 use crate::{
-    db::DB,
-    response::GenericResponse,
+    attachments::{ByteRange, NewAttachment, RangeRequest},
+    errors::Error::InvalidUploadError,
+    openapi::ApiDoc,
+    patch::NotePatch,
+    render::MarkdownRenderer,
+    response::{
+        AccessResponse, AttachmentListResponse, AuditListResponse, GenericResponse,
+        MoveCategoryResponse, NoteListResponse, SingleAttachmentResponse, SingleNoteResponse,
+        SinglePublicNoteResponse, SingleShareLinkResponse, SingleWorkspaceResponse,
+        WorkspaceListResponse,
+    },
     schema::UpdateNoteSchema,
-    schema::{CreateNoteSchema, FilterOptions},
+    schema::{
+        AttachmentUploadSchema, AuditQuery, CreateNoteSchema, CreateShareLinkSchema,
+        CreateWorkspaceSchema, FilterOptions, GrantAccessSchema, MoveCategorySchema, RenderFormat,
+        RenderOptions,
+    },
+    sharing,
+    store::Store,
     WebResult,
 };
-use warp::{http::StatusCode, reject, reply::json, reply::with_status, Reply};
+use chrono::Utc;
+use futures::TryStreamExt;
+use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::Config;
+use warp::http::header::{
+    HeaderValue, ACCEPT_RANGES, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE,
+};
+use warp::http::Uri;
+use warp::multipart::FormData;
+use warp::path::{FullPath, Tail};
+use warp::reply::Response;
+use warp::{http::StatusCode, reject, reply::json, reply::with_status, Buf, Reply};
 
 /// Handles the health check endpoint.
 ///
 /// This function responds with a JSON indicating the status of the API.
 /// Returns a `Reply` which is a trait used for generating HTTP responses.
+#[utoipa::path(
+    get,
+    path = "/api/healthchecker",
+    summary = "Check API health",
+    description = "Reports whether the API is up.",
+    tag = "health",
+    responses((status = 200, description = "API is healthy", body = GenericResponse))
+)]
 pub async fn health_checker_handler() -> WebResult<impl Reply> {
     const MESSAGE: &str = "Build CRUD API with Rust and MongoDB";
 
@@ -23,26 +58,96 @@ pub async fn health_checker_handler() -> WebResult<impl Reply> {
     Ok(json(response_json))
 }
 
+/// Serves the OpenAPI specification of the API.
+///
+/// The document is generated from the route annotations and the request and response types.
+pub async fn openapi_handler() -> WebResult<impl Reply> {
+    Ok(json(&ApiDoc::openapi()))
+}
+
+/// Serves the Swagger UI rendering the OpenAPI specification.
+///
+/// The page and its assets are served from the binary under `/api/docs/`; the bare `/api/docs`
+/// path redirects there so the relative asset links resolve.
+///
+/// # Arguments
+///
+/// * `full` - The full path of the request.
+/// * `tail` - The path of the requested file below `/api/docs/`.
+/// * `config` - The Swagger UI configuration.
+pub async fn swagger_ui_handler(
+    full: FullPath,
+    tail: Tail,
+    config: Arc<Config<'static>>,
+) -> WebResult<Response> {
+    if !full.as_str().ends_with('/') && tail.as_str().is_empty() {
+        let location = Uri::from_static("/api/docs/");
+        return Ok(warp::redirect::found(location).into_response());
+    }
+
+    match utoipa_swagger_ui::serve(tail.as_str(), config) {
+        Ok(Some(file)) => {
+            let mut response = Response::new(file.bytes.into_owned().into());
+            let content_type = HeaderValue::from_str(&file.content_type).unwrap();
+            response.headers_mut().insert(CONTENT_TYPE, content_type);
+            Ok(response)
+        }
+        Ok(None) => Err(reject::not_found()),
+        Err(error) => {
+            Ok(with_status(error.to_string(), StatusCode::INTERNAL_SERVER_ERROR).into_response())
+        }
+    }
+}
+
 /// Handles the retrieval of a list of notes based on the provided options.
 ///
 /// # Arguments
 ///
 /// * `opts` - FilterOptions containing parameters like page number and limit.
-/// * `db` - An instance of the DB struct, representing the database connection.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+/// * `renderer` - Renders the note content to HTML when requested.
 ///
 /// # Returns
 ///
 /// Returns a Warp Result containing the JSON representation of the NoteListResponse or a rejection if an error occurs.
-pub async fn notes_list_handler(opts: FilterOptions, db: DB) -> WebResult<impl Reply> {
+#[utoipa::path(
+    get,
+    path = "/api/notes",
+    summary = "List notes",
+    description = "Returns a page of the notes visible to the user, optionally matching a full-text search query. \
+        Notes without an owner are visible to everyone, owned notes to their owner and the users they are shared with.",
+    tag = "notes",
+    params(FilterOptions, ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Page of notes", body = NoteListResponse),
+        (status = 400, description = "Invalid page", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn notes_list_handler(
+    opts: FilterOptions,
+    user: Option<String>,
+    store: Store,
+    renderer: MarkdownRenderer,
+) -> WebResult<impl Reply> {
     // Extract limit and page from FilterOptions or use default values if not provided
     let limit = opts.limit.unwrap_or(10) as i64;
     let page = opts.page.unwrap_or(1) as i64;
 
     // Fetch notes from the database based on provided options
-    let result_json = db
-        .fetch_notes(limit, page)
+    let mut result_json = store
+        .fetch_notes(limit, page, opts.q.as_deref(), user.as_deref())
         .await
-        .map_err(|e| reject::custom(e))?; // Map errors to a custom rejection
+        .map_err(reject::custom)?; // Map errors to a custom rejection
+
+    // Render the content of the notes if requested
+    if opts.render == Some(RenderFormat::Html) {
+        result_json
+            .notes
+            .iter_mut()
+            .for_each(|note| renderer.render_note(note));
+    }
 
     // Return the JSON representation of the fetched notes
     Ok(json(&result_json))
@@ -53,14 +158,37 @@ pub async fn notes_list_handler(opts: FilterOptions, db: DB) -> WebResult<impl R
 /// # Arguments
 ///
 /// * `body` - CreateNoteSchema containing details of the note to be created.
-/// * `db` - An instance of the DB struct, representing the database connection.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
 ///
 /// # Returns
 ///
 /// Returns a Warp Result containing the JSON representation of the created note or a rejection if an error occurs.
-pub async fn create_note_handler(body: CreateNoteSchema, db: DB) -> WebResult<impl Reply> {
-    // Create a new note based on the provided schema
-    let note = db.create_note(&body).await.map_err(|e| reject::custom(e))?;
+#[utoipa::path(
+    post,
+    path = "/api/notes",
+    summary = "Create a note",
+    description = "Creates a note owned by the user named in the `X-User-Id` header.",
+    tag = "notes",
+    request_body = CreateNoteSchema,
+    params(("X-User-Id" = Option<String>, Header, description = "Identifier of the note owner")),
+    responses(
+        (status = 201, description = "Note created", body = SingleNoteResponse),
+        (status = 400, description = "Invalid body", body = GenericResponse),
+        (status = 409, description = "Title already taken within its uniqueness scope", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn create_note_handler(
+    body: CreateNoteSchema,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    // Create a new note owned by the requesting user based on the provided schema
+    let note = store
+        .create_note(&body, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
 
     // Return the JSON representation of the created note with a status code indicating successful creation
     Ok(with_status(json(&note), StatusCode::CREATED))
@@ -71,15 +199,47 @@ pub async fn create_note_handler(body: CreateNoteSchema, db: DB) -> WebResult<im
 /// # Arguments
 ///
 /// * `id` - String representing the ID of the note to retrieve.
-/// * `db` - An instance of the DB struct, representing the database connection.
+/// * `opts` - RenderOptions selecting additional representations of the content.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+/// * `renderer` - Renders the note content to HTML when requested.
 ///
 /// # Returns
 ///
 /// Returns a Warp Result containing the JSON representation of the requested note if found,
 /// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
-pub async fn get_note_handler(id: String, db: DB) -> WebResult<impl Reply> {
+#[utoipa::path(
+    get,
+    path = "/api/notes/{id}",
+    summary = "Get a note",
+    description = "Returns the note with the given ID. With `render=html` the Markdown content is also returned \
+        as sanitized HTML in `contentHtml`.",
+    tag = "notes",
+    params(("id" = String, Path, description = "ObjectId of the note"), RenderOptions, ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Requested note", body = SingleNoteResponse),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn get_note_handler(
+    id: String,
+    opts: RenderOptions,
+    user: Option<String>,
+    store: Store,
+    renderer: MarkdownRenderer,
+) -> WebResult<impl Reply> {
     // Retrieve the note based on the provided ID
-    let note = db.get_note(&id).await.map_err(|e| reject::custom(e))?;
+    let mut note = store
+        .get_note(&id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Render the content of the note if requested
+    if let (Some(note), Some(RenderFormat::Html)) = (&mut note, opts.render) {
+        renderer.render_note(&mut note.data.note);
+    }
 
     // Construct an error response if the note is not found
     let error_response = GenericResponse {
@@ -96,28 +256,172 @@ pub async fn get_note_handler(id: String, db: DB) -> WebResult<impl Reply> {
     Ok(with_status(json(&note), StatusCode::OK))
 }
 
-/// Handles editing an existing note based on the provided ID and request body.
+/// Handles retrieving a published note by its slug, without authentication.
+///
+/// # Arguments
+///
+/// * `slug` - String representing the slug of the note.
+/// * `opts` - Whether to render the content of the note.
+/// * `store` - The note store, e.g. the MongoDB database.
+/// * `renderer` - Renders the note content to HTML when requested.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the note if it is published,
+/// or a JSON response with a 'not found' status otherwise, or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/public/notes/{slug}",
+    summary = "Get a published note",
+    description = "Returns the published note with the given slug. Unpublished notes, including notes \
+        scheduled with `publishAt` whose time has not come yet, are reported as not found.",
+    tag = "public",
+    params(("slug" = String, Path, description = "Slug of the note"), RenderOptions),
+    responses(
+        (status = 200, description = "Requested note", body = SinglePublicNoteResponse),
+        (status = 404, description = "No published note with this slug", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn public_note_handler(
+    slug: String,
+    opts: RenderOptions,
+    store: Store,
+    renderer: MarkdownRenderer,
+) -> WebResult<impl Reply> {
+    // Retrieve the note only if it is published
+    let mut note = store
+        .get_published_note(&slug)
+        .await
+        .map_err(reject::custom)?;
+
+    // Render the content of the note if requested
+    if let (Some(note), Some(RenderFormat::Html)) = (&mut note, opts.render) {
+        renderer.render_note(&mut note.data.note);
+    }
+
+    // Check if the note exists and return the appropriate response
+    let Some(note) = note else {
+        let error_response = GenericResponse {
+            status: "fail".to_string(),
+            message: format!("Note with slug: {} not found", slug),
+        };
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    };
+
+    // Anonymous readers only see the public fields of the note
+    let note = SinglePublicNoteResponse::from(note);
+    Ok(with_status(json(&note), StatusCode::OK))
+}
+
+/// Handles replacing an existing note based on the provided ID and request body.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note to replace.
+/// * `body` - An instance of CreateNoteSchema containing the complete new note.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the replaced note if successful,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    put,
+    path = "/api/notes/{id}",
+    summary = "Replace a note",
+    description = "Replaces the note with the body. Optional fields missing from the body are removed.",
+    tag = "notes",
+    request_body = CreateNoteSchema,
+    params(("id" = String, Path, description = "ObjectId of the note"), ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Replaced note", body = SingleNoteResponse),
+        (status = 400, description = "Invalid ID or body", body = GenericResponse),
+        (status = 403, description = "Note is shared with the user read-only", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 409, description = "Title already taken within its uniqueness scope", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn replace_note_handler(
+    id: String,
+    body: CreateNoteSchema,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    // Replace the note based on the provided ID and request body
+    let note = store
+        .replace_note(&id, &body, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if the note is not found
+    let error_response = GenericResponse {
+        status: "fail".to_string(),
+        message: format!("Note with ID: {} not found", id),
+    };
+
+    // Check if the note exists and return the appropriate response
+    if note.is_none() {
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    // Return the JSON representation of the replaced note with a success status
+    Ok(with_status(json(&note), StatusCode::OK))
+}
+
+/// Handles editing an existing note based on the provided ID and patch.
 ///
 /// # Arguments
 ///
 /// * `id` - String representing the ID of the note to edit.
-/// * `body` - An instance of UpdateNoteSchema containing the updated note details.
-/// * `db` - An instance of the DB struct, representing the database connection.
+/// * `patch` - The change to apply, parsed according to the `Content-Type` of the request.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
 ///
 /// # Returns
 ///
 /// Returns a Warp Result containing the JSON representation of the edited note if successful,
 /// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    patch,
+    path = "/api/notes/{id}",
+    summary = "Update a note",
+    description = "Updates a note. With `application/json` the fields present in the body are set and the others \
+        left unchanged. With `application/merge-patch+json` (RFC 7396) `null` removes a field. With \
+        `application/json-patch+json` (RFC 6902) the `test`, `add`, `remove` and `replace` operations are applied \
+        all or nothing.",
+    tag = "notes",
+    request_body(content(
+        (UpdateNoteSchema = "application/json"),
+        (Object = "application/merge-patch+json"),
+        (Vec<Object> = "application/json-patch+json"),
+    )),
+    params(("id" = String, Path, description = "ObjectId of the note"), ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Updated note", body = SingleNoteResponse),
+        (status = 400, description = "Invalid ID or body", body = GenericResponse),
+        (status = 403, description = "Note is shared with the user read-only", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 409, description = "Title already taken within its uniqueness scope, or the note changed while it was patched", body = GenericResponse),
+        (status = 415, description = "Unsupported content type", body = GenericResponse),
+        (status = 422, description = "Patch cannot be applied, e.g. a `test` operation failed", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
 pub async fn edit_note_handler(
     id: String,
-    body: UpdateNoteSchema,
-    db: DB,
+    patch: NotePatch,
+    user: Option<String>,
+    store: Store,
 ) -> WebResult<impl Reply> {
-    // Edit the note based on the provided ID and request body
-    let note = db
-        .edit_note(&id, &body)
-        .await
-        .map_err(|e| reject::custom(e))?;
+    // Edit the note based on the provided ID and patch
+    let note = match &patch {
+        NotePatch::Fields(body) => store.edit_note(&id, body, user.as_deref()).await,
+        patch => store.patch_note(&id, patch, user.as_deref()).await,
+    }
+    .map_err(reject::custom)?;
 
     // Construct an error response if the note is not found
     let error_response = GenericResponse {
@@ -139,15 +443,38 @@ pub async fn edit_note_handler(
 /// # Arguments
 ///
 /// * `id` - String representing the ID of the note to delete.
-/// * `db` - An instance of the DB struct, representing the database connection.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
 ///
 /// # Returns
 ///
 /// Returns a Warp Result containing a 'no content' response if the note is successfully deleted,
 /// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
-pub async fn delete_note_handler(id: String, db: DB) -> WebResult<impl Reply> {
+#[utoipa::path(
+    delete,
+    path = "/api/notes/{id}",
+    summary = "Delete a note",
+    description = "Deletes the note with the given ID. Only the owner of a note can delete it.",
+    tag = "notes",
+    params(("id" = String, Path, description = "ObjectId of the note"), ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 204, description = "Note deleted"),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn delete_note_handler(
+    id: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
     // Delete the note based on the provided ID
-    let result = db.delete_note(&id).await.map_err(|e| reject::custom(e))?;
+    let result = store
+        .delete_note(&id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
 
     // Construct an error response if the note is not found
     let error_response = GenericResponse {
@@ -163,3 +490,970 @@ pub async fn delete_note_handler(id: String, db: DB) -> WebResult<impl Reply> {
     // Return a 'no content' response indicating successful deletion
     Ok(with_status(json(&""), StatusCode::NO_CONTENT))
 }
+
+/// Handles moving every note of a category to another category.
+///
+/// # Arguments
+///
+/// * `body` - The categories to move the notes between.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the number of moved notes, or a rejection if an error
+/// occurs.
+#[utoipa::path(
+    post,
+    path = "/api/notes/move-category",
+    summary = "Move notes between categories",
+    description = "Moves every note of a category the user may write to another category. All the notes \
+        are moved or none is.",
+    tag = "notes",
+    request_body = MoveCategorySchema,
+    params(("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Notes moved", body = MoveCategoryResponse),
+        (status = 400, description = "Invalid body", body = GenericResponse),
+        (status = 409, description = "A moved note has the same title as a note of the target category", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn move_category_handler(
+    body: MoveCategorySchema,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let moved = store
+        .move_category(&body.from, &body.to, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    Ok(json(&MoveCategoryResponse {
+        status: "success".to_string(),
+        moved,
+    }))
+}
+
+/// Handles restoring a deleted note from the audit log.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the deleted note.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the restored note,
+/// or a JSON response with a 'not found' status if the note was never deleted, or a rejection if an error occurs.
+#[utoipa::path(
+    post,
+    path = "/api/notes/{id}/restore",
+    summary = "Restore a deleted note",
+    description = "Recreates a deleted note with the ID and fields it had when it was deleted, as recorded in \
+        the audit log. Attachments are not restored, and the note gets a new slug if another note took its slug. \
+        Only the owner of a note can restore it.",
+    tag = "notes",
+    params(("id" = String, Path, description = "ObjectId of the deleted note"), ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 201, description = "Note restored", body = SingleNoteResponse),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "No deletion of the note was recorded", body = GenericResponse),
+        (status = 409, description = "Note exists or its title is taken", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn restore_note_handler(
+    id: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let note = store
+        .restore_note(&id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if no deletion of the note is recorded
+    let error_response = GenericResponse {
+        status: "fail".to_string(),
+        message: format!("Deleted note with ID: {} not found", id),
+    };
+
+    if note.is_none() {
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    Ok(with_status(json(&note), StatusCode::CREATED))
+}
+
+/// Handles listing the notes other users shared with the requesting user.
+///
+/// # Arguments
+///
+/// * `opts` - FilterOptions containing parameters like page number and limit.
+/// * `user` - Identifier of the requesting user, taken from the required `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+/// * `renderer` - Renders the note content to HTML when requested.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the NoteListResponse or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/notes/shared-with-me",
+    summary = "List notes shared with me",
+    description = "Returns a page of the notes other users shared with the user named in the `X-User-Id` header.",
+    tag = "sharing",
+    params(FilterOptions, ("X-User-Id" = String, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Page of shared notes", body = NoteListResponse),
+        (status = 400, description = "Missing `X-User-Id` header or invalid page", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn shared_notes_handler(
+    opts: FilterOptions,
+    user: String,
+    store: Store,
+    renderer: MarkdownRenderer,
+) -> WebResult<impl Reply> {
+    // Extract limit and page from FilterOptions or use default values if not provided
+    let limit = opts.limit.unwrap_or(10) as i64;
+    let page = opts.page.unwrap_or(1) as i64;
+
+    let mut result_json = store
+        .shared_notes(limit, page, &user)
+        .await
+        .map_err(reject::custom)?;
+
+    // Render the content of the notes if requested
+    if opts.render == Some(RenderFormat::Html) {
+        result_json
+            .notes
+            .iter_mut()
+            .for_each(|note| renderer.render_note(note));
+    }
+
+    Ok(json(&result_json))
+}
+
+/// Handles retrieving the sharing settings of a note.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the sharing settings,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/notes/{id}/acl",
+    summary = "Get sharing settings",
+    description = "Returns the users the note is shared with and its share links that have not expired. \
+        Only the owner of a note can see them.",
+    tag = "sharing",
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 200, description = "Sharing settings of the note", body = AccessResponse),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn access_handler(
+    id: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let access = store
+        .get_access(&id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    Ok(access_or_not_found(access, &id))
+}
+
+/// Handles sharing a note with a user.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `grantee` - String representing the user the note is shared with.
+/// * `body` - An instance of GrantAccessSchema holding the granted role.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the updated sharing settings,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    put,
+    path = "/api/notes/{id}/acl/{user}",
+    summary = "Share a note",
+    description = "Shares the note with a user as `viewer` or `editor`, replacing the role they had. \
+        Only the owner of a note can share it.",
+    tag = "sharing",
+    request_body = GrantAccessSchema,
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("user" = String, Path, description = "Identifier of the user the note is shared with"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 200, description = "Updated sharing settings", body = AccessResponse),
+        (status = 400, description = "Invalid ID or body, or the note cannot be shared with this user", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn grant_access_handler(
+    id: String,
+    grantee: String,
+    body: GrantAccessSchema,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let access = store
+        .grant_access(&id, &grantee, body.role, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    Ok(access_or_not_found(access, &id))
+}
+
+/// Handles stopping to share a note with a user.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `grantee` - String representing the user the note is no longer shared with.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the updated sharing settings,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    delete,
+    path = "/api/notes/{id}/acl/{user}",
+    summary = "Stop sharing a note",
+    description = "Removes the access of a user to the note. Only the owner of a note can do this.",
+    tag = "sharing",
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("user" = String, Path, description = "Identifier of the user the note is no longer shared with"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 200, description = "Updated sharing settings", body = AccessResponse),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn revoke_access_handler(
+    id: String,
+    grantee: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let access = store
+        .revoke_access(&id, &grantee, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    Ok(access_or_not_found(access, &id))
+}
+
+/// Handles creating a read-only share link for a note.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `body` - An instance of CreateShareLinkSchema holding the lifetime of the link.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the link including its token,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    post,
+    path = "/api/notes/{id}/links",
+    summary = "Create a share link",
+    description = "Creates a link giving anyone with its token read-only access to the note at \
+        `/api/links/{token}` until it expires. The token is only returned once. Only the owner of a note \
+        can create links.",
+    tag = "sharing",
+    request_body = CreateShareLinkSchema,
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 201, description = "Link created", body = SingleShareLinkResponse),
+        (status = 400, description = "Invalid ID or lifetime", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn create_share_link_handler(
+    id: String,
+    body: CreateShareLinkSchema,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    // Work out when the link expires before touching the note
+    let expires_at = sharing::link_expiry(Utc::now(), body.expires_in).map_err(reject::custom)?;
+
+    let link = store
+        .create_share_link(&id, expires_at, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if the note is not found
+    if link.is_none() {
+        let error_response = GenericResponse {
+            status: "fail".to_string(),
+            message: format!("Note with ID: {} not found", id),
+        };
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    Ok(with_status(json(&link), StatusCode::CREATED))
+}
+
+/// Handles revoking a share link of a note.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `link_id` - String representing the ID of the link.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing a 'no content' response if the link is successfully revoked,
+/// or a JSON response with a 'not found' status if it does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    delete,
+    path = "/api/notes/{id}/links/{link_id}",
+    summary = "Revoke a share link",
+    description = "Revokes the link so its token no longer grants access to the note.",
+    tag = "sharing",
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("link_id" = String, Path, description = "ObjectId of the link"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 204, description = "Link revoked"),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 403, description = "User does not own the note", body = GenericResponse),
+        (status = 404, description = "Note or link not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn revoke_share_link_handler(
+    id: String,
+    link_id: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let result = store
+        .revoke_share_link(&id, &link_id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if the link is not found
+    if result.is_none() {
+        let error_response = GenericResponse {
+            status: "fail".to_string(),
+            message: format!("Share link with ID: {} not found on note {}", link_id, id),
+        };
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    // Return a 'no content' response indicating successful revocation
+    Ok(with_status(json(&""), StatusCode::NO_CONTENT))
+}
+
+/// Handles reading a note through a share link, without authentication.
+///
+/// # Arguments
+///
+/// * `token` - String representing the secret token of the link.
+/// * `opts` - Whether to render the content of the note.
+/// * `store` - The note store, e.g. the MongoDB database.
+/// * `renderer` - Renders the note content to HTML when requested.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the note if the link is valid,
+/// or a JSON response with a 'not found' status otherwise, or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/links/{token}",
+    summary = "Read a shared note",
+    description = "Returns the note a share link grants access to. Unknown, revoked and expired links are \
+        reported as not found.",
+    tag = "sharing",
+    params(("token" = String, Path, description = "Token of the share link"), RenderOptions),
+    responses(
+        (status = 200, description = "Shared note", body = SingleNoteResponse),
+        (status = 404, description = "Link not found or expired", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn share_link_note_handler(
+    token: String,
+    opts: RenderOptions,
+    store: Store,
+    renderer: MarkdownRenderer,
+) -> WebResult<impl Reply> {
+    let mut note = store
+        .get_shared_note(&token)
+        .await
+        .map_err(reject::custom)?;
+
+    // Render the content of the note if requested
+    if let (Some(note), Some(RenderFormat::Html)) = (&mut note, opts.render) {
+        renderer.render_note(&mut note.data.note);
+    }
+
+    // The token is a secret, so it is left out of the error message
+    if note.is_none() {
+        let error_response = GenericResponse {
+            status: "fail".to_string(),
+            message: "Share link not found or expired".to_string(),
+        };
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    Ok(with_status(json(&note), StatusCode::OK))
+}
+
+/// Handles uploading a file attached to a note.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `form` - The multipart form holding the file in its `file` part.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the created attachment,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    post,
+    path = "/api/notes/{id}/attachments",
+    summary = "Attach a file",
+    description = "Uploads a file in the `file` part of a multipart form and attaches it to the note. \
+        The size, content type and SHA-256 checksum of the file are recorded.",
+    tag = "attachments",
+    request_body(content = AttachmentUploadSchema, content_type = "multipart/form-data"),
+    params(("id" = String, Path, description = "ObjectId of the note"), ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 201, description = "File attached", body = SingleAttachmentResponse),
+        (status = 400, description = "Invalid ID or form", body = GenericResponse),
+        (status = 403, description = "Note is shared with the user read-only", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 413, description = "File too large", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn create_attachment_handler(
+    id: String,
+    form: FormData,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    // Read the uploaded file from the form
+    let attachment = read_upload(form).await.map_err(reject::custom)?;
+
+    // Store the file and attach it to the note
+    let attachment = store
+        .create_attachment(&id, attachment, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if the note is not found
+    let error_response = GenericResponse {
+        status: "fail".to_string(),
+        message: format!("Note with ID: {} not found", id),
+    };
+
+    // Check if the note exists and return the appropriate response
+    if attachment.is_none() {
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    // Return the JSON representation of the attachment with a status code indicating successful creation
+    Ok(with_status(json(&attachment), StatusCode::CREATED))
+}
+
+/// Handles listing the files attached to a note.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the attachments,
+/// or a JSON response with a 'not found' status if the note does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/notes/{id}/attachments",
+    summary = "List attachments",
+    description = "Returns the files attached to the note, oldest first.",
+    tag = "attachments",
+    params(("id" = String, Path, description = "ObjectId of the note"), ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user")),
+    responses(
+        (status = 200, description = "Attachments of the note", body = AttachmentListResponse),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 404, description = "Note not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn attachments_list_handler(
+    id: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    // Retrieve the attachments of the note
+    let attachments = store
+        .list_attachments(&id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if the note is not found
+    let error_response = GenericResponse {
+        status: "fail".to_string(),
+        message: format!("Note with ID: {} not found", id),
+    };
+
+    // Check if the note exists and return the appropriate response
+    if attachments.is_none() {
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    // Return the JSON representation of the attachments with a success status
+    Ok(with_status(json(&attachments), StatusCode::OK))
+}
+
+/// Handles downloading a file attached to a note.
+///
+/// A `Range` header selecting a single range of bytes is answered with `206 Partial Content`.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `attachment_id` - String representing the ID of the attachment.
+/// * `range` - Value of the `Range` header, if any.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the content of the file, or a JSON response with a 'not found'
+/// or 'range not satisfiable' status, or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/notes/{id}/attachments/{attachment_id}",
+    summary = "Download an attachment",
+    description = "Returns the content of the file with its recorded content type. Supports a single \
+        `bytes` range in the `Range` header.",
+    tag = "attachments",
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("attachment_id" = String, Path, description = "ObjectId of the attachment"),
+        ("Range" = Option<String>, Header, description = "Single range of bytes to download, e.g. `bytes=0-1023`"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 200, description = "Content of the file", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 206, description = "Requested range of the file", body = Vec<u8>, content_type = "application/octet-stream"),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 404, description = "Note or attachment not found", body = GenericResponse),
+        (status = 416, description = "Range starts after the end of the file", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn download_attachment_handler(
+    id: String,
+    attachment_id: String,
+    range: Option<String>,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<Response> {
+    // Retrieve the description of the attachment
+    let attachment = store
+        .get_attachment(&id, &attachment_id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Return a 'not found' response if the file is not attached to the note
+    let Some(attachment) = attachment else {
+        let error_response = GenericResponse {
+            status: "fail".to_string(),
+            message: format!(
+                "Attachment with ID: {} not found on note {}",
+                attachment_id, id
+            ),
+        };
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND).into_response());
+    };
+
+    // Work out which bytes to send
+    let size = attachment.size;
+    let (status, range) = match RangeRequest::parse(range.as_deref(), size) {
+        RangeRequest::Full => (StatusCode::OK, None),
+        RangeRequest::Partial(range) => (StatusCode::PARTIAL_CONTENT, Some(range)),
+        RangeRequest::Unsatisfiable => {
+            let error_response = GenericResponse {
+                status: "fail".to_string(),
+                message: format!("Range not satisfiable, the attachment has {} bytes", size),
+            };
+            let mut response =
+                with_status(json(&error_response), StatusCode::RANGE_NOT_SATISFIABLE)
+                    .into_response();
+            response.headers_mut().insert(
+                CONTENT_RANGE,
+                HeaderValue::from_str(&format!("bytes */{}", size)).unwrap(),
+            );
+            return Ok(response);
+        }
+    };
+
+    // Read the content of the file
+    let data = match range.or((size > 0).then_some(ByteRange {
+        start: 0,
+        end: size - 1,
+    })) {
+        Some(bytes) => store
+            .read_attachment(&attachment, bytes)
+            .await
+            .map_err(reject::custom)?,
+        None => Vec::new(),
+    };
+
+    // Describe the content in the headers
+    let mut response = Response::new(data.into());
+    *response.status_mut() = status;
+    let headers = response.headers_mut();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_str(&attachment.contentType)
+            .unwrap_or(HeaderValue::from_static("application/octet-stream")),
+    );
+    headers.insert(
+        CONTENT_LENGTH,
+        HeaderValue::from(range.map_or(size, |r| r.size())),
+    );
+    headers.insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+    headers.insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"{}\"",
+            header_filename(&attachment.filename)
+        ))
+        .unwrap(),
+    );
+    if let Some(range) = range {
+        headers.insert(
+            CONTENT_RANGE,
+            HeaderValue::from_str(&format!("bytes {}-{}/{}", range.start, range.end, size))
+                .unwrap(),
+        );
+    }
+
+    Ok(response)
+}
+
+/// Handles deleting a file attached to a note.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the note.
+/// * `attachment_id` - String representing the ID of the attachment.
+/// * `user` - Identifier of the requesting user, taken from the `X-User-Id` header.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing a 'no content' response if the attachment is successfully deleted,
+/// or a JSON response with a 'not found' status if it does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    delete,
+    path = "/api/notes/{id}/attachments/{attachment_id}",
+    summary = "Delete an attachment",
+    description = "Deletes the file and its content.",
+    tag = "attachments",
+    params(
+        ("id" = String, Path, description = "ObjectId of the note"),
+        ("attachment_id" = String, Path, description = "ObjectId of the attachment"),
+        ("X-User-Id" = Option<String>, Header, description = "Identifier of the requesting user"),
+    ),
+    responses(
+        (status = 204, description = "Attachment deleted"),
+        (status = 400, description = "Invalid ID", body = GenericResponse),
+        (status = 403, description = "Note is shared with the user read-only", body = GenericResponse),
+        (status = 404, description = "Note or attachment not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn delete_attachment_handler(
+    id: String,
+    attachment_id: String,
+    user: Option<String>,
+    store: Store,
+) -> WebResult<impl Reply> {
+    // Delete the attachment of the note
+    let result = store
+        .delete_attachment(&id, &attachment_id, user.as_deref())
+        .await
+        .map_err(reject::custom)?;
+
+    // Construct an error response if the attachment is not found
+    let error_response = GenericResponse {
+        status: "fail".to_string(),
+        message: format!(
+            "Attachment with ID: {} not found on note {}",
+            attachment_id, id
+        ),
+    };
+
+    // Check if the attachment exists and return the appropriate response
+    if result.is_none() {
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    // Return a 'no content' response indicating successful deletion
+    Ok(with_status(json(&""), StatusCode::NO_CONTENT))
+}
+
+/// Handles listing the workspaces.
+///
+/// # Arguments
+///
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the workspaces or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/admin/workspaces",
+    summary = "List workspaces",
+    description = "Returns every workspace, including archived ones. Reserved to the administrators \
+        listed in `ADMIN_USERS`.",
+    tag = "workspaces",
+    params(("X-User-Id" = String, Header, description = "Identifier of an administrator")),
+    responses(
+        (status = 200, description = "Workspaces", body = WorkspaceListResponse),
+        (status = 403, description = "User is not an administrator", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn workspaces_list_handler(store: Store) -> WebResult<impl Reply> {
+    let workspaces = store.list_workspaces().await.map_err(reject::custom)?;
+
+    Ok(json(&workspaces))
+}
+
+/// Handles the creation of a workspace.
+///
+/// # Arguments
+///
+/// * `body` - CreateWorkspaceSchema containing the identifier, name and members of the workspace.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the created workspace or a rejection if an error occurs.
+#[utoipa::path(
+    post,
+    path = "/api/admin/workspaces",
+    summary = "Create a workspace",
+    description = "Creates a workspace whose notes are selected with the `X-Workspace-Id` header or \
+        the `/api/workspaces/{id}` path prefix, by its members only. Reserved to the administrators \
+        listed in `ADMIN_USERS`.",
+    tag = "workspaces",
+    request_body = CreateWorkspaceSchema,
+    params(("X-User-Id" = String, Header, description = "Identifier of an administrator")),
+    responses(
+        (status = 201, description = "Workspace created", body = SingleWorkspaceResponse),
+        (status = 400, description = "Invalid body or workspace ID", body = GenericResponse),
+        (status = 403, description = "User is not an administrator", body = GenericResponse),
+        (status = 409, description = "Workspace ID is taken", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn create_workspace_handler(
+    body: CreateWorkspaceSchema,
+    store: Store,
+) -> WebResult<impl Reply> {
+    let workspace = store
+        .create_workspace(&body)
+        .await
+        .map_err(reject::custom)?;
+
+    Ok(with_status(json(&workspace), StatusCode::CREATED))
+}
+
+/// Handles archiving a workspace.
+///
+/// # Arguments
+///
+/// * `id` - String representing the ID of the workspace.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the archived workspace,
+/// or a JSON response with a 'not found' status if the workspace does not exist, or a rejection if an error occurs.
+#[utoipa::path(
+    post,
+    path = "/api/admin/workspaces/{id}/archive",
+    summary = "Archive a workspace",
+    description = "Archives a workspace. Its notes are kept but requests selecting the workspace \
+        are answered with `410 Gone`. Reserved to the administrators listed in `ADMIN_USERS`.",
+    tag = "workspaces",
+    params(
+        ("id" = String, Path, description = "Identifier of the workspace"),
+        ("X-User-Id" = String, Header, description = "Identifier of an administrator"),
+    ),
+    responses(
+        (status = 200, description = "Workspace archived", body = SingleWorkspaceResponse),
+        (status = 403, description = "User is not an administrator", body = GenericResponse),
+        (status = 404, description = "Workspace not found", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn archive_workspace_handler(id: String, store: Store) -> WebResult<impl Reply> {
+    let workspace = store.archive_workspace(&id).await.map_err(reject::custom)?;
+
+    // Construct an error response if the workspace is not found
+    let error_response = GenericResponse {
+        status: "fail".to_string(),
+        message: format!("Workspace with ID: {} not found", id),
+    };
+
+    // Check if the workspace exists and return the appropriate response
+    if workspace.is_none() {
+        return Ok(with_status(json(&error_response), StatusCode::NOT_FOUND));
+    }
+
+    Ok(with_status(json(&workspace), StatusCode::OK))
+}
+
+/// Handles querying the audit log of the notes.
+///
+/// # Arguments
+///
+/// * `query` - AuditQuery filtering the records by note, actor and time.
+/// * `store` - The note store, e.g. the MongoDB database.
+///
+/// # Returns
+///
+/// Returns a Warp Result containing the JSON representation of the AuditListResponse or a rejection if an error occurs.
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    summary = "Query the audit log",
+    description = "Returns a page of the records of the changes made to the notes of the workspace, most recent \
+        first. Each record holds the actor, the action, the changed fields before and after the change, and the \
+        request ID from the `X-Request-Id` header. Reserved to the administrators listed in `ADMIN_USERS`.",
+    tag = "audit",
+    params(AuditQuery, ("X-User-Id" = String, Header, description = "Identifier of an administrator")),
+    responses(
+        (status = 200, description = "Page of audit records", body = AuditListResponse),
+        (status = 400, description = "Invalid note ID, time or page", body = GenericResponse),
+        (status = 403, description = "User is not an administrator", body = GenericResponse),
+        (status = 429, description = "Rate limit exceeded", body = GenericResponse),
+    )
+)]
+pub async fn audit_log_handler(query: AuditQuery, store: Store) -> WebResult<impl Reply> {
+    let records = store.audit_log(&query).await.map_err(reject::custom)?;
+
+    Ok(json(&records))
+}
+
+/// Reads the `file` part of an upload form.
+async fn read_upload(form: FormData) -> crate::Result<NewAttachment> {
+    let mut parts = form.map_err(|e| InvalidUploadError(e.to_string()));
+
+    while let Some(part) = parts.try_next().await? {
+        if part.name() != "file" {
+            continue;
+        }
+
+        let filename = part.filename().unwrap_or("attachment").to_string();
+        let content_type = part.content_type().map(str::to_string);
+
+        // Collect the content of the part
+        let data = part
+            .stream()
+            .try_fold(Vec::new(), |mut data, chunk| async move {
+                data.extend_from_slice(chunk.chunk());
+                Ok(data)
+            })
+            .await
+            .map_err(|e| InvalidUploadError(e.to_string()))?;
+
+        return Ok(NewAttachment::new(&filename, content_type.as_deref(), data));
+    }
+
+    Err(InvalidUploadError("The form has no file part".to_string()))
+}
+
+/// Makes a filename safe to quote in a `Content-Disposition` header.
+fn header_filename(filename: &str) -> String {
+    filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii() && !c.is_ascii_control() => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Replies with the sharing settings of a note, or a 'not found' response if there are none.
+fn access_or_not_found(
+    access: Option<AccessResponse>,
+    id: &str,
+) -> warp::reply::WithStatus<warp::reply::Json> {
+    match access {
+        Some(access) => with_status(json(&access), StatusCode::OK),
+        None => with_status(
+            json(&GenericResponse {
+                status: "fail".to_string(),
+                message: format!("Note with ID: {} not found", id),
+            }),
+            StatusCode::NOT_FOUND,
+        ),
+    }
+}
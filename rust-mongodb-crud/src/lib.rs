@@ -0,0 +1,64 @@
+//! RESTful API service for managing notes stored in MongoDB.
+//!
+//! The request and response types in [`schema`] and [`response`] are always available, so
+//! clients can share them. Everything else requires the default `server` feature.
+
+#[cfg(feature = "server")]
+pub mod attachments;
+#[cfg(feature = "server")]
+pub mod audit;
+#[cfg(feature = "server")]
+pub mod db;
+#[cfg(feature = "server")]
+pub mod errors;
+#[cfg(feature = "server")]
+pub mod handlers;
+#[cfg(feature = "server")]
+pub mod migrations;
+#[cfg(feature = "server")]
+pub mod model;
+#[cfg(feature = "server")]
+pub mod openapi;
+#[cfg(feature = "server")]
+pub mod patch;
+#[cfg(feature = "server")]
+pub mod publishing;
+#[cfg(feature = "server")]
+pub mod rate_limit;
+#[cfg(feature = "server")]
+pub mod render;
+pub mod response;
+#[cfg(feature = "server")]
+pub mod routes;
+pub mod schema;
+#[cfg(feature = "server")]
+pub mod sharing;
+#[cfg(feature = "server")]
+pub mod store;
+#[cfg(feature = "server")]
+pub mod transactions;
+#[cfg(feature = "server")]
+pub mod workspaces;
+
+// Define custom Result and WebResult types for handling errors and rejections
+#[cfg(feature = "server")]
+pub type Result<T> = std::result::Result<T, errors::Error>;
+#[cfg(feature = "server")]
+pub type WebResult<T> = std::result::Result<T, warp::Rejection>;
+
+/// Header carrying the identifier of the user making the request.
+///
+/// Authentication is expected to happen in front of the API, which forwards the
+/// authenticated user identifier in this header.
+pub const USER_HEADER: &str = "x-user-id";
+
+/// Header selecting the workspace a request operates on.
+///
+/// Requests without it, and without a `/api/workspaces/{id}` path prefix, use the default
+/// workspace.
+pub const WORKSPACE_HEADER: &str = "x-workspace-id";
+
+/// Header carrying the identifier of the request, recorded in the audit log.
+///
+/// Requests without it are given a generated identifier.
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
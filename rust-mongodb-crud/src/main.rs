@@ -1,19 +1,8 @@
-mod db;
-mod errors;
-mod handlers;
-mod model;
-mod response;
-mod schema;
-
-use db::DB;
 use dotenv::dotenv;
-use schema::FilterOptions;
-use std::convert::Infallible;
-use warp::{http::Method, Filter, Rejection};
-
-// Define custom Result and WebResult types for handling errors and rejections
-type Result<T> = std::result::Result<T, errors::Error>;
-type WebResult<T> = std::result::Result<T, Rejection>;
+use rust_mongodb_crud::{
+    db::DB, migrations, publishing, rate_limit::RateLimiter, routes::routes, store::Store, Result,
+};
+use std::sync::Arc;
 
 // Entry point of the application
 #[tokio::main]
@@ -29,61 +18,32 @@ async fn main() -> Result<()> {
     // Initialize a connection to the database
     let db = DB::init().await?;
 
-    // Configure Cross-Origin Resource Sharing (CORS) policies
-    let cors = warp::cors()
-        .allow_methods(&[Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-        .allow_origins(vec!["http://localhost:3000"])
-        .allow_headers(vec!["content-type"])
-        .allow_credentials(true);
-
-    // Define routes for different endpoints
-    let note_router = warp::path!("api" / "notes");
-    let note_router_id = warp::path!("api" / "notes" / String);
-    let health_checker = warp::path!("api" / "healthchecker")
-        .and(warp::get())
-        .and_then(handlers::health_checker_handler);
+    // Handle the `migrate` command line interface instead of starting the server
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("migrate") {
+        return migrations::run_command(&db.migrator(), &args[1..]).await;
+    }
 
-    // Define routes for handling note-related actions
-    let note_routes = note_router
-        .and(warp::post())
-        .and(warp::body::json())
-        .and(with_db(db.clone())) // Inject database instance into the handler
-        .and_then(handlers::create_note_handler)
-        .or(note_router
-            .and(warp::get())
-            .and(warp::query::<FilterOptions>())
-            .and(with_db(db.clone()))
-            .and_then(handlers::notes_list_handler));
+    // Apply pending schema migrations before serving requests
+    db.migrator().migrate().await?;
+    // Enforce title uniqueness within the configured scope
+    db.sync_title_index().await?;
+    // Prepare the note collections of workspaces for the applied migrations
+    db.sync_workspaces().await?;
+    // Give notes created before slugs existed a public slug
+    db.backfill_slugs().await?;
 
-    let note_routes_id = note_router_id
-        .and(warp::patch())
-        .and(warp::body::json())
-        .and(with_db(db.clone()))
-        .and_then(handlers::edit_note_handler)
-        .or(note_router_id
-            .and(warp::get())
-            .and(with_db(db.clone()))
-            .and_then(handlers::get_note_handler))
-        .or(note_router_id
-            .and(warp::delete())
-            .and(with_db(db.clone()))
-            .and_then(handlers::delete_note_handler));
+    // Configure request rate limits per route group
+    let limiter = RateLimiter::init(&db.database);
 
-    // Combine routes, logging, error recovery, and CORS policies
-    let routes = note_routes
-        .with(warp::log("api")) // Log API requests
-        .or(note_routes_id)
-        .or(health_checker)
-        .with(cors) // Apply CORS policies to routes
-        .recover(errors::handle_rejection); // Handle errors and rejections
+    // Publish scheduled notes in the background
+    let store: Store = Arc::new(db);
+    publishing::spawn_publisher(store.clone(), publishing::interval_from_env());
 
     // Start the server on port 8080
     println!("Server started successfully on port 8080");
-    warp::serve(routes).run(([0, 0, 0, 0], 8080)).await;
+    warp::serve(routes(store, limiter))
+        .run(([0, 0, 0, 0], 8080))
+        .await;
     Ok(())
 }
-
-// Helper function to inject the database instance into route handlers
-fn with_db(db: DB) -> impl Filter<Extract = (DB,), Error = Infallible> + Clone {
-    warp::any().map(move || db.clone())
-}
@@ -0,0 +1,579 @@
+use crate::attachments::ATTACHMENT_FILES_COLLECTION;
+use crate::audit::AUDIT_COLLECTION;
+use crate::publishing::SLUG_INDEX;
+use crate::{errors::Error::*, rate_limit::RATE_LIMIT_COLLECTION, Result};
+use chrono::prelude::*;
+use futures::StreamExt;
+use mongodb::bson::{self, doc, Document};
+use mongodb::error::ErrorKind;
+use mongodb::options::{IndexOptions, UpdateOptions};
+use mongodb::{Collection, Database, IndexModel};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Name of the collection that records which migrations have been applied.
+pub const MIGRATIONS_COLLECTION: &str = "_migrations";
+
+/// Server error code of commands on a collection that does not exist.
+const NAMESPACE_NOT_FOUND: i32 = 26;
+
+/// Describes an index managed by a migration.
+#[derive(Debug, Clone)]
+pub struct IndexSpec {
+    /// Collection the index belongs to. `None` targets the note collection.
+    pub collection: Option<&'static str>,
+    /// Explicit name of the index, used to drop it again on rollback.
+    pub name: &'static str,
+    /// Index key specification.
+    pub keys: Document,
+    /// Whether the index enforces uniqueness.
+    pub unique: bool,
+    /// Whether documents missing the indexed fields are left out of the index.
+    pub sparse: bool,
+    /// Time after which documents are removed, turning the index into a TTL index.
+    pub expire_after: Option<Duration>,
+}
+
+/// A single reversible step of a migration.
+#[derive(Debug, Clone)]
+pub enum Operation {
+    /// Creates the described index; rolling back drops it.
+    CreateIndex(IndexSpec),
+    /// Drops the described index; rolling back recreates it.
+    DropIndex(IndexSpec),
+}
+
+/// A versioned schema migration.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    /// Monotonically increasing version number.
+    pub version: u32,
+    /// Human readable name of the migration.
+    pub name: &'static str,
+    /// Operations executed, in order, when the migration is applied.
+    pub operations: Vec<Operation>,
+}
+
+/// Record stored in the `_migrations` collection for every applied migration.
+#[allow(non_snake_case)]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AppliedMigration {
+    /// Version of the applied migration.
+    #[serde(rename = "_id")]
+    pub version: u32,
+    /// Name of the applied migration.
+    pub name: String,
+    /// Date and time when the migration was applied.
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub appliedAt: DateTime<Utc>,
+}
+
+/// Status of a known migration as reported by [`Migrator::status`].
+#[derive(Debug)]
+pub struct MigrationStatus {
+    /// Version of the migration.
+    pub version: u32,
+    /// Name of the migration.
+    pub name: &'static str,
+    /// Date and time when the migration was applied, if it was.
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+/// Returns every known migration in ascending version order.
+pub fn migrations() -> Vec<Migration> {
+    vec![
+        Migration {
+            version: 1,
+            name: "unique_title_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: None,
+                name: "title_unique",
+                keys: doc! {"title": 1},
+                unique: true,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            version: 2,
+            name: "text_search_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: None,
+                name: "title_content_text",
+                keys: doc! {"title": "text", "content": "text"},
+                unique: false,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            version: 3,
+            name: "tags_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: None,
+                name: "tags",
+                keys: doc! {"tags": 1},
+                unique: false,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            version: 4,
+            name: "owner_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: None,
+                name: "owner",
+                keys: doc! {"owner": 1},
+                unique: false,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            // Title uniqueness is enforced by the scope-dependent index managed by
+            // `DB::sync_title_index` from now on
+            version: 5,
+            name: "drop_global_title_index",
+            operations: vec![Operation::DropIndex(IndexSpec {
+                collection: None,
+                name: "title_unique",
+                keys: doc! {"title": 1},
+                unique: true,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            version: 6,
+            name: "rate_limit_ttl_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: Some(RATE_LIMIT_COLLECTION),
+                name: "expires_at_ttl",
+                keys: doc! {"expiresAt": 1},
+                unique: false,
+                sparse: false,
+                expire_after: Some(Duration::ZERO),
+            })],
+        },
+        Migration {
+            version: 7,
+            name: "attachment_note_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: Some(ATTACHMENT_FILES_COLLECTION),
+                name: "attachment_note",
+                keys: doc! {"metadata.noteId": 1},
+                unique: false,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            // Notes created before slugs existed are left out until they are backfilled
+            version: 8,
+            name: "publishing_indexes",
+            operations: vec![
+                Operation::CreateIndex(IndexSpec {
+                    collection: None,
+                    name: SLUG_INDEX,
+                    keys: doc! {"slug": 1},
+                    unique: true,
+                    sparse: true,
+                    expire_after: None,
+                }),
+                Operation::CreateIndex(IndexSpec {
+                    collection: None,
+                    name: "publish_at",
+                    keys: doc! {"publishAt": 1},
+                    unique: false,
+                    sparse: true,
+                    expire_after: None,
+                }),
+            ],
+        },
+        Migration {
+            version: 9,
+            name: "sharing_indexes",
+            operations: vec![
+                Operation::CreateIndex(IndexSpec {
+                    collection: None,
+                    name: "acl_user",
+                    keys: doc! {"acl.user": 1},
+                    unique: false,
+                    sparse: false,
+                    expire_after: None,
+                }),
+                Operation::CreateIndex(IndexSpec {
+                    collection: None,
+                    name: "share_link_token",
+                    keys: doc! {"shareLinks.tokenHash": 1},
+                    unique: false,
+                    sparse: true,
+                    expire_after: None,
+                }),
+            ],
+        },
+        Migration {
+            version: 10,
+            name: "workspace_index",
+            operations: vec![Operation::CreateIndex(IndexSpec {
+                collection: None,
+                name: "workspace",
+                keys: doc! {"workspaceId": 1},
+                unique: false,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+        Migration {
+            // Every audit query is restricted to a workspace and sorted by time
+            version: 11,
+            name: "audit_indexes",
+            operations: vec![
+                Operation::CreateIndex(IndexSpec {
+                    collection: Some(AUDIT_COLLECTION),
+                    name: "audit_timestamp",
+                    keys: doc! {"workspaceId": 1, "timestamp": -1},
+                    unique: false,
+                    sparse: false,
+                    expire_after: None,
+                }),
+                Operation::CreateIndex(IndexSpec {
+                    collection: Some(AUDIT_COLLECTION),
+                    name: "audit_note",
+                    keys: doc! {"workspaceId": 1, "noteId": 1, "timestamp": -1},
+                    unique: false,
+                    sparse: false,
+                    expire_after: None,
+                }),
+                Operation::CreateIndex(IndexSpec {
+                    collection: Some(AUDIT_COLLECTION),
+                    name: "audit_actor",
+                    keys: doc! {"workspaceId": 1, "actor": 1, "timestamp": -1},
+                    unique: false,
+                    sparse: false,
+                    expire_after: None,
+                }),
+            ],
+        },
+        Migration {
+            // Notes have no tags, so the index of version 3 only costs writes
+            version: 12,
+            name: "drop_tags_index",
+            operations: vec![Operation::DropIndex(IndexSpec {
+                collection: None,
+                name: "tags",
+                keys: doc! {"tags": 1},
+                unique: false,
+                sparse: false,
+                expire_after: None,
+            })],
+        },
+    ]
+}
+
+/// Applies and rolls back migrations against a database.
+#[derive(Clone, Debug)]
+pub struct Migrator {
+    /// Database the migrations operate on.
+    database: Database,
+    /// Name of the note collection targeted by index specifications without a collection.
+    note_collection: String,
+    /// Collection recording applied migrations.
+    applied: Collection<AppliedMigration>,
+}
+
+impl Migrator {
+    /// Creates a new `Migrator` for the given database and note collection.
+    pub fn new(database: &Database, note_collection: &str) -> Self {
+        Self {
+            database: database.clone(),
+            note_collection: note_collection.to_string(),
+            applied: database.collection(MIGRATIONS_COLLECTION),
+        }
+    }
+
+    /// Lists every known migration together with the time it was applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `_migrations` collection cannot be queried.
+    pub async fn status(&self) -> Result<Vec<MigrationStatus>> {
+        let applied = self.applied_migrations().await?;
+
+        let status = migrations()
+            .into_iter()
+            .map(|migration| MigrationStatus {
+                version: migration.version,
+                name: migration.name,
+                applied_at: applied
+                    .iter()
+                    .find(|record| record.version == migration.version)
+                    .map(|record| record.appliedAt),
+            })
+            .collect();
+
+        Ok(status)
+    }
+
+    /// Applies every pending migration in ascending version order.
+    ///
+    /// Returns the versions that were applied. Instances starting at the same time may apply
+    /// the same migration, which is harmless since its operations are idempotent: only the
+    /// instance that records it reports it as applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any operation of a migration fails. Migrations applied before the
+    /// failing one stay recorded.
+    pub async fn migrate(&self) -> Result<Vec<u32>> {
+        let applied = self.applied_migrations().await?;
+        let mut versions = Vec::new();
+
+        for migration in migrations() {
+            // Skip migrations that are already recorded as applied
+            if applied
+                .iter()
+                .any(|record| record.version == migration.version)
+            {
+                continue;
+            }
+
+            for operation in &migration.operations {
+                self.execute(operation).await?;
+            }
+
+            // Record the migration so it is not applied again, unless another instance did
+            let record = AppliedMigration {
+                version: migration.version,
+                name: migration.name.to_string(),
+                appliedAt: Utc::now(),
+            };
+            let record = bson::to_document(&record).map_err(MongoSerializeBsonError)?;
+            let options = UpdateOptions::builder().upsert(true).build();
+            let result = self
+                .applied
+                .update_one(
+                    doc! {"_id": migration.version},
+                    doc! {"$setOnInsert": record},
+                    options,
+                )
+                .await
+                .map_err(MongoQueryError)?;
+            if result.upserted_id.is_none() {
+                continue;
+            }
+
+            println!("Applied migration {} {}", migration.version, migration.name);
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+
+    /// Rolls back the latest `steps` applied migrations in descending version order.
+    ///
+    /// Returns the versions that were rolled back.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reverting any operation fails.
+    pub async fn rollback(&self, steps: usize) -> Result<Vec<u32>> {
+        let applied = self.applied_migrations().await?;
+        let mut versions = Vec::new();
+
+        // Walk the applied migrations from newest to oldest
+        let known = migrations();
+        for record in applied.iter().rev().take(steps) {
+            let Some(migration) = known.iter().find(|m| m.version == record.version) else {
+                return Err(MigrationError(format!(
+                    "applied migration {} is unknown to this build",
+                    record.version
+                )));
+            };
+
+            // Revert the operations in reverse order
+            for operation in migration.operations.iter().rev() {
+                self.revert(operation).await?;
+            }
+
+            self.applied
+                .delete_one(doc! {"_id": migration.version}, None)
+                .await
+                .map_err(MongoQueryError)?;
+
+            println!(
+                "Rolled back migration {} {}",
+                migration.version, migration.name
+            );
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+
+    /// Replays the note collection operations of the applied migrations.
+    ///
+    /// Prepares note collections created after the migrations were recorded, such as the
+    /// collections of workspaces. Operations on other collections are skipped and nothing is
+    /// recorded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any operation fails.
+    pub async fn prepare_collection(&self) -> Result<()> {
+        let applied = self.applied_migrations().await?;
+
+        for migration in migrations() {
+            if !applied
+                .iter()
+                .any(|record| record.version == migration.version)
+            {
+                continue;
+            }
+
+            for operation in &migration.operations {
+                let (Operation::CreateIndex(spec) | Operation::DropIndex(spec)) = operation;
+                if spec.collection.is_none() {
+                    self.execute(operation).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetches the applied migrations sorted by ascending version.
+    async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>> {
+        let mut cursor = self
+            .applied
+            .find(None, None)
+            .await
+            .map_err(MongoQueryError)?;
+
+        let mut applied = Vec::new();
+        while let Some(record) = cursor.next().await {
+            applied.push(record.map_err(MongoQueryError)?);
+        }
+        applied.sort_by_key(|record| record.version);
+
+        Ok(applied)
+    }
+
+    /// Executes a single migration operation.
+    async fn execute(&self, operation: &Operation) -> Result<()> {
+        match operation {
+            Operation::CreateIndex(spec) => self.create_index(spec).await,
+            Operation::DropIndex(spec) => self.drop_index(spec).await,
+        }
+    }
+
+    /// Reverts a single migration operation.
+    async fn revert(&self, operation: &Operation) -> Result<()> {
+        match operation {
+            Operation::CreateIndex(spec) => self.drop_index(spec).await,
+            Operation::DropIndex(spec) => self.create_index(spec).await,
+        }
+    }
+
+    /// Creates the index described by `spec`.
+    async fn create_index(&self, spec: &IndexSpec) -> Result<()> {
+        let options = IndexOptions::builder()
+            .name(spec.name.to_string())
+            .unique(spec.unique)
+            .sparse(spec.sparse)
+            .expire_after(spec.expire_after)
+            .build();
+        let index = IndexModel::builder()
+            .keys(spec.keys.clone())
+            .options(options)
+            .build();
+
+        self.collection(spec)
+            .create_index(index, None)
+            .await
+            .map_err(MongoQueryError)?;
+
+        Ok(())
+    }
+
+    /// Drops the index described by `spec`, ignoring indexes that do not exist.
+    async fn drop_index(&self, spec: &IndexSpec) -> Result<()> {
+        let collection = self.collection(spec);
+        let names = match collection.list_index_names().await {
+            Ok(names) => names,
+            // Collections that do not exist yet have no indexes
+            Err(e) if is_namespace_not_found(&e) => Vec::new(),
+            Err(e) => return Err(MongoQueryError(e)),
+        };
+
+        if names.iter().any(|name| name == spec.name) {
+            collection
+                .drop_index(spec.name, None)
+                .await
+                .map_err(MongoQueryError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Resolves the collection targeted by an index specification.
+    fn collection(&self, spec: &IndexSpec) -> Collection<Document> {
+        let name = spec.collection.unwrap_or(self.note_collection.as_str());
+        self.database.collection(name)
+    }
+}
+
+/// Returns whether `e` reports a collection that does not exist.
+fn is_namespace_not_found(e: &mongodb::error::Error) -> bool {
+    matches!(e.kind.as_ref(), ErrorKind::Command(e) if e.code == NAMESPACE_NOT_FOUND)
+}
+
+/// Runs the `migrate` command line interface.
+///
+/// Supported subcommands:
+///
+/// * `list` - prints every known migration and whether it is applied (default).
+/// * `up` - applies every pending migration.
+/// * `down [steps]` - rolls back the latest `steps` migrations (default: 1).
+///
+/// # Errors
+///
+/// Returns an error if the subcommand is unknown or if a migration fails.
+pub async fn run_command(migrator: &Migrator, args: &[String]) -> Result<()> {
+    match args.first().map(String::as_str).unwrap_or("list") {
+        "list" => {
+            for migration in migrator.status().await? {
+                let state = match migration.applied_at {
+                    Some(applied_at) => format!("applied {}", applied_at.to_rfc3339()),
+                    None => "pending".to_string(),
+                };
+                println!("{:>4}  {:<24} {}", migration.version, migration.name, state);
+            }
+        }
+        "up" => {
+            if migrator.migrate().await?.is_empty() {
+                println!("No pending migrations");
+            }
+        }
+        "down" => {
+            let steps = match args.get(1) {
+                Some(steps) => steps
+                    .parse::<usize>()
+                    .map_err(|_| MigrationError(format!("invalid number of steps: {}", steps)))?,
+                None => 1,
+            };
+            if migrator.rollback(steps).await?.is_empty() {
+                println!("No applied migrations to roll back");
+            }
+        }
+        other => {
+            return Err(MigrationError(format!(
+                "unknown migrate subcommand: {} (expected list, up or down)",
+                other
+            )));
+        }
+    }
+
+    Ok(())
+}
@@ -1,6 +1,9 @@
+use crate::schema::{AclEntry, AuditAction, ShareRole};
 use chrono::prelude::*;
-use mongodb::bson::{self, oid::ObjectId};
+use mongodb::bson::{self, oid::ObjectId, Document};
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[allow(non_snake_case)]
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -14,8 +17,20 @@ pub struct NoteModel {
     pub content: String,
     /// Optional category of the note.
     pub category: Option<String>,
+    /// Optional identifier of the user owning the note.
+    pub owner: Option<String>,
     /// Optional publication status of the note.
     pub published: Option<bool>,
+    /// Date and time when the note is scheduled to be published, if any.
+    pub publishAt: Option<bson::DateTime>,
+    /// URL slug derived from the title, identifying the note on the public endpoint.
+    pub slug: Option<String>,
+    /// Users the note is shared with, besides its owner.
+    pub acl: Option<Vec<AclEntry>>,
+    /// Links granting read-only access to the note without an account.
+    pub shareLinks: Option<Vec<ShareLinkModel>>,
+    /// Workspace the note belongs to when workspaces share the note collection.
+    pub workspaceId: Option<String>,
     /// Date and time when the note was created.
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub createdAt: DateTime<Utc>,
@@ -23,3 +38,216 @@ pub struct NoteModel {
     #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
     pub updatedAt: DateTime<Utc>,
 }
+
+/// Read-only share link of a note.
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ShareLinkModel {
+    /// Identifier of the link, used to revoke it.
+    pub id: ObjectId,
+    /// Hex encoded SHA-256 hash of the token, the token itself is never stored.
+    pub tokenHash: String,
+    /// Date and time after which the link no longer grants access.
+    pub expiresAt: bson::DateTime,
+}
+
+/// Workspace holding its own set of notes.
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct WorkspaceModel {
+    /// Unique identifier of the workspace, chosen when it is created.
+    #[serde(rename = "_id")]
+    pub id: String,
+    /// Display name of the workspace.
+    pub name: String,
+    /// Users allowed to access the notes of the workspace.
+    #[serde(default)]
+    pub members: Vec<String>,
+    /// Date and time when the workspace was created.
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub createdAt: DateTime<Utc>,
+    /// Date and time when the workspace was archived, if it was.
+    pub archivedAt: Option<bson::DateTime>,
+}
+
+/// Immutable record of a change made to a note.
+#[allow(non_snake_case)]
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct AuditRecordModel {
+    /// Unique identifier of the record.
+    #[serde(rename = "_id")]
+    pub id: ObjectId,
+    /// Kind of change.
+    pub action: AuditAction,
+    /// Identifier of the user who made the change, if known.
+    pub actor: Option<String>,
+    /// Identifier of the changed note.
+    pub noteId: ObjectId,
+    /// Workspace of the note, `None` for the default workspace.
+    pub workspaceId: Option<String>,
+    /// Identifier of the request that made the change.
+    pub requestId: Option<String>,
+    /// Values of the changed fields before the change.
+    pub before: Document,
+    /// Values of the changed fields after the change.
+    pub after: Document,
+    /// Date and time of the change.
+    #[serde(with = "bson::serde_helpers::chrono_datetime_as_bson_datetime")]
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Level of access a user has to a note, in increasing order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    /// Read the note and its attachments.
+    Read,
+    /// Also update the note and its attachments.
+    Write,
+    /// Also delete and share the note.
+    Manage,
+}
+
+impl Access {
+    /// Message returned to clients lacking this level of access.
+    pub fn forbidden_message(&self) -> &'static str {
+        match self {
+            Access::Read => "You do not have access to this note",
+            Access::Write => "This note is shared with you read-only",
+            Access::Manage => "Only the owner of this note can do this",
+        }
+    }
+}
+
+impl fmt::Display for Access {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Access::Read => write!(f, "read"),
+            Access::Write => write!(f, "write"),
+            Access::Manage => write!(f, "manage"),
+        }
+    }
+}
+
+impl NoteModel {
+    /// Returns the access `user` has to the note, or `None` if the note is hidden from them.
+    ///
+    /// Notes without an owner are open to everyone, but have no sharing settings to manage
+    /// (see [`crate::sharing::check_owned`]). Owned notes are managed by their owner and shared
+    /// with other users through the access control list.
+    pub fn access(&self, user: Option<&str>) -> Option<Access> {
+        let Some(owner) = &self.owner else {
+            return Some(Access::Manage);
+        };
+        let user = user?;
+        if owner == user {
+            return Some(Access::Manage);
+        }
+
+        self.acl
+            .iter()
+            .flatten()
+            .find(|entry| entry.user == user)
+            .map(|entry| match entry.role {
+                ShareRole::Viewer => Access::Read,
+                ShareRole::Editor => Access::Write,
+            })
+    }
+}
+
+/// Scope within which note titles must be unique.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TitleScope {
+    /// Titles are unique across the whole collection.
+    #[default]
+    Global,
+    /// Titles are unique among the notes of the same owner.
+    ///
+    /// Notes without an owner all share one namespace, the index seeing their missing owner as
+    /// `null`, so their titles are unique among every ownerless note.
+    Owner,
+    /// Titles are unique among the notes of the same category.
+    Category,
+}
+
+impl TitleScope {
+    /// All available scopes.
+    pub const ALL: [TitleScope; 3] = [TitleScope::Global, TitleScope::Owner, TitleScope::Category];
+
+    /// Name of the unique index enforcing this scope.
+    pub fn index_name(&self) -> &'static str {
+        match self {
+            TitleScope::Global => "title_unique_global",
+            TitleScope::Owner => "title_unique_owner",
+            TitleScope::Category => "title_unique_category",
+        }
+    }
+
+    /// Message returned to clients when a title violates this scope.
+    pub fn conflict_message(&self) -> &'static str {
+        match self {
+            TitleScope::Global => "A note with this title already exists",
+            TitleScope::Owner => "A note with this title already exists for this owner",
+            TitleScope::Category => "A note with this title already exists in this category",
+        }
+    }
+}
+
+impl fmt::Display for TitleScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TitleScope::Global => write!(f, "global"),
+            TitleScope::Owner => write!(f, "owner"),
+            TitleScope::Category => write!(f, "category"),
+        }
+    }
+}
+
+impl FromStr for TitleScope {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "global" => Ok(TitleScope::Global),
+            "owner" => Ok(TitleScope::Owner),
+            "category" => Ok(TitleScope::Category),
+            other => Err(format!(
+                "invalid title scope: {} (expected global, owner or category)",
+                other
+            )),
+        }
+    }
+}
+
+/// How the notes of different workspaces are kept apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TenancyMode {
+    /// Workspaces share the note collection and notes record their `workspaceId`.
+    #[default]
+    Field,
+    /// Every workspace has its own note collection.
+    Collection,
+}
+
+impl fmt::Display for TenancyMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TenancyMode::Field => write!(f, "field"),
+            TenancyMode::Collection => write!(f, "collection"),
+        }
+    }
+}
+
+impl FromStr for TenancyMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "field" => Ok(TenancyMode::Field),
+            "collection" => Ok(TenancyMode::Collection),
+            other => Err(format!(
+                "invalid tenancy mode: {} (expected field or collection)",
+                other
+            )),
+        }
+    }
+}
@@ -0,0 +1,115 @@
+use crate::handlers;
+use crate::response::{
+    AccessResponse, AttachmentData, AttachmentListResponse, AttachmentResponse, AuditListResponse,
+    AuditRecordResponse, GenericResponse, MoveCategoryResponse, NoteData, NoteListResponse,
+    NoteResponse, PublicNoteData, PublicNoteResponse, ShareLinkData, ShareLinkResponse,
+    SingleAttachmentResponse, SingleNoteResponse, SinglePublicNoteResponse,
+    SingleShareLinkResponse, SingleWorkspaceResponse, WorkspaceData, WorkspaceListResponse,
+    WorkspaceResponse,
+};
+use crate::schema::{
+    AclEntry, AttachmentUploadSchema, AuditAction, CreateNoteSchema, CreateShareLinkSchema,
+    CreateWorkspaceSchema, GrantAccessSchema, MoveCategorySchema, RenderFormat, ShareRole,
+    UpdateNoteSchema,
+};
+use std::sync::Arc;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::Config;
+
+/// OpenAPI 3 specification of the notes API.
+///
+/// Paths are collected from the `#[utoipa::path]` annotations on the handlers and schemas from
+/// the request and response types. `GenericResponse` is the envelope of every error response.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Rust MongoDB CRUD",
+        description = "RESTful API service for managing notes.\n\n\
+            Notes belong to workspaces. Every `/api/...` note route operates on the workspace \
+            named in the `X-Workspace-Id` header or in an `/api/workspaces/{id}/...` path prefix, \
+            and on the default workspace when neither is given."
+    ),
+    paths(
+        handlers::health_checker_handler,
+        handlers::notes_list_handler,
+        handlers::create_note_handler,
+        handlers::get_note_handler,
+        handlers::replace_note_handler,
+        handlers::edit_note_handler,
+        handlers::delete_note_handler,
+        handlers::move_category_handler,
+        handlers::restore_note_handler,
+        handlers::public_note_handler,
+        handlers::shared_notes_handler,
+        handlers::access_handler,
+        handlers::grant_access_handler,
+        handlers::revoke_access_handler,
+        handlers::create_share_link_handler,
+        handlers::revoke_share_link_handler,
+        handlers::share_link_note_handler,
+        handlers::create_attachment_handler,
+        handlers::attachments_list_handler,
+        handlers::download_attachment_handler,
+        handlers::delete_attachment_handler,
+        handlers::workspaces_list_handler,
+        handlers::create_workspace_handler,
+        handlers::archive_workspace_handler,
+        handlers::audit_log_handler,
+    ),
+    components(schemas(
+        CreateNoteSchema,
+        UpdateNoteSchema,
+        RenderFormat,
+        GenericResponse,
+        NoteResponse,
+        NoteData,
+        SingleNoteResponse,
+        PublicNoteResponse,
+        PublicNoteData,
+        SinglePublicNoteResponse,
+        NoteListResponse,
+        MoveCategorySchema,
+        MoveCategoryResponse,
+        AttachmentUploadSchema,
+        AttachmentResponse,
+        AttachmentData,
+        SingleAttachmentResponse,
+        AttachmentListResponse,
+        ShareRole,
+        AclEntry,
+        GrantAccessSchema,
+        CreateShareLinkSchema,
+        AccessResponse,
+        ShareLinkResponse,
+        ShareLinkData,
+        SingleShareLinkResponse,
+        CreateWorkspaceSchema,
+        WorkspaceResponse,
+        WorkspaceData,
+        SingleWorkspaceResponse,
+        WorkspaceListResponse,
+        AuditAction,
+        AuditRecordResponse,
+        AuditListResponse,
+    )),
+    tags(
+        (name = "notes", description = "Note management"),
+        (name = "attachments", description = "Files attached to notes"),
+        (name = "sharing", description = "Sharing notes with other users and through links"),
+        (name = "public", description = "Published notes, readable without authentication"),
+        (name = "workspaces", description = "Workspace administration"),
+        (name = "audit", description = "Audit log of the changes made to notes"),
+        (name = "health", description = "Health checks"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Path of the specification rendered by the Swagger UI.
+pub const OPENAPI_PATH: &str = "/api/openapi.json";
+
+/// Swagger UI configuration pointing the page at the specification served at `OPENAPI_PATH`.
+///
+/// The Swagger UI assets are embedded in the binary, so serving the page needs no network access.
+pub fn swagger_ui_config() -> Arc<Config<'static>> {
+    Arc::new(Config::from(OPENAPI_PATH))
+}
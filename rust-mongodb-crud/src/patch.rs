@@ -0,0 +1,143 @@
+use crate::errors::Error::{InvalidPatchError, PatchParseError};
+use crate::model::NoteModel;
+use crate::schema::{CreateNoteSchema, UpdateNoteSchema};
+use crate::Result;
+use json_patch::PatchOperation;
+use serde_json::{Map, Value};
+
+/// Content type of a JSON Merge Patch (RFC 7396) request body.
+pub const MERGE_PATCH_CONTENT_TYPE: &str = "application/merge-patch+json";
+
+/// Content type of a JSON Patch (RFC 6902) request body.
+pub const JSON_PATCH_CONTENT_TYPE: &str = "application/json-patch+json";
+
+/// Fields of a note that can be changed by a patch.
+const EDITABLE_FIELDS: [&str; 5] = ["title", "content", "category", "published", "publishAt"];
+
+/// Change requested by a `PATCH` request, selected by its `Content-Type`.
+#[derive(Debug, Clone)]
+pub enum NotePatch {
+    /// `application/json`: fields present in the body are set, absent fields are left unchanged.
+    Fields(UpdateNoteSchema),
+    /// `application/merge-patch+json`: like `Fields`, but `null` removes a field.
+    Merge(Value),
+    /// `application/json-patch+json`: a list of `test`, `add`, `remove` and `replace` operations
+    /// applied all or nothing.
+    Json(Vec<PatchOperation>),
+}
+
+impl NotePatch {
+    /// Parses a request body according to its content type.
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - Value of the `Content-Type` header, parameters such as `charset` are ignored.
+    /// * `body` - The raw request body.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `PatchParseError` if the body is not a valid patch document of the given type.
+    /// Returns `Ok(None)` if the content type is not a patch format.
+    pub fn parse(content_type: &str, body: &[u8]) -> Result<Option<Self>> {
+        let essence = content_type.split(';').next().unwrap_or("").trim();
+
+        let patch = if essence.eq_ignore_ascii_case(MERGE_PATCH_CONTENT_TYPE) {
+            let value: Value =
+                serde_json::from_slice(body).map_err(|e| PatchParseError(e.to_string()))?;
+            if !value.is_object() {
+                return Err(PatchParseError(
+                    "A merge patch must be a JSON object".to_string(),
+                ));
+            }
+            NotePatch::Merge(value)
+        } else if essence.eq_ignore_ascii_case(JSON_PATCH_CONTENT_TYPE) {
+            let operations: Vec<PatchOperation> =
+                serde_json::from_slice(body).map_err(|e| PatchParseError(e.to_string()))?;
+
+            // Only the operations that make sense on a flat note are supported
+            for operation in &operations {
+                if let PatchOperation::Move(_) | PatchOperation::Copy(_) = operation {
+                    return Err(PatchParseError(format!(
+                        "Unsupported JSON Patch operation: {}",
+                        operation_name(operation)
+                    )));
+                }
+            }
+            NotePatch::Json(operations)
+        } else {
+            return Ok(None);
+        };
+
+        Ok(Some(patch))
+    }
+
+    /// Applies the patch to the editable fields of `note`.
+    ///
+    /// Returns the complete set of fields the note should have afterwards, where `None` means
+    /// the field is removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an `InvalidPatchError` if a `test` operation fails, an operation targets a missing
+    /// or non-editable field, or the patched note is not valid, e.g. because its title was removed.
+    pub fn apply(&self, note: &NoteModel) -> Result<CreateNoteSchema> {
+        let current = CreateNoteSchema {
+            title: note.title.to_owned(),
+            content: note.content.to_owned(),
+            category: note.category.to_owned(),
+            published: note.published,
+            publish_at: note.publishAt.map(|publish_at| publish_at.to_chrono()),
+        };
+
+        // Patch the JSON representation of the note, then read the result back
+        let mut value =
+            serde_json::to_value(&current).map_err(|e| InvalidPatchError(e.to_string()))?;
+        match self {
+            NotePatch::Fields(body) => {
+                return Ok(CreateNoteSchema {
+                    title: body.title.to_owned().unwrap_or(current.title),
+                    content: body.content.to_owned().unwrap_or(current.content),
+                    category: body.category.to_owned().or(current.category),
+                    published: body.published.or(current.published),
+                    publish_at: body.publish_at.or(current.publish_at),
+                });
+            }
+            NotePatch::Merge(patch) => json_patch::merge(&mut value, patch),
+            NotePatch::Json(operations) => json_patch::patch(&mut value, operations)
+                .map_err(|e| InvalidPatchError(e.to_string()))?,
+        }
+
+        let fields = value.as_object().ok_or_else(|| {
+            InvalidPatchError("The patched note must be a JSON object".to_string())
+        })?;
+        check_fields(fields)?;
+
+        serde_json::from_value(value).map_err(|e| InvalidPatchError(e.to_string()))
+    }
+}
+
+/// Returns an error if `fields` contains a field that cannot be edited.
+fn check_fields(fields: &Map<String, Value>) -> Result<()> {
+    match fields
+        .keys()
+        .find(|field| !EDITABLE_FIELDS.contains(&field.as_str()))
+    {
+        Some(field) => Err(InvalidPatchError(format!(
+            "Field {} cannot be edited",
+            field
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Name of a JSON Patch operation as it appears in the `op` member.
+fn operation_name(operation: &PatchOperation) -> &'static str {
+    match operation {
+        PatchOperation::Add(_) => "add",
+        PatchOperation::Remove(_) => "remove",
+        PatchOperation::Replace(_) => "replace",
+        PatchOperation::Move(_) => "move",
+        PatchOperation::Copy(_) => "copy",
+        PatchOperation::Test(_) => "test",
+    }
+}
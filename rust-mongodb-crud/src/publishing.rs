@@ -0,0 +1,128 @@
+use crate::errors::duplicate_key_index;
+use crate::errors::Error::{self, MongoDuplicateError};
+use crate::store::Store;
+use chrono::Utc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Default interval between two runs of the scheduled publisher.
+pub const PUBLISH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Name of the unique index on note slugs.
+pub const SLUG_INDEX: &str = "slug_unique";
+
+/// Number of slugs tried for a new note before a conflict is returned to the caller.
+pub const SLUG_ATTEMPTS: usize = 5;
+
+/// Slug used for titles without any letter or digit.
+const FALLBACK_SLUG: &str = "note";
+
+/// Derives the URL slug of a note from its title.
+///
+/// ASCII letters and digits are kept in lowercase, every other run of characters becomes a
+/// single `-`.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::publishing::slugify;
+/// assert_eq!(slugify("Release notes: v1.2!"), "release-notes-v1-2");
+/// assert_eq!(slugify("???"), "note");
+/// ```
+pub fn slugify(title: &str) -> String {
+    let mut slug = String::with_capacity(title.len());
+
+    for c in title.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        return FALLBACK_SLUG.to_string();
+    }
+
+    slug.to_string()
+}
+
+/// Picks the first slug based on `base` that is not in `taken`.
+///
+/// The base slug is used as is when it is free, otherwise `-2`, `-3` and so on are appended.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::publishing::unique_slug;
+/// let taken = vec!["groceries".to_string(), "groceries-2".to_string()];
+///
+/// assert_eq!(unique_slug("groceries", &taken), "groceries-3");
+/// assert_eq!(unique_slug("errands", &taken), "errands");
+/// ```
+pub fn unique_slug(base: &str, taken: &[String]) -> String {
+    (1..)
+        .map(|n| match n {
+            1 => base.to_string(),
+            n => format!("{}-{}", base, n),
+        })
+        .find(|slug| !taken.contains(slug))
+        .unwrap()
+}
+
+/// Returns whether `error` was caused by another note holding the slug of the written note.
+///
+/// Two notes created at the same time with the same title both derive the same free slug, and
+/// the unique slug index rejects the second one.
+pub fn is_slug_conflict(error: &Error) -> bool {
+    matches!(error, MongoDuplicateError(e) if duplicate_key_index(e) == Some(SLUG_INDEX))
+}
+
+/// Reads the publisher interval from `PUBLISH_INTERVAL_SECS`, defaulting to [`PUBLISH_INTERVAL`].
+///
+/// # Panics
+///
+/// Panics if the variable is set but is not a positive number of seconds.
+pub fn interval_from_env() -> Duration {
+    match std::env::var("PUBLISH_INTERVAL_SECS") {
+        Ok(seconds) => match seconds.trim().parse::<u64>() {
+            Ok(seconds) if seconds > 0 => Duration::from_secs(seconds),
+            _ => panic!("PUBLISH_INTERVAL_SECS is invalid: {}", seconds),
+        },
+        Err(_) => PUBLISH_INTERVAL,
+    }
+}
+
+/// Spawns the background task publishing notes whose `publishAt` time has passed.
+///
+/// The task checks the store every `interval` until the runtime shuts down. Failures are
+/// reported and retried on the next run.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rust_mongodb_crud::{db::DB, publishing};
+/// # use std::sync::Arc;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = DB::init().await?;
+///
+/// publishing::spawn_publisher(Arc::new(db), publishing::PUBLISH_INTERVAL);
+/// # Ok(())
+/// # }
+/// ```
+pub fn spawn_publisher(store: Store, interval: Duration) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+
+        loop {
+            ticker.tick().await;
+
+            match store.publish_due_notes(Utc::now()).await {
+                Ok(0) => {}
+                Ok(published) => println!("Published {} scheduled note(s)", published),
+                Err(e) => eprintln!("Failed to publish scheduled notes: {}", e),
+            }
+        }
+    })
+}
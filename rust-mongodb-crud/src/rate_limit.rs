@@ -0,0 +1,348 @@
+use crate::{errors::Error, errors::Error::*};
+use chrono::prelude::*;
+use mongodb::bson::{doc, Document};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument};
+use mongodb::{Collection, Database};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use warp::http::header::{HeaderValue, RETRY_AFTER};
+use warp::http::Method;
+use warp::hyper::Body;
+use warp::{reject, reply::Response, Filter, Rejection, Reply};
+
+/// Name of the collection holding shared rate limit counters.
+pub const RATE_LIMIT_COLLECTION: &str = "rate_limits";
+
+/// Number of in-memory buckets kept before idle ones are purged.
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// Groups of routes sharing the same rate limit.
+///
+/// Routes are grouped by the HTTP method of the request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RouteGroup {
+    /// Routes reading notes.
+    Read,
+    /// Routes creating, editing or deleting notes.
+    Write,
+}
+
+impl RouteGroup {
+    /// Returns the group of a request made with `method`.
+    pub fn from_method(method: &Method) -> Self {
+        match *method {
+            Method::GET | Method::HEAD | Method::OPTIONS => RouteGroup::Read,
+            _ => RouteGroup::Write,
+        }
+    }
+
+    /// Short name of the group used in counter keys.
+    fn name(&self) -> &'static str {
+        match self {
+            RouteGroup::Read => "read",
+            RouteGroup::Write => "write",
+        }
+    }
+}
+
+/// Number of requests allowed within a time window.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Maximum number of requests, which is also the burst size.
+    pub requests: u32,
+    /// Window within which `requests` are allowed.
+    pub per: Duration,
+}
+
+impl RateLimit {
+    /// Number of tokens added to a bucket per second.
+    fn refill_rate(&self) -> f64 {
+        self.requests as f64 / self.per.as_secs_f64()
+    }
+}
+
+impl FromStr for RateLimit {
+    type Err = String;
+
+    /// Parses a limit in the `<requests>/<seconds>` format, e.g. `120/60`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || format!("invalid rate limit: {} (expected <requests>/<seconds>)", s);
+        let (requests, seconds) = s.split_once('/').ok_or_else(invalid)?;
+        let requests: u32 = requests.trim().parse().map_err(|_| invalid())?;
+        let seconds: u64 = seconds.trim().parse().map_err(|_| invalid())?;
+
+        if requests == 0 || seconds == 0 {
+            return Err(invalid());
+        }
+
+        Ok(RateLimit {
+            requests,
+            per: Duration::from_secs(seconds),
+        })
+    }
+}
+
+/// Outcome of a rate limit check, reported through `RateLimit-*` headers.
+#[derive(Debug, Clone)]
+pub struct RateLimitInfo {
+    /// Maximum number of requests in the window.
+    pub limit: u32,
+    /// Requests left in the current window.
+    pub remaining: u32,
+    /// Seconds until the limit resets.
+    pub reset: u64,
+    /// Seconds until the next request is allowed, set when the limit is exceeded.
+    pub retry_after: Option<u64>,
+}
+
+impl RateLimitInfo {
+    /// Adds the `RateLimit-*` headers, and `Retry-After` when limited, to a response.
+    pub fn apply_headers(&self, response: &mut Response) {
+        let headers = response.headers_mut();
+        headers.insert("ratelimit-limit", HeaderValue::from(self.limit));
+        headers.insert("ratelimit-remaining", HeaderValue::from(self.remaining));
+        headers.insert("ratelimit-reset", HeaderValue::from(self.reset));
+        if let Some(retry_after) = self.retry_after {
+            headers.insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+    }
+}
+
+/// A reply decorated with `RateLimit-*` headers.
+pub struct RateLimitedReply<R> {
+    /// Outcome of the rate limit check.
+    info: RateLimitInfo,
+    /// The wrapped reply.
+    reply: R,
+}
+
+impl<R: Reply> Reply for RateLimitedReply<R> {
+    fn into_response(self) -> warp::http::Response<Body> {
+        let mut response = self.reply.into_response();
+        self.info.apply_headers(&mut response);
+        response
+    }
+}
+
+/// Wraps a reply so it carries the `RateLimit-*` headers of the passed check.
+pub fn with_headers<R: Reply>(info: RateLimitInfo, reply: R) -> RateLimitedReply<R> {
+    RateLimitedReply { info, reply }
+}
+
+/// Token bucket tracked in memory for a single client and route group.
+#[derive(Debug)]
+struct Bucket {
+    /// Tokens currently available.
+    tokens: f64,
+    /// Last time the bucket was refilled.
+    updated: Instant,
+    /// Window of the limit of the bucket, after which an idle bucket is full again.
+    per: Duration,
+}
+
+/// Storage used to count requests.
+#[derive(Clone, Debug)]
+enum Backend {
+    /// Token buckets local to this instance.
+    Memory(Arc<Mutex<HashMap<String, Bucket>>>),
+    /// Fixed window counters shared by every instance through MongoDB.
+    Mongo(Collection<Document>),
+}
+
+/// Token bucket rate limiter keyed by client IP.
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    /// Limit applied to read routes.
+    read: RateLimit,
+    /// Limit applied to write routes.
+    write: RateLimit,
+    /// Storage used to count requests.
+    backend: Backend,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter keeping its buckets in memory.
+    pub fn in_memory(read: RateLimit, write: RateLimit) -> Self {
+        RateLimiter {
+            read,
+            write,
+            backend: Backend::Memory(Arc::new(Mutex::new(HashMap::new()))),
+        }
+    }
+
+    /// Creates a rate limiter sharing fixed window counters through MongoDB.
+    pub fn shared(read: RateLimit, write: RateLimit, database: &Database) -> Self {
+        RateLimiter {
+            read,
+            write,
+            backend: Backend::Mongo(database.collection(RATE_LIMIT_COLLECTION)),
+        }
+    }
+
+    /// Initializes a rate limiter from environment variables.
+    ///
+    /// * `RATE_LIMIT_READ` - limit of read routes as `<requests>/<seconds>` (default: `120/60`).
+    /// * `RATE_LIMIT_WRITE` - limit of write routes as `<requests>/<seconds>` (default: `30/60`).
+    /// * `RATE_LIMIT_BACKEND` - `memory` (default) or `mongo` to share counters between instances.
+    ///
+    /// # Panics
+    ///
+    /// Panics if any of the variables holds an invalid value.
+    pub fn init(database: &Database) -> Self {
+        let limit = |name: &str, default: &str| -> RateLimit {
+            std::env::var(name)
+                .unwrap_or_else(|_| default.to_string())
+                .parse()
+                .unwrap_or_else(|e| panic!("{} is invalid: {}", name, e))
+        };
+        let read = limit("RATE_LIMIT_READ", "120/60");
+        let write = limit("RATE_LIMIT_WRITE", "30/60");
+
+        match std::env::var("RATE_LIMIT_BACKEND").as_deref() {
+            Ok("mongo") => RateLimiter::shared(read, write, database),
+            Ok("memory") | Err(_) => RateLimiter::in_memory(read, write),
+            Ok(other) => panic!("RATE_LIMIT_BACKEND is invalid: {}", other),
+        }
+    }
+
+    /// Returns the limit configured for a route group.
+    fn limit(&self, group: RouteGroup) -> RateLimit {
+        match group {
+            RouteGroup::Read => self.read,
+            RouteGroup::Write => self.write,
+        }
+    }
+
+    /// Counts a request of `client` against the limit of `group`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `RateLimitError` if the limit is exceeded, or a `MongoQueryError` if the shared
+    /// counter cannot be updated.
+    pub async fn check(&self, group: RouteGroup, client: &str) -> Result<RateLimitInfo, Error> {
+        let limit = self.limit(group);
+        let key = format!("{}:{}", group.name(), client);
+
+        let info = match &self.backend {
+            Backend::Memory(buckets) => Self::take_token(buckets, &key, limit),
+            Backend::Mongo(collection) => Self::count_shared(collection, &key, limit).await?,
+        };
+
+        if info.retry_after.is_some() {
+            return Err(RateLimitError(info));
+        }
+
+        Ok(info)
+    }
+
+    /// Takes a token from the in-memory bucket of `key`.
+    fn take_token(
+        buckets: &Mutex<HashMap<String, Bucket>>,
+        key: &str,
+        limit: RateLimit,
+    ) -> RateLimitInfo {
+        let now = Instant::now();
+        let capacity = limit.requests as f64;
+        let rate = limit.refill_rate();
+        let mut buckets = buckets.lock().unwrap();
+
+        // Forget buckets that had time to refill completely, each within the window of its own
+        // route group
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, bucket| now.duration_since(bucket.updated) < bucket.per);
+        }
+
+        let bucket = buckets.entry(key.to_string()).or_insert(Bucket {
+            tokens: capacity,
+            updated: now,
+            per: limit.per,
+        });
+
+        // Refill the bucket for the time elapsed since the last request
+        let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+        bucket.updated = now;
+
+        let retry_after = if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            None
+        } else {
+            Some(((1.0 - bucket.tokens) / rate).ceil() as u64)
+        };
+
+        RateLimitInfo {
+            limit: limit.requests,
+            remaining: bucket.tokens.floor() as u32,
+            reset: ((capacity - bucket.tokens) / rate).ceil() as u64,
+            retry_after,
+        }
+    }
+
+    /// Increments the shared counter of `key` for the current window.
+    async fn count_shared(
+        collection: &Collection<Document>,
+        key: &str,
+        limit: RateLimit,
+    ) -> Result<RateLimitInfo, Error> {
+        let window = limit.per.as_secs() as i64;
+        let now = Utc::now().timestamp();
+        let window_start = now - now.rem_euclid(window);
+        let window_end = window_start + window;
+
+        // Atomically create or increment the counter of the current window. Counters expire
+        // through the TTL index on `expiresAt`.
+        let options = FindOneAndUpdateOptions::builder()
+            .upsert(true)
+            .return_document(ReturnDocument::After)
+            .build();
+        let expires_at = Utc.timestamp_opt(window_end, 0).unwrap();
+        let counter = collection
+            .find_one_and_update(
+                doc! {"_id": format!("{}:{}", key, window_start)},
+                doc! {"$inc": {"count": 1}, "$setOnInsert": {"expiresAt": expires_at}},
+                options,
+            )
+            .await
+            .map_err(MongoQueryError)?;
+
+        let count = counter
+            .and_then(|counter| counter.get_i32("count").ok())
+            .unwrap_or(1)
+            .max(0) as u32;
+        let reset = (window_end - now) as u64;
+
+        Ok(RateLimitInfo {
+            limit: limit.requests,
+            remaining: limit.requests.saturating_sub(count),
+            reset,
+            retry_after: (count > limit.requests).then_some(reset),
+        })
+    }
+}
+
+/// Checks the request against the limit of its route group.
+///
+/// Requests are keyed by the client IP. The `X-User-Id` header is not verified, so keying on it
+/// would let a client get a fresh quota by sending another value with every request. Rejects with a `RateLimitError` when the limit is exceeded, which `handle_rejection` turns into
+/// a `429 Too Many Requests` response.
+pub fn rate_limit(
+    limiter: RateLimiter,
+) -> impl Filter<Extract = (RateLimitInfo,), Error = Rejection> + Clone {
+    warp::method().and(warp::addr::remote()).and_then(
+        move |method: Method, addr: Option<SocketAddr>| {
+            let limiter = limiter.clone();
+            async move {
+                let group = RouteGroup::from_method(&method);
+                let client = match addr {
+                    Some(addr) => format!("ip:{}", addr.ip()),
+                    None => "unknown".to_string(),
+                };
+
+                limiter.check(group, &client).await.map_err(reject::custom)
+            }
+        },
+    )
+}
@@ -0,0 +1,112 @@
+use crate::response::NoteResponse;
+use ammonia::Builder;
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use pulldown_cmark::{html, Options, Parser};
+use std::borrow::Cow;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+
+/// Number of rendered notes kept in memory.
+pub const HTML_CACHE_CAPACITY: usize = 1024;
+
+/// HTML rendered from a version of a note.
+#[derive(Clone, Debug)]
+struct CachedHtml {
+    /// `updatedAt` of the note the HTML was rendered from.
+    updated_at: DateTime<Utc>,
+    /// Sanitized HTML.
+    html: String,
+}
+
+/// Renders note content from Markdown to sanitized HTML.
+///
+/// Content is parsed as CommonMark with the GitHub Flavored Markdown tables, task lists and
+/// strikethrough extensions. The HTML is sanitized with an allow list, so scripts, event
+/// handlers and `javascript:` links in note content never reach the browser.
+#[derive(Clone)]
+pub struct MarkdownRenderer {
+    /// Sanitizer applied to the rendered HTML.
+    sanitizer: Arc<Builder<'static>>,
+    /// Rendered HTML by note ID, valid for the recorded `updatedAt`, in order of last access.
+    cache: Arc<Mutex<LruCache<String, CachedHtml>>>,
+}
+
+impl MarkdownRenderer {
+    /// Creates a renderer caching the HTML of up to `capacity` notes, and of at least one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        let mut sanitizer = Builder::default();
+
+        // Keep the checkboxes of task lists, but no other kind of input
+        sanitizer
+            .add_tags(["input"])
+            .add_tag_attributes("input", ["type", "checked", "disabled"])
+            .attribute_filter(|element, attribute, value| {
+                if element == "input" && attribute == "type" && value != "checkbox" {
+                    return None;
+                }
+                Some(Cow::Borrowed(value))
+            });
+
+        MarkdownRenderer {
+            sanitizer: Arc::new(sanitizer),
+            cache: Arc::new(Mutex::new(LruCache::new(capacity))),
+        }
+    }
+
+    /// Renders Markdown to sanitized HTML.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use rust_mongodb_crud::render::MarkdownRenderer;
+    /// let renderer = MarkdownRenderer::new(16);
+    /// let html = renderer.render("**bold** <script>alert(1)</script>");
+    ///
+    /// assert_eq!(html, "<p><strong>bold</strong> </p>\n");
+    /// ```
+    pub fn render(&self, markdown: &str) -> String {
+        let options =
+            Options::ENABLE_TABLES | Options::ENABLE_TASKLISTS | Options::ENABLE_STRIKETHROUGH;
+
+        let mut unsafe_html = String::new();
+        html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+
+        self.sanitizer.clean(&unsafe_html).to_string()
+    }
+
+    /// Sets `contentHtml` of a note to its rendered content.
+    ///
+    /// The HTML is cached until the note is updated.
+    pub fn render_note(&self, note: &mut NoteResponse) {
+        let cached = self.cache.lock().unwrap().get(&note.id).cloned();
+        let html = match cached {
+            Some(cached) if cached.updated_at == note.updatedAt => cached.html,
+            _ => {
+                let html = self.render(&note.content);
+                self.store(&note.id, note.updatedAt, &html);
+                html
+            }
+        };
+
+        note.contentHtml = Some(html);
+    }
+
+    /// Caches the HTML of a note version, evicting the least recently used note when full.
+    fn store(&self, id: &str, updated_at: DateTime<Utc>, html: &str) {
+        self.cache.lock().unwrap().put(
+            id.to_string(),
+            CachedHtml {
+                updated_at,
+                html: html.to_string(),
+            },
+        );
+    }
+}
+
+impl Default for MarkdownRenderer {
+    fn default() -> Self {
+        MarkdownRenderer::new(HTML_CACHE_CAPACITY)
+    }
+}
@@ -1,8 +1,10 @@
+use crate::schema::{AclEntry, AuditAction};
 use chrono::{DateTime, Utc};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 /// Represents a generic response structure.
-#[derive(Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct GenericResponse {
     /// Status of the response.
     pub status: String,
@@ -10,9 +12,18 @@ pub struct GenericResponse {
     pub message: String,
 }
 
+/// Represents a response structure for a category move.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MoveCategoryResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Number of notes moved.
+    pub moved: u64,
+}
+
 /// Represents a response structure for a single note.
 #[allow(non_snake_case)]
-#[derive(Serialize, Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NoteResponse {
     /// Unique identifier for the note.
     pub id: String,
@@ -20,10 +31,21 @@ pub struct NoteResponse {
     pub title: String,
     /// Content of the note.
     pub content: String,
+    /// Content rendered from Markdown to sanitized HTML, only present when requested with `render=html`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contentHtml: Option<String>,
     /// Category of the note.
     pub category: String,
+    /// Identifier of the user owning the note, if any.
+    pub owner: Option<String>,
     /// Indicates if the note is published or not.
     pub published: bool,
+    /// Date and time when the note is scheduled to be published, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publishAt: Option<DateTime<Utc>>,
+    /// URL slug serving the note on `/api/public/notes/{slug}` once published.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
     /// Date and time when the note was created.
     pub createdAt: DateTime<Utc>,
     /// Date and time when the note was last updated.
@@ -31,14 +53,14 @@ pub struct NoteResponse {
 }
 
 /// Represents the data part of a note response.
-#[derive(Serialize, Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NoteData {
     /// Contains the note details in NoteResponse format.
     pub note: NoteResponse,
 }
 
 /// Represents a response structure for a single note, including status and data.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SingleNoteResponse {
     /// Status of the response.
     pub status: String,
@@ -46,8 +68,79 @@ pub struct SingleNoteResponse {
     pub data: NoteData,
 }
 
+/// Represents a published note as served to anonymous readers.
+///
+/// Leaves out the owner of the note.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PublicNoteResponse {
+    /// Unique identifier for the note.
+    pub id: String,
+    /// Title of the note.
+    pub title: String,
+    /// Content of the note.
+    pub content: String,
+    /// Content rendered from Markdown to sanitized HTML, only present when requested with `render=html`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contentHtml: Option<String>,
+    /// Category of the note.
+    pub category: String,
+    /// Indicates if the note is published, always `true` for a note served publicly.
+    pub published: bool,
+    /// URL slug serving the note on `/api/public/notes/{slug}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    /// Date and time when the note was created.
+    pub createdAt: DateTime<Utc>,
+    /// Date and time when the note was last updated.
+    pub updatedAt: DateTime<Utc>,
+}
+
+impl From<NoteResponse> for PublicNoteResponse {
+    fn from(note: NoteResponse) -> Self {
+        PublicNoteResponse {
+            id: note.id,
+            title: note.title,
+            content: note.content,
+            contentHtml: note.contentHtml,
+            category: note.category,
+            published: note.published,
+            slug: note.slug,
+            createdAt: note.createdAt,
+            updatedAt: note.updatedAt,
+        }
+    }
+}
+
+/// Represents the data part of a published note response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PublicNoteData {
+    /// Contains the note details in PublicNoteResponse format.
+    pub note: PublicNoteResponse,
+}
+
+/// Represents a response structure for a published note, including status and data.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SinglePublicNoteResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Data part of the response containing note details.
+    pub data: PublicNoteData,
+}
+
+impl From<SingleNoteResponse> for SinglePublicNoteResponse {
+    fn from(response: SingleNoteResponse) -> Self {
+        SinglePublicNoteResponse {
+            status: response.status,
+            data: PublicNoteData {
+                note: response.data.note.into(),
+            },
+        }
+    }
+}
+
 /// Represents a response structure for a list of notes.
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct NoteListResponse {
     /// Status of the response.
     pub status: String,
@@ -56,3 +149,173 @@ pub struct NoteListResponse {
     /// List of NoteResponse objects.
     pub notes: Vec<NoteResponse>,
 }
+
+/// Represents a response structure for a file attached to a note.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttachmentResponse {
+    /// Unique identifier for the attachment.
+    pub id: String,
+    /// Identifier of the note the file is attached to.
+    pub noteId: String,
+    /// Name of the uploaded file.
+    pub filename: String,
+    /// Content type of the file.
+    pub contentType: String,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// Hex encoded SHA-256 checksum of the file.
+    pub sha256: String,
+    /// Date and time when the file was uploaded.
+    pub uploadedAt: DateTime<Utc>,
+}
+
+/// Represents the data part of an attachment response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttachmentData {
+    /// Contains the attachment details in AttachmentResponse format.
+    pub attachment: AttachmentResponse,
+}
+
+/// Represents a response structure for a single attachment, including status and data.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SingleAttachmentResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Data part of the response containing attachment details.
+    pub data: AttachmentData,
+}
+
+/// Represents a response structure for the attachments of a note.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AttachmentListResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Number of attachments in the response.
+    pub results: usize,
+    /// List of AttachmentResponse objects.
+    pub attachments: Vec<AttachmentResponse>,
+}
+
+/// Represents a read-only share link of a note.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ShareLinkResponse {
+    /// Unique identifier for the link, used to revoke it.
+    pub id: String,
+    /// Secret token of the link, only returned when the link is created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Date and time after which the link no longer grants access.
+    pub expiresAt: DateTime<Utc>,
+}
+
+/// Represents the data part of a share link response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ShareLinkData {
+    /// Contains the link details in ShareLinkResponse format.
+    pub link: ShareLinkResponse,
+}
+
+/// Represents a response structure for a single share link, including status and data.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SingleShareLinkResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Data part of the response containing link details.
+    pub data: ShareLinkData,
+}
+
+/// Represents a response structure for the sharing settings of a note.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AccessResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Identifier of the user owning the note, if any.
+    pub owner: Option<String>,
+    /// Users the note is shared with.
+    pub acl: Vec<AclEntry>,
+    /// Share links of the note that have not expired, without their tokens.
+    pub links: Vec<ShareLinkResponse>,
+}
+
+/// Represents a workspace holding its own set of notes.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceResponse {
+    /// Unique identifier for the workspace.
+    pub id: String,
+    /// Display name of the workspace.
+    pub name: String,
+    /// Users allowed to access the notes of the workspace.
+    pub members: Vec<String>,
+    /// Whether the workspace is archived, which makes its notes inaccessible.
+    pub archived: bool,
+    /// Date and time when the workspace was created.
+    pub createdAt: DateTime<Utc>,
+    /// Date and time when the workspace was archived, if it was.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archivedAt: Option<DateTime<Utc>>,
+}
+
+/// Represents the data part of a workspace response.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceData {
+    /// Contains the workspace details in WorkspaceResponse format.
+    pub workspace: WorkspaceResponse,
+}
+
+/// Represents a response structure for a single workspace, including status and data.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SingleWorkspaceResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Data part of the response containing workspace details.
+    pub data: WorkspaceData,
+}
+
+/// Represents a response structure for a list of workspaces.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WorkspaceListResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Number of workspaces in the response.
+    pub results: usize,
+    /// List of workspaces.
+    pub workspaces: Vec<WorkspaceResponse>,
+}
+
+/// Represents a record of the audit log.
+#[allow(non_snake_case)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditRecordResponse {
+    /// Unique identifier of the record.
+    pub id: String,
+    /// Kind of change.
+    pub action: AuditAction,
+    /// Identifier of the user who made the change, if known.
+    pub actor: Option<String>,
+    /// Identifier of the changed note.
+    pub noteId: String,
+    /// Identifier of the request that made the change.
+    pub requestId: Option<String>,
+    /// Values of the changed fields before the change, `null` for fields that were not set.
+    #[schema(value_type = Object)]
+    pub before: serde_json::Value,
+    /// Values of the changed fields after the change, `null` for fields that were removed.
+    #[schema(value_type = Object)]
+    pub after: serde_json::Value,
+    /// Date and time of the change.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Represents a response structure for a page of the audit log.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditListResponse {
+    /// Status of the response.
+    pub status: String,
+    /// Number of records in the response.
+    pub results: usize,
+    /// Records, most recent first.
+    pub records: Vec<AuditRecordResponse>,
+}
@@ -0,0 +1,400 @@
+use crate::attachments::MAX_ATTACHMENT_SIZE;
+use crate::patch::NotePatch;
+use crate::rate_limit::{self, rate_limit, RateLimiter};
+use crate::render::MarkdownRenderer;
+use crate::schema::{AuditQuery, FilterOptions, RenderOptions};
+use crate::store::Store;
+use crate::{
+    audit, errors, handlers, openapi, workspaces, REQUEST_ID_HEADER, USER_HEADER, WORKSPACE_HEADER,
+};
+use std::convert::Infallible;
+use std::sync::Arc;
+use utoipa_swagger_ui::Config;
+use warp::path::FullPath;
+use warp::{http::Method, hyper::body::Bytes, reject, Filter, Rejection, Reply};
+
+/// Builds the complete filter tree of the API.
+///
+/// Note routes are also served under an `/api/workspaces/{id}` prefix selecting the workspace,
+/// e.g. `/api/workspaces/acme/notes`. Workspace administration and the audit log are reserved
+/// to the users listed in `ADMIN_USERS`.
+///
+/// # Arguments
+///
+/// * `store` - The note store the handlers read and write.
+/// * `limiter` - The rate limiter applied to the note routes.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// # use rust_mongodb_crud::{db::DB, rate_limit::RateLimiter, routes::routes};
+/// # use std::sync::Arc;
+/// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// let db = DB::init().await?;
+/// let limiter = RateLimiter::init(&db.database);
+///
+/// warp::serve(routes(Arc::new(db), limiter))
+///     .run(([0, 0, 0, 0], 8080))
+///     .await;
+/// # Ok(())
+/// # }
+/// ```
+pub fn routes(
+    store: Store,
+    limiter: RateLimiter,
+) -> impl Filter<Extract = (impl Reply,), Error = Infallible> + Clone {
+    // Configure Cross-Origin Resource Sharing (CORS) policies
+    let cors = warp::cors()
+        .allow_methods(&[
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_origins(vec!["http://localhost:3000"])
+        .allow_headers(vec![
+            "content-type",
+            "range",
+            USER_HEADER,
+            WORKSPACE_HEADER,
+            REQUEST_ID_HEADER,
+        ])
+        .expose_headers(vec![
+            "accept-ranges",
+            "content-disposition",
+            "content-range",
+            "ratelimit-limit",
+            "ratelimit-remaining",
+            "ratelimit-reset",
+            "retry-after",
+        ])
+        .allow_credentials(true);
+
+    // Define routes for different endpoints
+    let note_router = api().and(warp::path!("notes"));
+    let note_router_id = api().and(warp::path!("notes" / String));
+    let restore_router = api().and(warp::path!("notes" / String / "restore"));
+    let move_router = api().and(warp::path!("notes" / "move-category"));
+    let attachment_router = api().and(warp::path!("notes" / String / "attachments"));
+    let attachment_router_id = api().and(warp::path!("notes" / String / "attachments" / String));
+    let public_router_slug = api().and(warp::path!("public" / "notes" / String));
+    let shared_router = api().and(warp::path!("notes" / "shared-with-me"));
+    let acl_router = api().and(warp::path!("notes" / String / "acl"));
+    let acl_router_user = api().and(warp::path!("notes" / String / "acl" / String));
+    let link_router = api().and(warp::path!("notes" / String / "links"));
+    let link_router_id = api().and(warp::path!("notes" / String / "links" / String));
+    let link_router_token = api().and(warp::path!("links" / String));
+    let audit_router = api().and(warp::path!("audit"));
+    let workspace_router = warp::path!("api" / "admin" / "workspaces");
+    let workspace_router_archive = warp::path!("api" / "admin" / "workspaces" / String / "archive");
+    let health_checker = warp::path!("api" / "healthchecker")
+        .and(warp::get())
+        .and_then(handlers::health_checker_handler);
+    let openapi = warp::path!("api" / "openapi.json")
+        .and(warp::get())
+        .and_then(handlers::openapi_handler);
+    let swagger_ui = warp::path("api")
+        .and(warp::path("docs"))
+        .and(warp::get())
+        .and(warp::path::full())
+        .and(warp::path::tail())
+        .and(with_swagger_ui(openapi::swagger_ui_config()))
+        .and_then(handlers::swagger_ui_handler);
+
+    // Share rendered note content between requests
+    let renderer = MarkdownRenderer::default();
+
+    // Define routes for handling note-related actions
+    let note_routes = note_router
+        .clone()
+        .and(warp::post())
+        .and(warp::body::json())
+        .and(with_user()) // Inject the requesting user into the handler
+        .and(with_workspace(store.clone())) // Inject the note store into the handler
+        .and_then(handlers::create_note_handler)
+        .or(note_router
+            .clone()
+            .and(warp::get())
+            .and(warp::query::<FilterOptions>())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and(with_renderer(renderer.clone()))
+            .and_then(handlers::notes_list_handler))
+        .or(move_router
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::move_category_handler));
+
+    let note_routes_id = note_router_id
+        .clone()
+        .and(warp::put())
+        .and(warp::body::json())
+        .and(with_user())
+        .and(with_workspace(store.clone()))
+        .and_then(handlers::replace_note_handler)
+        .or(note_router_id
+            .clone()
+            .and(warp::patch())
+            .and(with_patch()) // Parse the body according to its content type
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::edit_note_handler))
+        .or(note_router_id
+            .clone()
+            .and(warp::get())
+            .and(warp::query::<RenderOptions>())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and(with_renderer(renderer.clone()))
+            .and_then(handlers::get_note_handler))
+        .or(note_router_id
+            .clone()
+            .and(warp::delete())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::delete_note_handler))
+        .or(restore_router
+            .and(warp::post())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::restore_note_handler));
+
+    // Define routes for handling files attached to notes
+    let attachment_routes = attachment_router
+        .clone()
+        .and(warp::post())
+        .and(warp::multipart::form().max_length(MAX_ATTACHMENT_SIZE))
+        .and(with_user())
+        .and(with_workspace(store.clone()))
+        .and_then(handlers::create_attachment_handler)
+        .or(attachment_router
+            .clone()
+            .and(warp::get())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::attachments_list_handler))
+        .or(attachment_router_id
+            .clone()
+            .and(warp::get())
+            .and(warp::header::optional::<String>("range"))
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::download_attachment_handler))
+        .or(attachment_router_id
+            .clone()
+            .and(warp::delete())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::delete_attachment_handler));
+
+    // Define routes for sharing notes with users and through links
+    let sharing_routes = shared_router
+        .clone()
+        .and(warp::get())
+        .and(warp::query::<FilterOptions>())
+        .and(warp::header::<String>(USER_HEADER)) // Only known users have notes shared with them
+        .and(with_workspace(store.clone()))
+        .and(with_renderer(renderer.clone()))
+        .and_then(handlers::shared_notes_handler)
+        .or(acl_router
+            .clone()
+            .and(warp::get())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::access_handler))
+        .or(acl_router_user
+            .clone()
+            .and(warp::put())
+            .and(warp::body::json())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::grant_access_handler))
+        .or(acl_router_user
+            .clone()
+            .and(warp::delete())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::revoke_access_handler))
+        .or(link_router
+            .clone()
+            .and(warp::post())
+            .and(warp::body::json())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::create_share_link_handler))
+        .or(link_router_id
+            .clone()
+            .and(warp::delete())
+            .and(with_user())
+            .and(with_workspace(store.clone()))
+            .and_then(handlers::revoke_share_link_handler))
+        .or(link_router_token
+            .clone()
+            .and(warp::get())
+            .and(warp::query::<RenderOptions>())
+            .and(with_any_workspace(store.clone())) // Links work for anyone holding them
+            .and(with_renderer(renderer.clone()))
+            .and_then(handlers::share_link_note_handler));
+
+    // Define routes serving published notes to anonymous readers
+    let public_routes = public_router_slug
+        .clone()
+        .and(warp::get())
+        .and(warp::query::<RenderOptions>())
+        .and(with_any_workspace(store.clone()))
+        .and(with_renderer(renderer))
+        .and_then(handlers::public_note_handler);
+
+    // Define routes for administering workspaces
+    let admins = workspaces::admins_from_env();
+    let admin_routes = workspace_router
+        .and(warp::get())
+        .and(with_admin(admins.clone())) // Only administrators manage workspaces
+        .and(with_store(store.clone()))
+        .and_then(handlers::workspaces_list_handler)
+        .or(workspace_router
+            .and(warp::post())
+            .and(with_admin(admins.clone()))
+            .and(warp::body::json())
+            .and(with_store(store.clone()))
+            .and_then(handlers::create_workspace_handler))
+        .or(workspace_router_archive
+            .and(warp::post())
+            .and(with_admin(admins.clone()))
+            .and(with_store(store.clone()))
+            .and_then(handlers::archive_workspace_handler))
+        .or(audit_router
+            .and(warp::get())
+            .and(with_admin(admins)) // Only administrators read the audit log
+            .and(warp::query::<AuditQuery>())
+            .and(with_any_workspace(store))
+            .and_then(handlers::audit_log_handler));
+
+    // Count note requests against the rate limit of their route group
+    let limited_routes = rate_limit(limiter)
+        .and(
+            note_routes
+                .with(warp::log("api"))
+                .or(sharing_routes) // Before the note routes, which would read `shared-with-me` as an ID
+                .or(note_routes_id)
+                .or(attachment_routes)
+                .or(public_routes)
+                .or(admin_routes),
+        )
+        .map(rate_limit::with_headers); // Report the remaining quota in `RateLimit-*` headers
+
+    // Combine routes, logging, error recovery, and CORS policies
+    health_checker
+        .or(openapi)
+        .or(swagger_ui)
+        .or(limited_routes)
+        .with(cors) // Apply CORS policies to routes
+        .recover(errors::handle_rejection) // Handle errors and rejections
+}
+
+// Helper function to inject the note store into route handlers
+fn with_store(store: Store) -> impl Filter<Extract = (Store,), Error = Infallible> + Clone {
+    warp::any().map(move || store.clone())
+}
+
+// Helper function to inject the note store of the workspace selected by the request, recording
+// the request ID in the audit log. Only the members of the workspace get through.
+fn with_workspace(store: Store) -> impl Filter<Extract = (Store,), Error = Rejection> + Clone {
+    select_workspace(store, true)
+}
+
+// Helper function to inject the note store of the workspace selected by the request for routes
+// open to non-members: published notes, share links and the audit log of the administrators
+fn with_any_workspace(store: Store) -> impl Filter<Extract = (Store,), Error = Rejection> + Clone {
+    select_workspace(store, false)
+}
+
+fn select_workspace(
+    store: Store,
+    members_only: bool,
+) -> impl Filter<Extract = (Store,), Error = Rejection> + Clone {
+    warp::path::full()
+        .and(warp::header::optional::<String>(WORKSPACE_HEADER))
+        .and(warp::header::optional::<String>(REQUEST_ID_HEADER))
+        .and(with_user())
+        .and_then(
+            move |path: FullPath,
+                  header: Option<String>,
+                  request_id: Option<String>,
+                  user: Option<String>| {
+                let store = store.clone();
+                async move {
+                    let store = match workspaces::select(path.as_str(), header.as_deref()) {
+                        Ok(Some(id)) if members_only => store
+                            .member_workspace(&id, user.as_deref())
+                            .await
+                            .map_err(reject::custom)?,
+                        Ok(Some(id)) => store.workspace(&id).await.map_err(reject::custom)?,
+                        Ok(None) => store,
+                        Err(e) => return Err(reject::custom(e)),
+                    };
+                    let request_id = request_id.unwrap_or_else(audit::new_request_id);
+
+                    Ok(store.for_request(&request_id))
+                }
+            },
+        )
+}
+
+// Helper function to match the `api` path segment and an optional workspace prefix
+fn api() -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    let workspace = warp::path!("workspaces" / String / ..)
+        .map(|_| ())
+        .untuple_one();
+
+    warp::path("api").and(workspace.or(warp::any()).unify())
+}
+
+// Helper function to only let administrators through
+fn with_admin(admins: Vec<String>) -> impl Filter<Extract = (), Error = Rejection> + Clone {
+    with_user()
+        .and_then(move |user: Option<String>| {
+            let result = workspaces::check_admin(user.as_deref(), &admins);
+            async move { result.map_err(reject::custom) }
+        })
+        .untuple_one()
+}
+
+// Helper function to inject the Markdown renderer into route handlers
+fn with_renderer(
+    renderer: MarkdownRenderer,
+) -> impl Filter<Extract = (MarkdownRenderer,), Error = Infallible> + Clone {
+    warp::any().map(move || renderer.clone())
+}
+
+// Helper function to inject the Swagger UI configuration into route handlers
+fn with_swagger_ui(
+    config: Arc<Config<'static>>,
+) -> impl Filter<Extract = (Arc<Config<'static>>,), Error = Infallible> + Clone {
+    warp::any().map(move || config.clone())
+}
+
+// Helper function to inject the requesting user, if any, into route handlers
+fn with_user() -> impl Filter<Extract = (Option<String>,), Error = Rejection> + Clone {
+    warp::header::optional::<String>(USER_HEADER)
+}
+
+// Helper function to parse the body of a PATCH request according to its content type
+fn with_patch() -> impl Filter<Extract = (NotePatch,), Error = Rejection> + Clone {
+    let fields = warp::body::json().map(NotePatch::Fields);
+    let patch = warp::header::<String>("content-type")
+        .and(warp::body::bytes())
+        .and_then(|content_type: String, body: Bytes| async move {
+            match NotePatch::parse(&content_type, &body) {
+                Ok(Some(patch)) => Ok(patch),
+                // Not a patch format, leave the rejection to the JSON body filter
+                Ok(None) => Err(reject::not_found()),
+                Err(e) => Err(reject::custom(e)),
+            }
+        });
+
+    fields.or(patch).unify()
+}
@@ -1,12 +1,40 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+/// Additional representation of the note content to include in responses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RenderFormat {
+    /// Markdown rendered to sanitized HTML in `contentHtml`.
+    Html,
+}
 
 /// Structure defining options for filtering notes.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct FilterOptions {
     /// The page number for pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub page: Option<usize>,
-    /// The maximum number of notes per page.
+    /// The maximum number of notes per page, 10 by default and 100 at most.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub limit: Option<usize>,
+    /// Full-text query matched against the title and content of the notes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub q: Option<String>,
+    /// Set to `html` to include the rendered content of each note.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render: Option<RenderFormat>,
+}
+
+/// Structure defining options for retrieving a single note.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RenderOptions {
+    /// Set to `html` to include the rendered content of the note.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub render: Option<RenderFormat>,
 }
 
 /// Structure defining parameters for note operations.
@@ -17,7 +45,7 @@ pub struct ParamOptions {
 }
 
 /// Schema for creating a new note.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
 pub struct CreateNoteSchema {
     /// The title of the note.
     pub title: String,
@@ -29,10 +57,13 @@ pub struct CreateNoteSchema {
     /// Whether the note is published or not.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub published: Option<bool>,
+    /// Date and time at which the note is published automatically.
+    #[serde(rename = "publishAt", skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<DateTime<Utc>>,
 }
 
 /// Schema for updating an existing note.
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
 pub struct UpdateNoteSchema {
     /// The updated title of the note.
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -46,4 +77,111 @@ pub struct UpdateNoteSchema {
     /// Whether the note should be marked as published or unpublished.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub published: Option<bool>,
+    /// The updated date and time at which the note is published automatically.
+    #[serde(rename = "publishAt", skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<DateTime<Utc>>,
+}
+
+/// Role of a user a note is shared with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ShareRole {
+    /// Can read the note and download its attachments.
+    Viewer,
+    /// Can also update the note and manage its attachments.
+    Editor,
+}
+
+/// Entry of the access control list of a note.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+pub struct AclEntry {
+    /// Identifier of the user the note is shared with.
+    pub user: String,
+    /// Role granted to the user.
+    pub role: ShareRole,
+}
+
+/// Schema for sharing a note with a user.
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
+pub struct GrantAccessSchema {
+    /// Role granted to the user, replacing any previous role.
+    pub role: ShareRole,
+}
+
+/// Schema for creating a read-only share link.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateShareLinkSchema {
+    /// Number of seconds the link stays valid, seven days by default and 30 days at most.
+    #[serde(rename = "expiresIn", skip_serializing_if = "Option::is_none")]
+    pub expires_in: Option<u64>,
+}
+
+/// Schema for moving the notes of a category to another category.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct MoveCategorySchema {
+    /// Category to move the notes out of.
+    pub from: String,
+    /// Category to move the notes into.
+    pub to: String,
+}
+
+/// Schema for creating a workspace.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, ToSchema)]
+pub struct CreateWorkspaceSchema {
+    /// Identifier of the workspace: lowercase letters, digits and dashes, 63 characters at most.
+    pub id: String,
+    /// Display name of the workspace, the identifier by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Users allowed to access the notes of the workspace.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub members: Vec<String>,
+}
+
+/// Kind of change recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    /// Note was created.
+    Create,
+    /// Note was edited, replaced or patched.
+    Update,
+    /// Note was deleted.
+    Delete,
+    /// Deleted note was restored.
+    Restore,
+}
+
+/// Structure defining options for querying the audit log.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    /// The page number for pagination.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+    /// The maximum number of records per page, 10 by default and 100 at most.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<usize>,
+    /// Only return the records of the note with this ID.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+    /// Only return the records of changes made by this user.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub actor: Option<String>,
+    /// Only return the records of changes made at or after this time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    /// Only return the records of changes made before this time.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+}
+
+/// Multipart form uploading an attachment.
+///
+/// Only describes the request body in the OpenAPI specification, the form is read part by part.
+#[derive(Debug, ToSchema)]
+pub struct AttachmentUploadSchema {
+    /// The file to attach. Its name and content type are taken from the part headers.
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
 }
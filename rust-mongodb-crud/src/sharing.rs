@@ -0,0 +1,119 @@
+use crate::errors::Error::InvalidShareError;
+use crate::model::NoteModel;
+use crate::response::{AccessResponse, ShareLinkResponse};
+use crate::Result;
+use chrono::{DateTime, Duration, Utc};
+use rand::RngCore;
+use sha2::{Digest, Sha256};
+
+/// Lifetime of a share link created without `expiresIn`, in seconds.
+pub const DEFAULT_LINK_TTL: u64 = 7 * 24 * 60 * 60;
+
+/// Longest lifetime of a share link, in seconds.
+pub const MAX_LINK_TTL: u64 = 30 * 24 * 60 * 60;
+
+/// Number of random bytes in a share link token.
+const TOKEN_BYTES: usize = 32;
+
+/// Generates the secret token of a new share link.
+pub fn new_token() -> String {
+    let mut token = [0u8; TOKEN_BYTES];
+    rand::thread_rng().fill_bytes(&mut token);
+
+    hex::encode(token)
+}
+
+/// Hashes a share link token for storage and lookup.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::sharing::hash_token;
+/// assert_eq!(
+///     hash_token("hello"),
+///     "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+/// );
+/// ```
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// Computes when a share link created at `now` expires.
+///
+/// # Errors
+///
+/// Returns an `InvalidShareError` if `expires_in` is zero or longer than [`MAX_LINK_TTL`].
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::sharing::link_expiry;
+/// let now = chrono::Utc::now();
+///
+/// assert_eq!(link_expiry(now, Some(60)).unwrap(), now + chrono::Duration::seconds(60));
+/// assert!(link_expiry(now, Some(0)).is_err());
+/// ```
+pub fn link_expiry(now: DateTime<Utc>, expires_in: Option<u64>) -> Result<DateTime<Utc>> {
+    let seconds = expires_in.unwrap_or(DEFAULT_LINK_TTL);
+    if seconds == 0 || seconds > MAX_LINK_TTL {
+        return Err(InvalidShareError(format!(
+            "expiresIn must be between 1 and {} seconds",
+            MAX_LINK_TTL
+        )));
+    }
+
+    Ok(now + Duration::seconds(seconds as i64))
+}
+
+/// Returns an error if `note` has no owner to manage its sharing settings.
+///
+/// Notes without an owner are accessible to everyone, so their access control list and share
+/// links would neither restrict nor grant anything, and nobody could be trusted to manage them.
+///
+/// # Errors
+///
+/// Returns an `InvalidShareError` if the note has no owner.
+pub fn check_owned(note: &NoteModel) -> Result<()> {
+    match &note.owner {
+        None => Err(InvalidShareError(
+            "Notes without an owner are already accessible to everyone".to_string(),
+        )),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Returns an error if `note` cannot be shared with `grantee`.
+///
+/// # Errors
+///
+/// Returns an `InvalidShareError` if `grantee` is the owner of the note.
+pub fn check_grantee(note: &NoteModel, grantee: &str) -> Result<()> {
+    match &note.owner {
+        Some(owner) if owner == grantee => Err(InvalidShareError(
+            "The owner already has full access to the note".to_string(),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Describes the sharing settings of a note, leaving out expired links.
+pub fn access_response(note: &NoteModel, now: DateTime<Utc>) -> AccessResponse {
+    let links = note
+        .shareLinks
+        .iter()
+        .flatten()
+        .filter(|link| link.expiresAt.to_chrono() > now)
+        .map(|link| ShareLinkResponse {
+            id: link.id.to_hex(),
+            token: None,
+            expiresAt: link.expiresAt.to_chrono(),
+        })
+        .collect();
+
+    AccessResponse {
+        status: "success".to_string(),
+        owner: note.owner.to_owned(),
+        acl: note.acl.to_owned().unwrap_or_default(),
+        links,
+    }
+}
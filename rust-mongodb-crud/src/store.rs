@@ -0,0 +1,454 @@
+use crate::attachments::{ByteRange, NewAttachment};
+use crate::db::DB;
+use crate::errors::Error::InvalidQueryError;
+use crate::patch::NotePatch;
+use crate::response::{
+    AccessResponse, AttachmentListResponse, AttachmentResponse, AuditListResponse,
+    NoteListResponse, SingleAttachmentResponse, SingleNoteResponse, SingleShareLinkResponse,
+    SingleWorkspaceResponse, WorkspaceListResponse,
+};
+use crate::schema::{
+    AuditQuery, CreateNoteSchema, CreateWorkspaceSchema, ShareRole, UpdateNoteSchema,
+};
+use crate::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+
+/// Storage backend of the notes served by the API.
+///
+/// `DB` is the MongoDB implementation used in production. Other implementations, such as the
+/// in-memory store of the integration tests, can be plugged into [`crate::routes::routes`].
+///
+/// Operations on a note take the requesting `user` and enforce their
+/// [`Access`](crate::model::Access) to it: notes hidden from the user are reported as missing,
+/// and operations beyond their access fail with a `ForbiddenError`.
+///
+/// A store serves the notes of one workspace. [`NoteStore::member_workspace`] gives the members
+/// of the other workspaces access to their notes.
+///
+/// Every create, update, delete and restore of a note is recorded in the audit log of its
+/// workspace.
+#[async_trait]
+pub trait NoteStore: Send + Sync {
+    /// Returns a store recording `request_id` in the audit records of the changes it makes.
+    fn for_request(&self, request_id: &str) -> Store;
+
+    /// Returns the store serving the notes of a workspace, whoever is asking.
+    ///
+    /// Only serves the routes open to non-members, such as published notes and share links.
+    /// Fails with a `WorkspaceNotFoundError` or `WorkspaceArchivedError` if the workspace does
+    /// not exist or was archived.
+    async fn workspace(&self, id: &str) -> Result<Store>;
+
+    /// Returns the store serving the notes of a workspace to one of its members.
+    ///
+    /// Fails like [`NoteStore::workspace`], or with a `WorkspaceForbiddenError` if `user` is not
+    /// a member of the workspace.
+    async fn member_workspace(&self, id: &str, user: Option<&str>) -> Result<Store>;
+
+    /// Creates a workspace.
+    async fn create_workspace(
+        &self,
+        body: &CreateWorkspaceSchema,
+    ) -> Result<SingleWorkspaceResponse>;
+
+    /// Lists every workspace, including archived ones.
+    async fn list_workspaces(&self) -> Result<WorkspaceListResponse>;
+
+    /// Archives a workspace, making its notes inaccessible.
+    async fn archive_workspace(&self, id: &str) -> Result<Option<SingleWorkspaceResponse>>;
+
+    /// Fetches a page of the notes visible to `user`, optionally matching a full-text query.
+    async fn fetch_notes(
+        &self,
+        limit: i64,
+        page: i64,
+        search: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<NoteListResponse>;
+
+    /// Fetches a page of the notes other users shared with `user`.
+    async fn shared_notes(&self, limit: i64, page: i64, user: &str) -> Result<NoteListResponse>;
+
+    /// Creates a note owned by `owner`.
+    async fn create_note(
+        &self,
+        body: &CreateNoteSchema,
+        owner: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>>;
+
+    /// Retrieves a note by ID.
+    async fn get_note(&self, id: &str, user: Option<&str>) -> Result<Option<SingleNoteResponse>>;
+
+    /// Updates the fields of a note present in `body`.
+    async fn edit_note(
+        &self,
+        id: &str,
+        body: &UpdateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>>;
+
+    /// Replaces the editable fields of a note, removing the ones missing from `body`.
+    async fn replace_note(
+        &self,
+        id: &str,
+        body: &CreateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>>;
+
+    /// Applies a merge patch or JSON Patch to a note atomically.
+    async fn patch_note(
+        &self,
+        id: &str,
+        patch: &NotePatch,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>>;
+
+    /// Deletes a note by ID, together with its attachments.
+    async fn delete_note(&self, id: &str, user: Option<&str>) -> Result<Option<()>>;
+
+    /// Moves the notes of a category the user may write to another category, atomically where
+    /// the backend supports it.
+    async fn move_category(&self, from: &str, to: &str, user: Option<&str>) -> Result<u64>;
+
+    /// Restores a deleted note from the audit log.
+    async fn restore_note(
+        &self,
+        id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>>;
+
+    /// Fetches a page of the audit log, most recent records first.
+    async fn audit_log(&self, query: &AuditQuery) -> Result<AuditListResponse>;
+
+    /// Retrieves a published note by slug.
+    ///
+    /// The note still carries its owner, so it is converted to a `SinglePublicNoteResponse`
+    /// before being served to anonymous readers.
+    async fn get_published_note(&self, slug: &str) -> Result<Option<SingleNoteResponse>>;
+
+    /// Publishes the notes scheduled at or before `now`, returning how many were published.
+    async fn publish_due_notes(&self, now: DateTime<Utc>) -> Result<u64>;
+
+    /// Describes who a note is shared with.
+    ///
+    /// Only owned notes have sharing settings: this and the other sharing operations fail with
+    /// an `InvalidShareError` for notes without an owner.
+    async fn get_access(&self, id: &str, user: Option<&str>) -> Result<Option<AccessResponse>>;
+
+    /// Shares a note with `grantee`, replacing any role they had.
+    async fn grant_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        role: ShareRole,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>>;
+
+    /// Stops sharing a note with `grantee`.
+    async fn revoke_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>>;
+
+    /// Creates a link granting read-only access to a note until `expires_at`.
+    async fn create_share_link(
+        &self,
+        id: &str,
+        expires_at: DateTime<Utc>,
+        user: Option<&str>,
+    ) -> Result<Option<SingleShareLinkResponse>>;
+
+    /// Revokes a share link of a note.
+    async fn revoke_share_link(
+        &self,
+        id: &str,
+        link_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>>;
+
+    /// Retrieves the note a share link token grants access to, unless the link expired.
+    async fn get_shared_note(&self, token: &str) -> Result<Option<SingleNoteResponse>>;
+
+    /// Attaches a file to a note.
+    async fn create_attachment(
+        &self,
+        note_id: &str,
+        attachment: NewAttachment,
+        user: Option<&str>,
+    ) -> Result<Option<SingleAttachmentResponse>>;
+
+    /// Lists the files attached to a note.
+    async fn list_attachments(
+        &self,
+        note_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentListResponse>>;
+
+    /// Retrieves the description of a file attached to a note.
+    async fn get_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentResponse>>;
+
+    /// Reads a range of bytes of an attachment returned by `get_attachment`.
+    async fn read_attachment(
+        &self,
+        attachment: &AttachmentResponse,
+        range: ByteRange,
+    ) -> Result<Vec<u8>>;
+
+    /// Deletes a file attached to a note.
+    async fn delete_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>>;
+}
+
+/// Shared handle to the note store injected into route handlers.
+pub type Store = Arc<dyn NoteStore>;
+
+/// Largest number of items a page may hold.
+pub const MAX_PAGE_SIZE: i64 = 100;
+
+/// Returns the number of items before `page` of `limit` items, counting pages from 1.
+///
+/// # Errors
+///
+/// Returns an `InvalidQueryError` if `page` is lower than 1, if `limit` is not between 1 and
+/// [`MAX_PAGE_SIZE`], or if the page is too far for its offset to be represented.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::store::page_offset;
+/// assert_eq!(page_offset(10, 3).unwrap(), 20);
+/// assert!(page_offset(10, 0).is_err());
+/// assert!(page_offset(1000, 1).is_err());
+/// assert!(page_offset(100, i64::MAX).is_err());
+/// ```
+pub fn page_offset(limit: i64, page: i64) -> Result<u64> {
+    if page < 1 {
+        return Err(InvalidQueryError(format!(
+            "page must be at least 1, got {}",
+            page
+        )));
+    }
+    if !(1..=MAX_PAGE_SIZE).contains(&limit) {
+        return Err(InvalidQueryError(format!(
+            "limit must be between 1 and {}, got {}",
+            MAX_PAGE_SIZE, limit
+        )));
+    }
+
+    (page - 1)
+        .checked_mul(limit)
+        .and_then(|offset| u64::try_from(offset).ok())
+        .ok_or_else(|| InvalidQueryError(format!("page {} is out of range", page)))
+}
+
+#[async_trait]
+impl NoteStore for DB {
+    fn for_request(&self, request_id: &str) -> Store {
+        Arc::new(DB::for_request(self, request_id))
+    }
+
+    async fn workspace(&self, id: &str) -> Result<Store> {
+        Ok(Arc::new(DB::workspace(self, id).await?))
+    }
+
+    async fn member_workspace(&self, id: &str, user: Option<&str>) -> Result<Store> {
+        Ok(Arc::new(DB::member_workspace(self, id, user).await?))
+    }
+
+    async fn create_workspace(
+        &self,
+        body: &CreateWorkspaceSchema,
+    ) -> Result<SingleWorkspaceResponse> {
+        DB::create_workspace(self, body).await
+    }
+
+    async fn list_workspaces(&self) -> Result<WorkspaceListResponse> {
+        DB::list_workspaces(self).await
+    }
+
+    async fn archive_workspace(&self, id: &str) -> Result<Option<SingleWorkspaceResponse>> {
+        DB::archive_workspace(self, id).await
+    }
+
+    async fn fetch_notes(
+        &self,
+        limit: i64,
+        page: i64,
+        search: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<NoteListResponse> {
+        DB::fetch_notes(self, limit, page, search, user).await
+    }
+
+    async fn shared_notes(&self, limit: i64, page: i64, user: &str) -> Result<NoteListResponse> {
+        DB::shared_notes(self, limit, page, user).await
+    }
+
+    async fn create_note(
+        &self,
+        body: &CreateNoteSchema,
+        owner: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        DB::create_note(self, body, owner).await
+    }
+
+    async fn get_note(&self, id: &str, user: Option<&str>) -> Result<Option<SingleNoteResponse>> {
+        DB::get_note(self, id, user).await
+    }
+
+    async fn edit_note(
+        &self,
+        id: &str,
+        body: &UpdateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        DB::edit_note(self, id, body, user).await
+    }
+
+    async fn replace_note(
+        &self,
+        id: &str,
+        body: &CreateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        DB::replace_note(self, id, body, user).await
+    }
+
+    async fn patch_note(
+        &self,
+        id: &str,
+        patch: &NotePatch,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        DB::patch_note(self, id, patch, user).await
+    }
+
+    async fn delete_note(&self, id: &str, user: Option<&str>) -> Result<Option<()>> {
+        DB::delete_note(self, id, user).await
+    }
+
+    async fn move_category(&self, from: &str, to: &str, user: Option<&str>) -> Result<u64> {
+        DB::move_category(self, from, to, user).await
+    }
+
+    async fn restore_note(
+        &self,
+        id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        DB::restore_note(self, id, user).await
+    }
+
+    async fn audit_log(&self, query: &AuditQuery) -> Result<AuditListResponse> {
+        DB::audit_log(self, query).await
+    }
+
+    async fn get_published_note(&self, slug: &str) -> Result<Option<SingleNoteResponse>> {
+        DB::get_published_note(self, slug).await
+    }
+
+    async fn publish_due_notes(&self, now: DateTime<Utc>) -> Result<u64> {
+        DB::publish_due_notes(self, now).await
+    }
+
+    async fn get_access(&self, id: &str, user: Option<&str>) -> Result<Option<AccessResponse>> {
+        DB::get_access(self, id, user).await
+    }
+
+    async fn grant_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        role: ShareRole,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>> {
+        DB::grant_access(self, id, grantee, role, user).await
+    }
+
+    async fn revoke_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>> {
+        DB::revoke_access(self, id, grantee, user).await
+    }
+
+    async fn create_share_link(
+        &self,
+        id: &str,
+        expires_at: DateTime<Utc>,
+        user: Option<&str>,
+    ) -> Result<Option<SingleShareLinkResponse>> {
+        DB::create_share_link(self, id, expires_at, user).await
+    }
+
+    async fn revoke_share_link(
+        &self,
+        id: &str,
+        link_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>> {
+        DB::revoke_share_link(self, id, link_id, user).await
+    }
+
+    async fn get_shared_note(&self, token: &str) -> Result<Option<SingleNoteResponse>> {
+        DB::get_shared_note(self, token).await
+    }
+
+    async fn create_attachment(
+        &self,
+        note_id: &str,
+        attachment: NewAttachment,
+        user: Option<&str>,
+    ) -> Result<Option<SingleAttachmentResponse>> {
+        DB::create_attachment(self, note_id, attachment, user).await
+    }
+
+    async fn list_attachments(
+        &self,
+        note_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentListResponse>> {
+        DB::list_attachments(self, note_id, user).await
+    }
+
+    async fn get_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentResponse>> {
+        DB::get_attachment(self, note_id, attachment_id, user).await
+    }
+
+    async fn read_attachment(
+        &self,
+        attachment: &AttachmentResponse,
+        range: ByteRange,
+    ) -> Result<Vec<u8>> {
+        DB::read_attachment(self, attachment, range).await
+    }
+
+    async fn delete_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>> {
+        DB::delete_attachment(self, note_id, attachment_id, user).await
+    }
+}
@@ -0,0 +1,280 @@
+use crate::errors::Error::MongoQueryError;
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use futures::TryStreamExt;
+use mongodb::bson::Document;
+use mongodb::error::{Result, TRANSIENT_TRANSACTION_ERROR, UNKNOWN_TRANSACTION_COMMIT_RESULT};
+use mongodb::options::{FindOneAndUpdateOptions, FindOptions, UpdateModifications};
+use mongodb::results::{DeleteResult, InsertOneResult, UpdateResult};
+use mongodb::{ClientSession, Collection, Database};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Borrow;
+
+/// Number of times a transaction is run before a transient error is returned to the caller.
+pub const TRANSACTION_ATTEMPTS: usize = 5;
+
+/// Returns whether the deployment behind `database` supports multi-document transactions.
+///
+/// Transactions need a replica set or a sharded cluster. Standalone servers, and servers that
+/// cannot be asked, run units of work without a transaction.
+pub async fn supported(database: &Database) -> bool {
+    match database
+        .run_command(mongodb::bson::doc! {"hello": 1}, None)
+        .await
+    {
+        Ok(hello) => hello.contains_key("setName") || hello.get_str("msg") == Ok("isdbgrid"),
+        Err(_) => false,
+    }
+}
+
+/// Unit of work whose operations run in a MongoDB transaction.
+///
+/// Without transaction support, the operations are applied one after the other as they run.
+/// Created by [`crate::db::DB::transaction`].
+pub struct Session {
+    /// Session holding the transaction, `None` when transactions are not supported.
+    inner: Option<ClientSession>,
+}
+
+impl Session {
+    /// Creates a unit of work running in `session`, or without a transaction if `None`.
+    pub fn new(inner: Option<ClientSession>) -> Self {
+        Session { inner }
+    }
+
+    /// Creates a unit of work applying its operations directly, for use outside transactions.
+    pub fn detached() -> Self {
+        Session { inner: None }
+    }
+
+    /// Returns whether the operations of the unit of work run in a transaction.
+    pub fn is_transactional(&self) -> bool {
+        self.inner.is_some()
+    }
+
+    /// Inserts `doc` into `collection`.
+    pub async fn insert_one<T: Serialize + Send + Sync>(
+        &mut self,
+        collection: &Collection<T>,
+        doc: impl Borrow<T>,
+    ) -> Result<InsertOneResult> {
+        match &mut self.inner {
+            Some(session) => collection.insert_one_with_session(doc, None, session).await,
+            None => collection.insert_one(doc, None).await,
+        }
+    }
+
+    /// Finds the first document of `collection` matching `filter`.
+    pub async fn find_one<T: DeserializeOwned + Unpin + Send + Sync>(
+        &mut self,
+        collection: &Collection<T>,
+        filter: Document,
+    ) -> Result<Option<T>> {
+        match &mut self.inner {
+            Some(session) => {
+                collection
+                    .find_one_with_session(filter, None, session)
+                    .await
+            }
+            None => collection.find_one(filter, None).await,
+        }
+    }
+
+    /// Finds the documents of `collection` matching `filter`.
+    pub async fn find<T: DeserializeOwned + Unpin + Send + Sync>(
+        &mut self,
+        collection: &Collection<T>,
+        filter: Document,
+        options: impl Into<Option<FindOptions>>,
+    ) -> Result<Vec<T>> {
+        match &mut self.inner {
+            Some(session) => {
+                let mut cursor = collection
+                    .find_with_session(filter, options, session)
+                    .await?;
+                cursor.stream(session).try_collect().await
+            }
+            None => collection.find(filter, options).await?.try_collect().await,
+        }
+    }
+
+    /// Counts the documents of `collection` matching `filter`.
+    pub async fn count_documents<T>(
+        &mut self,
+        collection: &Collection<T>,
+        filter: Document,
+    ) -> Result<u64> {
+        match &mut self.inner {
+            Some(session) => {
+                collection
+                    .count_documents_with_session(filter, None, session)
+                    .await
+            }
+            None => collection.count_documents(filter, None).await,
+        }
+    }
+
+    /// Updates the first document of `collection` matching `filter` and returns it.
+    pub async fn find_one_and_update<T: DeserializeOwned + Send + Sync>(
+        &mut self,
+        collection: &Collection<T>,
+        filter: Document,
+        update: impl Into<UpdateModifications>,
+        options: impl Into<Option<FindOneAndUpdateOptions>>,
+    ) -> Result<Option<T>> {
+        match &mut self.inner {
+            Some(session) => {
+                collection
+                    .find_one_and_update_with_session(filter, update, options, session)
+                    .await
+            }
+            None => {
+                collection
+                    .find_one_and_update(filter, update, options)
+                    .await
+            }
+        }
+    }
+
+    /// Updates every document of `collection` matching `filter`.
+    pub async fn update_many<T>(
+        &mut self,
+        collection: &Collection<T>,
+        filter: Document,
+        update: impl Into<UpdateModifications>,
+    ) -> Result<UpdateResult> {
+        match &mut self.inner {
+            Some(session) => {
+                collection
+                    .update_many_with_session(filter, update, None, session)
+                    .await
+            }
+            None => collection.update_many(filter, update, None).await,
+        }
+    }
+
+    /// Deletes the first document of `collection` matching `filter`.
+    pub async fn delete_one<T>(
+        &mut self,
+        collection: &Collection<T>,
+        filter: Document,
+    ) -> Result<DeleteResult> {
+        match &mut self.inner {
+            Some(session) => {
+                collection
+                    .delete_one_with_session(filter, None, session)
+                    .await
+            }
+            None => collection.delete_one(filter, None).await,
+        }
+    }
+}
+
+/// Transaction a unit of work runs in, as driven by [`run`].
+///
+/// Implemented by [`Session`]. Other implementations can inject errors to exercise the retry
+/// logic of [`run`] without a server.
+#[async_trait]
+pub trait Transaction: Send {
+    /// Starts a transaction.
+    async fn start_transaction(&mut self) -> Result<()>;
+
+    /// Makes one attempt at committing the transaction.
+    async fn commit_transaction(&mut self) -> Result<()>;
+
+    /// Aborts the transaction, discarding its writes.
+    async fn abort_transaction(&mut self) -> Result<()>;
+}
+
+#[async_trait]
+impl Transaction for Session {
+    async fn start_transaction(&mut self) -> Result<()> {
+        match &mut self.inner {
+            Some(session) => session.start_transaction(None).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn commit_transaction(&mut self) -> Result<()> {
+        match &mut self.inner {
+            Some(session) => session.commit_transaction().await,
+            None => Ok(()),
+        }
+    }
+
+    async fn abort_transaction(&mut self) -> Result<()> {
+        match &mut self.inner {
+            Some(session) => session.abort_transaction().await,
+            None => Ok(()),
+        }
+    }
+}
+
+/// Runs `work` in a transaction of `transaction`, retrying on transient errors.
+///
+/// `context` holds the data `work` needs, since the returned future may only borrow from its
+/// arguments.
+///
+/// When the transaction fails with a transient error, such as a write conflict with another
+/// transaction, `work` is run again in a new transaction, up to [`TRANSACTION_ATTEMPTS`] times
+/// in total. A commit whose outcome is unknown is retried on its own, as many times. Any other
+/// error aborts the transaction and is returned.
+///
+/// # Errors
+///
+/// Returns the error `work` fails with, or an error if the transaction cannot be started or
+/// committed.
+pub async fn run<T, C, R, F>(transaction: &mut T, context: &C, mut work: F) -> crate::Result<R>
+where
+    T: Transaction,
+    C: Sync + ?Sized,
+    F: for<'a> FnMut(&'a C, &'a mut T) -> BoxFuture<'a, crate::Result<R>>,
+{
+    let mut attempt = 1;
+    loop {
+        transaction
+            .start_transaction()
+            .await
+            .map_err(MongoQueryError)?;
+        let result = match work(context, transaction).await {
+            Ok(value) => commit(transaction)
+                .await
+                .map(|()| value)
+                .map_err(MongoQueryError),
+            Err(e) => {
+                // The transaction is also aborted server side when it times out
+                let _ = transaction.abort_transaction().await;
+                Err(e)
+            }
+        };
+
+        // Run the whole unit of work again if another transaction got in the way
+        match result {
+            Err(e) if e.is_transient() && attempt < TRANSACTION_ATTEMPTS => attempt += 1,
+            result => return result,
+        }
+    }
+}
+
+/// Commits a transaction, retrying while the outcome of the commit is unknown.
+async fn commit<T: Transaction>(transaction: &mut T) -> Result<()> {
+    let mut attempt = 1;
+    loop {
+        match transaction.commit_transaction().await {
+            Err(e)
+                if e.contains_label(UNKNOWN_TRANSACTION_COMMIT_RESULT)
+                    && attempt < TRANSACTION_ATTEMPTS =>
+            {
+                attempt += 1;
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Returns whether `error` aborted a transaction that may succeed if run again, e.g. after a
+/// write conflict or a primary election.
+pub fn is_transient(error: &mongodb::error::Error) -> bool {
+    error.contains_label(TRANSIENT_TRANSACTION_ERROR)
+}
@@ -0,0 +1,152 @@
+use crate::errors::Error::{AdminRequiredError, InvalidWorkspaceError, WorkspaceForbiddenError};
+use crate::model::WorkspaceModel;
+use crate::response::WorkspaceResponse;
+use crate::Result;
+
+/// Name of the collection registering the workspaces.
+pub const WORKSPACES_COLLECTION: &str = "workspaces";
+
+/// Path segment introducing a workspace prefix, as in `/api/workspaces/{id}/notes`.
+pub const WORKSPACE_PATH_PREFIX: &str = "/api/workspaces/";
+
+/// Longest workspace identifier, which keeps per-workspace collection names short.
+const MAX_ID_LENGTH: usize = 63;
+
+/// Returns an error if `id` is not a valid workspace identifier.
+///
+/// Identifiers are made of lowercase ASCII letters, digits and dashes, start with a letter or
+/// digit, and are at most 63 characters long, so they can be embedded in collection names.
+///
+/// # Errors
+///
+/// Returns an `InvalidWorkspaceError` describing the expected format.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::workspaces::validate_id;
+/// assert!(validate_id("acme-corp").is_ok());
+/// assert!(validate_id("Acme Corp").is_err());
+/// assert!(validate_id("-acme").is_err());
+/// ```
+pub fn validate_id(id: &str) -> Result<()> {
+    let valid = !id.is_empty()
+        && id.len() <= MAX_ID_LENGTH
+        && !id.starts_with('-')
+        && id
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-');
+
+    if !valid {
+        return Err(InvalidWorkspaceError(format!(
+            "invalid workspace ID: {} (expected up to {} lowercase letters, digits and dashes)",
+            id, MAX_ID_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+/// Returns the name of the note collection of a workspace in collection-per-tenant mode.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::workspaces::collection_name;
+/// assert_eq!(collection_name("notes", "acme"), "notes_acme");
+/// ```
+pub fn collection_name(note_collection: &str, workspace: &str) -> String {
+    format!("{}_{}", note_collection, workspace)
+}
+
+/// Selects the workspace of a request from its path and `X-Workspace-Id` header.
+///
+/// A `/api/workspaces/{id}` path prefix and the header may both be used as long as they agree.
+/// `None` selects the default workspace.
+///
+/// # Errors
+///
+/// Returns an `InvalidWorkspaceError` if the identifier is malformed, or if the path and the
+/// header name different workspaces.
+///
+/// # Examples
+///
+/// ```rust
+/// # use rust_mongodb_crud::workspaces::select;
+/// assert_eq!(select("/api/workspaces/acme/notes", None).unwrap().as_deref(), Some("acme"));
+/// assert_eq!(select("/api/notes", Some("acme")).unwrap().as_deref(), Some("acme"));
+/// assert_eq!(select("/api/notes", None).unwrap(), None);
+/// assert!(select("/api/workspaces/acme/notes", Some("other")).is_err());
+/// ```
+pub fn select(path: &str, header: Option<&str>) -> Result<Option<String>> {
+    let prefix = path
+        .strip_prefix(WORKSPACE_PATH_PREFIX)
+        .and_then(|rest| rest.split('/').next())
+        .filter(|id| !id.is_empty());
+
+    let workspace = match (prefix, header) {
+        (Some(prefix), Some(header)) if prefix != header => {
+            return Err(InvalidWorkspaceError(format!(
+                "workspace {} in the path does not match workspace {} in the header",
+                prefix, header
+            )));
+        }
+        (Some(id), _) | (None, Some(id)) => id,
+        (None, None) => return Ok(None),
+    };
+    validate_id(workspace)?;
+
+    Ok(Some(workspace.to_string()))
+}
+
+/// Reads the administrators allowed to manage workspaces from `ADMIN_USERS`.
+///
+/// The variable holds a comma separated list of user identifiers. No user is an administrator
+/// when it is not set.
+pub fn admins_from_env() -> Vec<String> {
+    std::env::var("ADMIN_USERS")
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|user| !user.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Returns an error unless `user` is one of the `admins`.
+///
+/// # Errors
+///
+/// Returns an `AdminRequiredError` for anonymous requests and users who are not administrators.
+pub fn check_admin(user: Option<&str>, admins: &[String]) -> Result<()> {
+    match user {
+        Some(user) if admins.iter().any(|admin| admin == user) => Ok(()),
+        _ => Err(AdminRequiredError),
+    }
+}
+
+/// Returns an error unless `user` is one of the members of `workspace`.
+///
+/// # Errors
+///
+/// Returns a `WorkspaceForbiddenError` for anonymous requests and users who are not members.
+pub fn check_member(workspace: &WorkspaceModel, user: Option<&str>) -> Result<()> {
+    match user {
+        Some(user) if workspace.members.iter().any(|member| member == user) => Ok(()),
+        _ => Err(WorkspaceForbiddenError(workspace.id.to_owned())),
+    }
+}
+
+/// Converts a stored workspace into its API representation.
+pub fn workspace_response(workspace: &WorkspaceModel) -> WorkspaceResponse {
+    WorkspaceResponse {
+        id: workspace.id.to_owned(),
+        name: workspace.name.to_owned(),
+        members: workspace.members.to_owned(),
+        archived: workspace.archivedAt.is_some(),
+        createdAt: workspace.createdAt,
+        archivedAt: workspace
+            .archivedAt
+            .map(|archived_at| archived_at.to_chrono()),
+    }
+}
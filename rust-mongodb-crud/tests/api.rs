@@ -0,0 +1,318 @@
+mod common;
+
+use common::{limits, MemoryStore, TestServer};
+use reqwest::Method;
+use rust_mongodb_crud::model::TitleScope;
+use serde_json::{json, Value};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn health_checker_responds() {
+    let server = TestServer::spawn();
+
+    let response = server
+        .client
+        .get(server.url("/api/healthchecker"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "success");
+}
+
+#[tokio::test]
+async fn note_lifecycle() {
+    let server = TestServer::spawn();
+
+    // Create
+    let response = server
+        .client
+        .post(server.url("/api/notes"))
+        .header("x-user-id", "alice")
+        .json(&json!({"title": "Groceries", "content": "Milk", "category": "home"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let note = &body["data"]["note"];
+    assert_eq!(note["title"], "Groceries");
+    assert_eq!(note["category"], "home");
+    assert_eq!(note["owner"], "alice");
+    assert_eq!(note["published"], false);
+    let id = note["id"].as_str().unwrap().to_string();
+
+    // Read
+    let response = server
+        .client
+        .get(server.url(&format!("/api/notes/{}", id)))
+        .header("x-user-id", "alice")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["content"], "Milk");
+
+    // Update only the fields present in the body
+    let response = server
+        .client
+        .patch(server.url(&format!("/api/notes/{}", id)))
+        .header("x-user-id", "alice")
+        .json(&json!({"content": "Milk and eggs", "published": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["title"], "Groceries");
+    assert_eq!(body["data"]["note"]["content"], "Milk and eggs");
+    assert_eq!(body["data"]["note"]["published"], true);
+
+    // Delete
+    let response = server
+        .client
+        .delete(server.url(&format!("/api/notes/{}", id)))
+        .header("x-user-id", "alice")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 204);
+
+    let response = server
+        .client
+        .get(server.url(&format!("/api/notes/{}", id)))
+        .header("x-user-id", "alice")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn list_paginates_and_searches() {
+    let server = TestServer::spawn();
+    for title in ["Rust notes", "Shopping list", "Rust traits"] {
+        server.create_note(title, "Some content").await;
+    }
+
+    let response = server
+        .client
+        .get(server.url("/api/notes?page=2&limit=2"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 1);
+    assert_eq!(body["notes"][0]["title"], "Rust traits");
+
+    let response = server
+        .client
+        .get(server.url("/api/notes?q=rust"))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 2);
+}
+
+#[tokio::test]
+async fn out_of_range_pages_are_rejected() {
+    let server = TestServer::spawn();
+    server.create_note("Rust notes", "Some content").await;
+
+    let too_far = format!("/api/notes?page={}&limit=100", i64::MAX);
+    for path in ["/api/notes?limit=0", "/api/notes?limit=101", &too_far] {
+        let response = server.client.get(server.url(path)).send().await.unwrap();
+        assert_eq!(response.status(), 400, "{}", path);
+    }
+}
+
+#[tokio::test]
+async fn invalid_id_is_rejected() {
+    let server = TestServer::spawn();
+
+    let response = server
+        .client
+        .get(server.url("/api/notes/not-an-id"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "fail");
+}
+
+#[tokio::test]
+async fn missing_note_is_not_found() {
+    let server = TestServer::spawn();
+    let id = "65a0c0ffee0000000000beef";
+
+    for request in [
+        server.client.get(server.url(&format!("/api/notes/{}", id))),
+        server
+            .client
+            .patch(server.url(&format!("/api/notes/{}", id)))
+            .json(&json!({"title": "Nothing"})),
+        server
+            .client
+            .delete(server.url(&format!("/api/notes/{}", id))),
+    ] {
+        let response = request.send().await.unwrap();
+        assert_eq!(response.status(), 404);
+    }
+}
+
+#[tokio::test]
+async fn invalid_body_is_rejected() {
+    let server = TestServer::spawn();
+
+    let response = server
+        .client
+        .post(server.url("/api/notes"))
+        .json(&json!({"title": "No content"}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["message"], "Invalid Body");
+}
+
+#[tokio::test]
+async fn unknown_route_and_method_are_rejected() {
+    let server = TestServer::spawn();
+
+    let response = server
+        .client
+        .get(server.url("/api/unknown"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    let response = server
+        .client
+        .put(server.url("/api/notes"))
+        .json(&json!({"title": "Put", "content": "Not supported"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 405);
+}
+
+#[tokio::test]
+async fn duplicate_title_conflicts() {
+    let store = Arc::new(MemoryStore::with_scope(TitleScope::Owner));
+    let server = TestServer::spawn_with(store, limits(1000, 1000));
+
+    let create = |user: &'static str| {
+        server
+            .client
+            .post(server.url("/api/notes"))
+            .header("x-user-id", user)
+            .json(&json!({"title": "Todo", "content": "Write tests"}))
+            .send()
+    };
+
+    assert_eq!(create("alice").await.unwrap().status(), 201);
+    assert_eq!(create("bob").await.unwrap().status(), 201);
+
+    let response = create("alice").await.unwrap();
+    assert_eq!(response.status(), 409);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["message"], TitleScope::Owner.conflict_message());
+}
+
+#[tokio::test]
+async fn rate_limit_rejects_excess_requests() {
+    let server = TestServer::spawn_with(Arc::new(MemoryStore::default()), limits(2, 1));
+
+    for remaining in ["1", "0"] {
+        let response = server
+            .client
+            .get(server.url("/api/notes"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 200);
+        assert_eq!(response.headers()["ratelimit-limit"], "2");
+        assert_eq!(response.headers()["ratelimit-remaining"], remaining);
+    }
+
+    let response = server
+        .client
+        .get(server.url("/api/notes"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 429);
+    assert!(response.headers().contains_key("retry-after"));
+
+    // Writes are counted separately from reads
+    server
+        .create_note("Still allowed", "Writes have their own quota")
+        .await;
+}
+
+#[tokio::test]
+async fn changing_the_user_header_does_not_reset_the_quota() {
+    let server = TestServer::spawn_with(Arc::new(MemoryStore::default()), limits(2, 1));
+
+    let mut statuses = Vec::new();
+    for user in ["alice", "bob", "carol"] {
+        let response = server
+            .request(Method::GET, "/api/notes", Some(user), None)
+            .await;
+        statuses.push(response.status().as_u16());
+    }
+
+    assert_eq!(statuses, [200, 200, 429]);
+}
+
+#[tokio::test]
+async fn serves_openapi_document() {
+    let server = TestServer::spawn();
+
+    let response = server
+        .client
+        .get(server.url("/api/openapi.json"))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["paths"]["/api/notes"].is_object());
+    assert!(body["paths"]["/api/notes/{id}"].is_object());
+}
+
+#[tokio::test]
+async fn serves_embedded_swagger_ui() {
+    let server = TestServer::spawn();
+
+    // The bare path redirects to the page
+    let response = server
+        .client
+        .get(server.url("/api/docs"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.text().await.unwrap().contains("swagger-ui"));
+
+    // The page loads its configuration and assets from the server itself
+    let response = server
+        .client
+        .get(server.url("/api/docs/swagger-initializer.js"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    assert!(response.text().await.unwrap().contains("/api/openapi.json"));
+}
@@ -0,0 +1,166 @@
+mod common;
+
+use common::TestServer;
+use reqwest::multipart::{Form, Part};
+use serde_json::Value;
+
+/// Uploads `data` as `filename` to a note and returns the response.
+async fn upload(
+    server: &TestServer,
+    note_id: &str,
+    filename: &str,
+    data: &[u8],
+) -> reqwest::Response {
+    let part = Part::bytes(data.to_vec())
+        .file_name(filename.to_string())
+        .mime_str("text/plain")
+        .unwrap();
+
+    server
+        .client
+        .post(server.url(&format!("/api/notes/{}/attachments", note_id)))
+        .multipart(Form::new().part("file", part))
+        .send()
+        .await
+        .unwrap()
+}
+
+/// Uploads a file to a note and returns the URL of the attachment.
+async fn attachment_url(server: &TestServer, note_id: &str, data: &[u8]) -> String {
+    let body: Value = upload(server, note_id, "app.log", data)
+        .await
+        .json()
+        .await
+        .unwrap();
+    let id = body["data"]["attachment"]["id"].as_str().unwrap();
+
+    server.url(&format!("/api/notes/{}/attachments/{}", note_id, id))
+}
+
+#[tokio::test]
+async fn upload_records_metadata() {
+    let server = TestServer::spawn();
+    let note_id = server.create_note("Bug report", "See the log").await;
+
+    let response = upload(&server, &note_id, "app.log", b"hello").await;
+
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let attachment = &body["data"]["attachment"];
+    assert_eq!(attachment["noteId"], note_id.as_str());
+    assert_eq!(attachment["filename"], "app.log");
+    assert_eq!(attachment["contentType"], "text/plain");
+    assert_eq!(attachment["size"], 5);
+    assert_eq!(
+        attachment["sha256"],
+        "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+    );
+
+    let body: Value = server
+        .client
+        .get(server.url(&format!("/api/notes/{}/attachments", note_id)))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["results"], 1);
+    assert_eq!(body["attachments"][0]["filename"], "app.log");
+}
+
+#[tokio::test]
+async fn upload_requires_an_existing_note_and_a_file() {
+    let server = TestServer::spawn();
+
+    let response = upload(&server, "65a0c0ffee0000000000beef", "app.log", b"hello").await;
+    assert_eq!(response.status(), 404);
+
+    let note_id = server.create_note("Empty form", "No file").await;
+    let response = server
+        .client
+        .post(server.url(&format!("/api/notes/{}/attachments", note_id)))
+        .multipart(Form::new().text("comment", "no file here"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn downloads_whole_file_and_ranges() {
+    let server = TestServer::spawn();
+    let note_id = server.create_note("Ranges", "Partial downloads").await;
+    let url = attachment_url(&server, &note_id, b"0123456789").await;
+
+    let response = server.client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 200);
+    assert_eq!(response.headers()["content-type"], "text/plain");
+    assert_eq!(response.headers()["accept-ranges"], "bytes");
+    assert_eq!(
+        response.headers()["content-disposition"],
+        "attachment; filename=\"app.log\""
+    );
+    assert_eq!(response.bytes().await.unwrap().as_ref(), b"0123456789");
+
+    let response = server
+        .client
+        .get(&url)
+        .header("range", "bytes=2-5")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 206);
+    assert_eq!(response.headers()["content-range"], "bytes 2-5/10");
+    assert_eq!(response.bytes().await.unwrap().as_ref(), b"2345");
+
+    let response = server
+        .client
+        .get(&url)
+        .header("range", "bytes=-3")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 206);
+    assert_eq!(response.bytes().await.unwrap().as_ref(), b"789");
+
+    let response = server
+        .client
+        .get(&url)
+        .header("range", "bytes=10-")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 416);
+    assert_eq!(response.headers()["content-range"], "bytes */10");
+}
+
+#[tokio::test]
+async fn deletes_attachments() {
+    let server = TestServer::spawn();
+    let note_id = server.create_note("Delete", "Attachments").await;
+    let url = attachment_url(&server, &note_id, b"bye").await;
+
+    let response = server.client.delete(&url).send().await.unwrap();
+    assert_eq!(response.status(), 204);
+
+    let response = server.client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn deleting_a_note_removes_its_attachments() {
+    let server = TestServer::spawn();
+    let note_id = server.create_note("Cleanup", "Attachments").await;
+    let url = attachment_url(&server, &note_id, b"orphan").await;
+
+    server
+        .client
+        .delete(server.url(&format!("/api/notes/{}", note_id)))
+        .send()
+        .await
+        .unwrap();
+
+    let response = server.client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+}
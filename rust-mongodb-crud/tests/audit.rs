@@ -0,0 +1,200 @@
+mod common;
+
+use common::{TestServer, ADMIN};
+use reqwest::Method;
+use serde_json::{json, Value};
+
+/// Queries the audit log as the administrator and returns its records.
+async fn records(server: &TestServer, query: &str) -> Vec<Value> {
+    let path = format!("/api/audit{}", query);
+    let response = server.request(Method::GET, &path, Some(ADMIN), None).await;
+    assert_eq!(response.status(), 200);
+
+    let body: Value = response.json().await.unwrap();
+    body["records"].as_array().unwrap().clone()
+}
+
+#[tokio::test]
+async fn changes_are_recorded_with_their_actor_and_request() {
+    let server = TestServer::spawn_with_admin();
+    let id = server.create_owned_note("alice", "Plan").await;
+
+    let response = server
+        .client
+        .patch(server.url(&format!("/api/notes/{}", id)))
+        .header("x-user-id", "alice")
+        .header("x-request-id", "req-42")
+        .json(&json!({"content": "final"}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Most recent first, with only the changed fields in updates
+    let records = records(&server, "").await;
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0]["action"], "update");
+    assert_eq!(records[0]["actor"], "alice");
+    assert_eq!(records[0]["noteId"], id);
+    assert_eq!(records[0]["requestId"], "req-42");
+    assert_eq!(records[0]["before"], json!({"content": "draft"}));
+    assert_eq!(records[0]["after"], json!({"content": "final"}));
+
+    // Requests without an ID are given one
+    assert_eq!(records[1]["action"], "create");
+    assert_eq!(records[1]["before"], json!({}));
+    assert_eq!(records[1]["after"]["title"], "Plan");
+    assert!(records[1]["requestId"].is_string());
+}
+
+#[tokio::test]
+async fn deleted_notes_can_be_restored() {
+    let server = TestServer::spawn_with_admin();
+    let id = server.create_owned_note("alice", "Keep").await;
+    let path = format!("/api/notes/{}", id);
+    let restore = format!("{}/restore", path);
+
+    // Only deleted notes can be restored
+    let response = server
+        .request(Method::POST, &restore, Some("alice"), None)
+        .await;
+    assert_eq!(response.status(), 409);
+
+    let response = server
+        .request(Method::DELETE, &path, Some("alice"), None)
+        .await;
+    assert_eq!(response.status(), 204);
+    let log = records(&server, &format!("?note={}", id)).await;
+    assert_eq!(log[0]["action"], "delete");
+    assert_eq!(log[0]["before"]["content"], "draft");
+    assert_eq!(log[0]["after"], json!({}));
+
+    let response = server
+        .request(Method::POST, &restore, Some("alice"), None)
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["id"], id);
+    assert_eq!(body["data"]["note"]["title"], "Keep");
+    assert_eq!(body["data"]["note"]["owner"], "alice");
+
+    let response = server
+        .request(Method::GET, &path, Some("alice"), None)
+        .await;
+    assert_eq!(response.status(), 200);
+    let log = records(&server, &format!("?note={}", id)).await;
+    assert_eq!(log.len(), 3);
+    assert_eq!(log[0]["action"], "restore");
+}
+
+#[tokio::test]
+async fn only_owners_restore_their_notes() {
+    let server = TestServer::spawn_with_admin();
+    let id = server.create_owned_note("alice", "Mine").await;
+    let path = format!("/api/notes/{}", id);
+    let restore = format!("{}/restore", path);
+
+    // Notes that were never deleted are not found
+    let unknown = "/api/notes/6021e59541a3ae69b39ecb46/restore";
+    let response = server
+        .request(Method::POST, unknown, Some("alice"), None)
+        .await;
+    assert_eq!(response.status(), 404);
+
+    // Collaborators cannot restore a note they could not delete
+    let response = server
+        .request(
+            Method::PUT,
+            &format!("{}/acl/bob", path),
+            Some("alice"),
+            Some(json!({"role": "editor"})),
+        )
+        .await;
+    assert_eq!(response.status(), 200);
+    server
+        .request(Method::DELETE, &path, Some("alice"), None)
+        .await;
+
+    let response = server
+        .request(Method::POST, &restore, Some("bob"), None)
+        .await;
+    assert_eq!(response.status(), 403);
+    let response = server
+        .request(Method::POST, &restore, Some("carol"), None)
+        .await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn audit_log_filters_by_note_actor_and_time() {
+    let server = TestServer::spawn_with_admin();
+    let first = server.create_owned_note("alice", "First").await;
+    server.create_owned_note("bob", "Second").await;
+
+    assert_eq!(records(&server, "").await.len(), 2);
+    let by_bob = records(&server, "?actor=bob").await;
+    assert_eq!(by_bob.len(), 1);
+    assert_eq!(by_bob[0]["after"]["title"], "Second");
+    let by_note = records(&server, &format!("?note={}", first)).await;
+    assert_eq!(by_note.len(), 1);
+    assert_eq!(by_note[0]["actor"], "alice");
+
+    assert_eq!(
+        records(&server, "?from=2100-01-01T00:00:00Z").await.len(),
+        0
+    );
+    assert_eq!(records(&server, "?to=2100-01-01T00:00:00Z").await.len(), 2);
+    assert_eq!(records(&server, "?limit=1&page=2").await.len(), 1);
+
+    let response = server
+        .request(Method::GET, "/api/audit?note=x", Some(ADMIN), None)
+        .await;
+    assert_eq!(response.status(), 400);
+
+    // Pages are counted from 1
+    let response = server
+        .request(Method::GET, "/api/audit?page=0", Some(ADMIN), None)
+        .await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn audit_log_is_reserved_to_administrators_per_workspace() {
+    let server = TestServer::spawn_with_admin();
+    server.create_owned_note("alice", "Default").await;
+
+    for user in [None, Some("alice")] {
+        let response = server.request(Method::GET, "/api/audit", user, None).await;
+        assert_eq!(response.status(), 403);
+    }
+
+    let response = server
+        .request(
+            Method::POST,
+            "/api/admin/workspaces",
+            Some(ADMIN),
+            Some(json!({"id": "acme", "members": ["alice"]})),
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+    let response = server
+        .request(
+            Method::POST,
+            "/api/workspaces/acme/notes",
+            Some("alice"),
+            Some(json!({"title": "Acme", "content": "draft"})),
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+
+    // Each workspace has its own audit log
+    let default = records(&server, "").await;
+    assert_eq!(default.len(), 1);
+    assert_eq!(default[0]["after"]["title"], "Default");
+    let response = server
+        .request(Method::GET, "/api/workspaces/acme/audit", Some(ADMIN), None)
+        .await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 1);
+    assert_eq!(body["records"][0]["after"]["title"], "Acme");
+}
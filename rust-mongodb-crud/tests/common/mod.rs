@@ -0,0 +1,956 @@
+// Each test binary only uses part of the shared helpers
+#![allow(dead_code)]
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mongodb::bson::oid::ObjectId;
+use reqwest::{Method, Response};
+use rust_mongodb_crud::attachments::{ByteRange, NewAttachment};
+use rust_mongodb_crud::errors::Error::{
+    DuplicateTitleError, ForbiddenError, InvalidIDError, RestoreConflictError,
+    WorkspaceArchivedError, WorkspaceExistsError, WorkspaceNotFoundError,
+};
+use rust_mongodb_crud::model::{
+    Access, AuditRecordModel, NoteModel, ShareLinkModel, TitleScope, WorkspaceModel,
+};
+use rust_mongodb_crud::patch::NotePatch;
+use rust_mongodb_crud::publishing::{slugify, unique_slug};
+use rust_mongodb_crud::rate_limit::{RateLimit, RateLimiter};
+use rust_mongodb_crud::response::{
+    AccessResponse, AttachmentData, AttachmentListResponse, AttachmentResponse, AuditListResponse,
+    NoteData, NoteListResponse, NoteResponse, ShareLinkData, ShareLinkResponse,
+    SingleAttachmentResponse, SingleNoteResponse, SingleShareLinkResponse, SingleWorkspaceResponse,
+    WorkspaceData, WorkspaceListResponse,
+};
+use rust_mongodb_crud::routes::routes;
+use rust_mongodb_crud::schema::{
+    AclEntry, AuditAction, AuditQuery, CreateNoteSchema, CreateWorkspaceSchema, ShareRole,
+    UpdateNoteSchema,
+};
+use rust_mongodb_crud::store::{page_offset, NoteStore, Store};
+use rust_mongodb_crud::Result;
+use rust_mongodb_crud::{audit, sharing, workspaces};
+use std::net::SocketAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Attached file with its content.
+type StoredAttachment = (AttachmentResponse, Vec<u8>);
+
+/// In-memory `NoteStore` mirroring the behaviour of the MongoDB implementation.
+///
+/// Clones share their data, like views of the same database.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryStore {
+    /// Stored notes in insertion order.
+    notes: Arc<Mutex<Vec<NoteModel>>>,
+    /// Scope within which titles must be unique.
+    title_scope: TitleScope,
+    /// Attached files with their content.
+    attachments: Arc<Mutex<Vec<StoredAttachment>>>,
+    /// Workspaces with the store of their notes, like collection-per-tenant mode.
+    workspaces: Arc<Mutex<Vec<(WorkspaceModel, MemoryStore)>>>,
+    /// Audit records in insertion order.
+    audit_log: Arc<Mutex<Vec<AuditRecordModel>>>,
+    /// Identifier of the request the store is used for.
+    request_id: Option<String>,
+}
+
+impl MemoryStore {
+    /// Creates an empty store enforcing unique titles within `title_scope`.
+    pub fn with_scope(title_scope: TitleScope) -> Self {
+        MemoryStore {
+            title_scope,
+            ..Default::default()
+        }
+    }
+
+    /// Finds a workspace and the store of its notes, failing if it does not exist or was archived.
+    fn find_workspace(&self, id: &str) -> Result<(WorkspaceModel, MemoryStore)> {
+        workspaces::validate_id(id)?;
+        let workspaces = self.workspaces.lock().unwrap();
+
+        match workspaces.iter().find(|(workspace, _)| workspace.id == id) {
+            Some((workspace, _)) if workspace.archivedAt.is_some() => {
+                Err(WorkspaceArchivedError(id.to_owned()))
+            }
+            Some((workspace, store)) => Ok((workspace.clone(), store.clone())),
+            None => Err(WorkspaceNotFoundError(id.to_owned())),
+        }
+    }
+
+    /// Appends the record of a change made by `actor` to the audit log.
+    fn audit(
+        &self,
+        action: AuditAction,
+        actor: Option<&str>,
+        before: Option<&NoteModel>,
+        after: Option<&NoteModel>,
+    ) -> Result<()> {
+        let record = audit::record(
+            action,
+            actor,
+            self.request_id.as_deref(),
+            None,
+            before,
+            after,
+        )?;
+        self.audit_log.lock().unwrap().push(record);
+
+        Ok(())
+    }
+
+    /// Returns an error if `note` shares its title with another note in the same scope.
+    fn check_title(&self, notes: &[NoteModel], note: &NoteModel) -> Result<()> {
+        let conflict = notes.iter().any(|other| {
+            other.id != note.id
+                && other.title == note.title
+                && match self.title_scope {
+                    TitleScope::Global => true,
+                    TitleScope::Owner => other.owner == note.owner,
+                    TitleScope::Category => other.category == note.category,
+                }
+        });
+
+        if conflict {
+            return Err(DuplicateTitleError(self.title_scope));
+        }
+
+        Ok(())
+    }
+
+    /// Finds the note with the given ID if `user` has the `required` access, like the MongoDB store.
+    fn authorize(
+        &self,
+        id: &str,
+        user: Option<&str>,
+        required: Access,
+    ) -> Result<Option<NoteModel>> {
+        let oid = parse_id(id)?;
+        let notes = self.notes.lock().unwrap();
+        let Some(note) = notes.iter().find(|note| note.id == oid) else {
+            return Ok(None);
+        };
+
+        match note.access(user) {
+            Some(access) if access >= required => Ok(Some(note.clone())),
+            Some(_) => Err(ForbiddenError(required)),
+            None => Ok(None),
+        }
+    }
+
+    /// Finds the note with the given ID if `user` may manage its sharing settings.
+    fn authorize_sharing(&self, id: &str, user: Option<&str>) -> Result<Option<NoteModel>> {
+        let note = self.authorize(id, user, Access::Manage)?;
+        if let Some(note) = &note {
+            sharing::check_owned(note)?;
+        }
+
+        Ok(note)
+    }
+
+    /// Applies `update` to the note with the given ID and returns its sharing settings.
+    fn update_sharing(
+        &self,
+        id: &str,
+        update: impl FnOnce(&mut NoteModel),
+    ) -> Option<AccessResponse> {
+        let oid = parse_id(id).ok()?;
+        let mut notes = self.notes.lock().unwrap();
+        let note = notes.iter_mut().find(|note| note.id == oid)?;
+        update(note);
+
+        Some(sharing::access_response(note, Utc::now()))
+    }
+
+    /// Fetches a page of the notes matching `filter`.
+    fn page(
+        &self,
+        limit: i64,
+        page: i64,
+        filter: impl Fn(&NoteModel) -> bool,
+    ) -> Result<NoteListResponse> {
+        let offset = page_offset(limit, page)?;
+        let notes: Vec<NoteResponse> = self
+            .notes
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|note| filter(note))
+            .skip(offset as usize)
+            .take(limit as usize)
+            .map(to_response)
+            .collect();
+
+        Ok(NoteListResponse {
+            status: "success".to_string(),
+            results: notes.len(),
+            notes,
+        })
+    }
+
+    /// Sets the editable fields of the note with ID `oid` to `body` and records the change made
+    /// by `user`, like the MongoDB store.
+    fn write_fields(
+        &self,
+        notes: &mut [NoteModel],
+        oid: ObjectId,
+        body: &CreateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        let Some(before) = notes.iter().find(|note| note.id == oid).cloned() else {
+            return Ok(None);
+        };
+        let mut note = before.clone();
+
+        note.title = body.title.to_owned();
+        note.content = body.content.to_owned();
+        note.category = body.category.to_owned();
+        note.published = body.published;
+        note.publishAt = body.publish_at.map(Into::into);
+        note.updatedAt = Utc::now();
+
+        self.check_title(notes, &note)?;
+        let stored = notes.iter_mut().find(|stored| stored.id == oid).unwrap();
+        *stored = note.clone();
+        self.audit(AuditAction::Update, user, Some(&before), Some(&note))?;
+
+        Ok(Some(single(&note)))
+    }
+}
+
+/// Parses a note ID the same way the MongoDB store does.
+fn parse_id(id: &str) -> Result<ObjectId> {
+    ObjectId::from_str(id).map_err(|_| InvalidIDError(id.to_owned()))
+}
+
+/// Wraps a note in the single note response envelope.
+fn single(note: &NoteModel) -> SingleNoteResponse {
+    SingleNoteResponse {
+        status: "success".to_string(),
+        data: NoteData {
+            note: to_response(note),
+        },
+    }
+}
+
+/// Converts a stored note into its response representation.
+fn to_response(note: &NoteModel) -> NoteResponse {
+    NoteResponse {
+        id: note.id.to_hex(),
+        title: note.title.to_owned(),
+        content: note.content.to_owned(),
+        contentHtml: None,
+        category: note.category.to_owned().unwrap_or_default(),
+        owner: note.owner.to_owned(),
+        published: note.published.unwrap_or_default(),
+        publishAt: note.publishAt.map(|publish_at| publish_at.to_chrono()),
+        slug: note.slug.to_owned(),
+        createdAt: note.createdAt,
+        updatedAt: note.updatedAt,
+    }
+}
+
+#[async_trait]
+impl NoteStore for MemoryStore {
+    fn for_request(&self, request_id: &str) -> Store {
+        Arc::new(MemoryStore {
+            request_id: Some(request_id.to_string()),
+            ..self.clone()
+        })
+    }
+
+    async fn workspace(&self, id: &str) -> Result<Store> {
+        let (_, store) = self.find_workspace(id)?;
+        Ok(Arc::new(store))
+    }
+
+    async fn member_workspace(&self, id: &str, user: Option<&str>) -> Result<Store> {
+        let (workspace, store) = self.find_workspace(id)?;
+        workspaces::check_member(&workspace, user)?;
+        Ok(Arc::new(store))
+    }
+
+    async fn create_workspace(
+        &self,
+        body: &CreateWorkspaceSchema,
+    ) -> Result<SingleWorkspaceResponse> {
+        workspaces::validate_id(&body.id)?;
+        let mut workspaces = self.workspaces.lock().unwrap();
+        if workspaces
+            .iter()
+            .any(|(workspace, _)| workspace.id == body.id)
+        {
+            return Err(WorkspaceExistsError(body.id.to_owned()));
+        }
+
+        let workspace = WorkspaceModel {
+            id: body.id.to_owned(),
+            name: body.name.to_owned().unwrap_or_else(|| body.id.to_owned()),
+            members: body.members.to_owned(),
+            createdAt: Utc::now(),
+            archivedAt: None,
+        };
+        let store = MemoryStore::with_scope(self.title_scope);
+        workspaces.push((workspace.clone(), store));
+
+        Ok(SingleWorkspaceResponse {
+            status: "success".to_string(),
+            data: WorkspaceData {
+                workspace: workspaces::workspace_response(&workspace),
+            },
+        })
+    }
+
+    async fn list_workspaces(&self) -> Result<WorkspaceListResponse> {
+        let mut workspaces: Vec<_> = self
+            .workspaces
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(workspace, _)| workspaces::workspace_response(workspace))
+            .collect();
+        workspaces.sort_by(|a, b| a.id.cmp(&b.id));
+
+        Ok(WorkspaceListResponse {
+            status: "success".to_string(),
+            results: workspaces.len(),
+            workspaces,
+        })
+    }
+
+    async fn archive_workspace(&self, id: &str) -> Result<Option<SingleWorkspaceResponse>> {
+        let mut workspaces = self.workspaces.lock().unwrap();
+        let Some((workspace, _)) = workspaces
+            .iter_mut()
+            .find(|(workspace, _)| workspace.id == id)
+        else {
+            return Ok(None);
+        };
+        workspace
+            .archivedAt
+            .get_or_insert_with(|| Utc::now().into());
+
+        Ok(Some(SingleWorkspaceResponse {
+            status: "success".to_string(),
+            data: WorkspaceData {
+                workspace: workspaces::workspace_response(workspace),
+            },
+        }))
+    }
+
+    async fn fetch_notes(
+        &self,
+        limit: i64,
+        page: i64,
+        search: Option<&str>,
+        user: Option<&str>,
+    ) -> Result<NoteListResponse> {
+        let search = search.map(str::to_lowercase);
+
+        self.page(limit, page, |note| {
+            let matches = match &search {
+                Some(search) => {
+                    note.title.to_lowercase().contains(search)
+                        || note.content.to_lowercase().contains(search)
+                }
+                None => true,
+            };
+            matches && note.access(user).is_some()
+        })
+    }
+
+    async fn shared_notes(&self, limit: i64, page: i64, user: &str) -> Result<NoteListResponse> {
+        self.page(limit, page, |note| {
+            note.acl.iter().flatten().any(|entry| entry.user == user)
+        })
+    }
+
+    async fn create_note(
+        &self,
+        body: &CreateNoteSchema,
+        owner: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        let mut notes = self.notes.lock().unwrap();
+        let now = Utc::now();
+        let taken: Vec<String> = notes.iter().filter_map(|note| note.slug.clone()).collect();
+        let note = NoteModel {
+            id: ObjectId::new(),
+            title: body.title.to_owned(),
+            content: body.content.to_owned(),
+            category: Some(body.category.to_owned().unwrap_or_default()),
+            owner: owner.map(str::to_string),
+            published: Some(body.published.unwrap_or(false)),
+            publishAt: body.publish_at.map(Into::into),
+            slug: Some(unique_slug(&slugify(&body.title), &taken)),
+            acl: None,
+            shareLinks: None,
+            workspaceId: None,
+            createdAt: now,
+            updatedAt: now,
+        };
+
+        self.check_title(&notes, &note)?;
+        notes.push(note.clone());
+        self.audit(AuditAction::Create, owner, None, Some(&note))?;
+
+        Ok(Some(single(&note)))
+    }
+
+    async fn get_note(&self, id: &str, user: Option<&str>) -> Result<Option<SingleNoteResponse>> {
+        Ok(self.authorize(id, user, Access::Read)?.as_ref().map(single))
+    }
+
+    async fn edit_note(
+        &self,
+        id: &str,
+        body: &UpdateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        self.patch_note(id, &NotePatch::Fields(body.clone()), user)
+            .await
+    }
+
+    async fn replace_note(
+        &self,
+        id: &str,
+        body: &CreateNoteSchema,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        if self.authorize(id, user, Access::Write)?.is_none() {
+            return Ok(None);
+        }
+        let oid = parse_id(id)?;
+        let mut notes = self.notes.lock().unwrap();
+
+        self.write_fields(&mut notes, oid, body, user)
+    }
+
+    async fn patch_note(
+        &self,
+        id: &str,
+        patch: &NotePatch,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        if self.authorize(id, user, Access::Write)?.is_none() {
+            return Ok(None);
+        }
+        let oid = parse_id(id)?;
+        let mut notes = self.notes.lock().unwrap();
+        let Some(note) = notes.iter().find(|note| note.id == oid) else {
+            return Ok(None);
+        };
+
+        // The lock is held throughout, so the patch is applied atomically
+        let body = patch.apply(note)?;
+        self.write_fields(&mut notes, oid, &body, user)
+    }
+
+    async fn delete_note(&self, id: &str, user: Option<&str>) -> Result<Option<()>> {
+        let Some(before) = self.authorize(id, user, Access::Manage)? else {
+            return Ok(None);
+        };
+        let oid = parse_id(id)?;
+        let mut notes = self.notes.lock().unwrap();
+        let count = notes.len();
+        notes.retain(|note| note.id != oid);
+        if notes.len() == count {
+            return Ok(None);
+        }
+
+        // Clean up the files attached to the note
+        self.attachments
+            .lock()
+            .unwrap()
+            .retain(|(attachment, _)| attachment.noteId != oid.to_hex());
+        self.audit(AuditAction::Delete, user, Some(&before), None)?;
+
+        Ok(Some(()))
+    }
+
+    async fn move_category(&self, from: &str, to: &str, user: Option<&str>) -> Result<u64> {
+        if from == to {
+            return Ok(0);
+        }
+        let mut notes = self.notes.lock().unwrap();
+
+        // Move a copy of the notes, so none moves if one of them cannot
+        let now = Utc::now();
+        let mut moved = notes.clone();
+        let mut changes = Vec::new();
+        for note in moved.iter_mut() {
+            let writable = matches!(note.access(user), Some(access) if access >= Access::Write);
+            if writable && note.category.as_deref() == Some(from) {
+                let before = note.clone();
+                note.category = Some(to.to_string());
+                note.updatedAt = now;
+                changes.push((before, note.clone()));
+            }
+        }
+        for (_, note) in &changes {
+            self.check_title(&moved, note)?;
+        }
+
+        *notes = moved;
+        for (before, after) in &changes {
+            self.audit(AuditAction::Update, user, Some(before), Some(after))?;
+        }
+
+        Ok(changes.len() as u64)
+    }
+
+    async fn restore_note(
+        &self,
+        id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<SingleNoteResponse>> {
+        let oid = parse_id(id)?;
+        if self.notes.lock().unwrap().iter().any(|note| note.id == oid) {
+            return Err(RestoreConflictError(id.to_owned()));
+        }
+
+        // Rebuild the note from its latest deletion
+        let record = self
+            .audit_log
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|record| record.noteId == oid && record.action == AuditAction::Delete)
+            .cloned();
+        let Some(record) = record else {
+            return Ok(None);
+        };
+        let mut note = audit::restored_note(&record, Utc::now())?;
+        match note.access(user) {
+            Some(access) if access >= Access::Manage => {}
+            Some(_) => return Err(ForbiddenError(Access::Manage)),
+            None => return Ok(None),
+        }
+
+        // Pick a new slug if another note took it in the meantime
+        let mut notes = self.notes.lock().unwrap();
+        let taken: Vec<String> = notes.iter().filter_map(|note| note.slug.clone()).collect();
+        if let Some(slug) = &note.slug {
+            if taken.contains(slug) {
+                note.slug = Some(unique_slug(&slugify(&note.title), &taken));
+            }
+        }
+
+        self.check_title(&notes, &note)?;
+        notes.push(note.clone());
+        self.audit(AuditAction::Restore, user, None, Some(&note))?;
+
+        Ok(Some(single(&note)))
+    }
+
+    async fn audit_log(&self, query: &AuditQuery) -> Result<AuditListResponse> {
+        let note = query.note.as_deref().map(parse_id).transpose()?;
+        let limit = query.limit.unwrap_or(10);
+        let offset = page_offset(limit as i64, query.page.unwrap_or(1) as i64)?;
+
+        let records: Vec<_> = self
+            .audit_log
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .filter(|record| {
+                note.is_none_or(|note| record.noteId == note)
+                    && query
+                        .actor
+                        .as_ref()
+                        .is_none_or(|actor| record.actor.as_ref() == Some(actor))
+                    && query.from.is_none_or(|from| record.timestamp >= from)
+                    && query.to.is_none_or(|to| record.timestamp < to)
+            })
+            .skip(offset as usize)
+            .take(limit)
+            .map(audit::record_response)
+            .collect();
+
+        Ok(AuditListResponse {
+            status: "success".to_string(),
+            results: records.len(),
+            records,
+        })
+    }
+
+    async fn get_published_note(&self, slug: &str) -> Result<Option<SingleNoteResponse>> {
+        let notes = self.notes.lock().unwrap();
+
+        Ok(notes
+            .iter()
+            .find(|note| note.slug.as_deref() == Some(slug) && note.published == Some(true))
+            .map(single))
+    }
+
+    async fn publish_due_notes(&self, now: DateTime<Utc>) -> Result<u64> {
+        let mut published = 0;
+
+        for note in self.notes.lock().unwrap().iter_mut() {
+            if note
+                .publishAt
+                .is_some_and(|publish_at| publish_at.to_chrono() <= now)
+            {
+                note.published = Some(true);
+                note.publishAt = None;
+                note.updatedAt = now;
+                published += 1;
+            }
+        }
+
+        // Publish the notes of the active workspaces too
+        let stores: Vec<_> = self
+            .workspaces
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(workspace, _)| workspace.archivedAt.is_none())
+            .map(|(_, store)| store.clone())
+            .collect();
+        for store in stores {
+            published += store.publish_due_notes(now).await?;
+        }
+
+        Ok(published)
+    }
+
+    async fn get_access(&self, id: &str, user: Option<&str>) -> Result<Option<AccessResponse>> {
+        Ok(self
+            .authorize_sharing(id, user)?
+            .map(|note| sharing::access_response(&note, Utc::now())))
+    }
+
+    async fn grant_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        role: ShareRole,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>> {
+        let Some(note) = self.authorize_sharing(id, user)? else {
+            return Ok(None);
+        };
+        sharing::check_grantee(&note, grantee)?;
+
+        Ok(self.update_sharing(id, |note| {
+            let acl = note.acl.get_or_insert_with(Vec::new);
+            acl.retain(|entry| entry.user != grantee);
+            acl.push(AclEntry {
+                user: grantee.to_string(),
+                role,
+            });
+        }))
+    }
+
+    async fn revoke_access(
+        &self,
+        id: &str,
+        grantee: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AccessResponse>> {
+        if self.authorize_sharing(id, user)?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(self.update_sharing(id, |note| {
+            if let Some(acl) = &mut note.acl {
+                acl.retain(|entry| entry.user != grantee);
+            }
+        }))
+    }
+
+    async fn create_share_link(
+        &self,
+        id: &str,
+        expires_at: DateTime<Utc>,
+        user: Option<&str>,
+    ) -> Result<Option<SingleShareLinkResponse>> {
+        if self.authorize_sharing(id, user)?.is_none() {
+            return Ok(None);
+        }
+
+        let token = sharing::new_token();
+        let link = ShareLinkModel {
+            id: ObjectId::new(),
+            tokenHash: sharing::hash_token(&token),
+            expiresAt: expires_at.into(),
+        };
+        self.update_sharing(id, |note| {
+            note.shareLinks
+                .get_or_insert_with(Vec::new)
+                .push(link.clone())
+        });
+
+        Ok(Some(SingleShareLinkResponse {
+            status: "success".to_string(),
+            data: ShareLinkData {
+                link: ShareLinkResponse {
+                    id: link.id.to_hex(),
+                    token: Some(token),
+                    expiresAt: expires_at,
+                },
+            },
+        }))
+    }
+
+    async fn revoke_share_link(
+        &self,
+        id: &str,
+        link_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>> {
+        let lid = parse_id(link_id)?;
+        let Some(note) = self.authorize_sharing(id, user)? else {
+            return Ok(None);
+        };
+        if !note.shareLinks.iter().flatten().any(|link| link.id == lid) {
+            return Ok(None);
+        }
+
+        self.update_sharing(id, |note| {
+            if let Some(links) = &mut note.shareLinks {
+                links.retain(|link| link.id != lid);
+            }
+        });
+
+        Ok(Some(()))
+    }
+
+    async fn get_shared_note(&self, token: &str) -> Result<Option<SingleNoteResponse>> {
+        let hash = sharing::hash_token(token);
+        let now = Utc::now();
+        let notes = self.notes.lock().unwrap();
+
+        Ok(notes
+            .iter()
+            .find(|note| {
+                note.shareLinks
+                    .iter()
+                    .flatten()
+                    .any(|link| link.tokenHash == hash && link.expiresAt.to_chrono() > now)
+            })
+            .map(single))
+    }
+
+    async fn create_attachment(
+        &self,
+        note_id: &str,
+        attachment: NewAttachment,
+        user: Option<&str>,
+    ) -> Result<Option<SingleAttachmentResponse>> {
+        if self.authorize(note_id, user, Access::Write)?.is_none() {
+            return Ok(None);
+        }
+
+        let response = AttachmentResponse {
+            id: ObjectId::new().to_hex(),
+            noteId: note_id.to_string(),
+            filename: attachment.filename,
+            contentType: attachment.content_type,
+            size: attachment.data.len() as u64,
+            sha256: attachment.sha256,
+            uploadedAt: Utc::now(),
+        };
+        self.attachments
+            .lock()
+            .unwrap()
+            .push((response.clone(), attachment.data));
+
+        Ok(Some(SingleAttachmentResponse {
+            status: "success".to_string(),
+            data: AttachmentData {
+                attachment: response,
+            },
+        }))
+    }
+
+    async fn list_attachments(
+        &self,
+        note_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentListResponse>> {
+        if self.authorize(note_id, user, Access::Read)?.is_none() {
+            return Ok(None);
+        }
+
+        let attachments: Vec<AttachmentResponse> = self
+            .attachments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(attachment, _)| attachment.noteId == note_id)
+            .map(|(attachment, _)| attachment.clone())
+            .collect();
+
+        Ok(Some(AttachmentListResponse {
+            status: "success".to_string(),
+            results: attachments.len(),
+            attachments,
+        }))
+    }
+
+    async fn get_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<AttachmentResponse>> {
+        parse_id(attachment_id)?;
+        if self.authorize(note_id, user, Access::Read)?.is_none() {
+            return Ok(None);
+        }
+
+        Ok(self
+            .attachments
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(attachment, _)| attachment.id == attachment_id && attachment.noteId == note_id)
+            .map(|(attachment, _)| attachment.clone()))
+    }
+
+    async fn read_attachment(
+        &self,
+        attachment: &AttachmentResponse,
+        range: ByteRange,
+    ) -> Result<Vec<u8>> {
+        let attachments = self.attachments.lock().unwrap();
+        let (_, data) = attachments
+            .iter()
+            .find(|(stored, _)| stored.id == attachment.id)
+            .unwrap();
+
+        Ok(data[range.start as usize..=range.end as usize].to_vec())
+    }
+
+    async fn delete_attachment(
+        &self,
+        note_id: &str,
+        attachment_id: &str,
+        user: Option<&str>,
+    ) -> Result<Option<()>> {
+        if self.authorize(note_id, user, Access::Write)?.is_none() {
+            return Ok(None);
+        }
+        if self
+            .get_attachment(note_id, attachment_id, user)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        self.attachments
+            .lock()
+            .unwrap()
+            .retain(|(attachment, _)| attachment.id != attachment_id);
+
+        Ok(Some(()))
+    }
+}
+
+/// Administrator of the servers started with [`TestServer::spawn_with_admin`].
+pub const ADMIN: &str = "root";
+
+/// API server running on an ephemeral port for the duration of a test.
+pub struct TestServer {
+    /// Address the server listens on.
+    pub addr: SocketAddr,
+    /// HTTP client used to call the server.
+    pub client: reqwest::Client,
+}
+
+impl TestServer {
+    /// Serves the full filter tree against an empty in-memory store with generous rate limits.
+    pub fn spawn() -> Self {
+        Self::spawn_with(Arc::new(MemoryStore::default()), limits(1000, 1000))
+    }
+
+    /// Serves the full filter tree like [`TestServer::spawn`], with [`ADMIN`] as the only
+    /// administrator.
+    pub fn spawn_with_admin() -> Self {
+        std::env::set_var("ADMIN_USERS", ADMIN);
+        Self::spawn()
+    }
+
+    /// Serves the full filter tree against `store`, rate limited by `limiter`.
+    pub fn spawn_with(store: Store, limiter: RateLimiter) -> Self {
+        let (addr, server) =
+            warp::serve(routes(store, limiter)).bind_ephemeral(([127, 0, 0, 1], 0));
+        tokio::spawn(server);
+
+        TestServer {
+            addr,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Returns the absolute URL of `path` on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}{}", self.addr, path)
+    }
+
+    /// Creates a note through the API and returns its ID.
+    pub async fn create_note(&self, title: &str, content: &str) -> String {
+        let response = self
+            .client
+            .post(self.url("/api/notes"))
+            .json(&serde_json::json!({"title": title, "content": content}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 201);
+
+        let body: serde_json::Value = response.json().await.unwrap();
+        body["data"]["note"]["id"].as_str().unwrap().to_string()
+    }
+
+    /// Sends a request as `user`, with an optional JSON body.
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        user: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> Response {
+        let mut request = self.client.request(method, self.url(path));
+        if let Some(user) = user {
+            request = request.header("x-user-id", user);
+        }
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        request.send().await.unwrap()
+    }
+
+    /// Creates a note owned by `owner` through the API and returns its ID.
+    pub async fn create_owned_note(&self, owner: &str, title: &str) -> String {
+        let note = serde_json::json!({"title": title, "content": "draft"});
+        let response = self
+            .request(Method::POST, "/api/notes", Some(owner), Some(note))
+            .await;
+        assert_eq!(response.status(), 201);
+
+        let body: serde_json::Value = response.json().await.unwrap();
+        body["data"]["note"]["id"].as_str().unwrap().to_string()
+    }
+}
+
+/// Builds an in-memory rate limiter allowing `read` and `write` requests per minute.
+pub fn limits(read: u32, write: u32) -> RateLimiter {
+    let per = Duration::from_secs(60);
+    RateLimiter::in_memory(
+        RateLimit {
+            requests: read,
+            per,
+        },
+        RateLimit {
+            requests: write,
+            per,
+        },
+    )
+}
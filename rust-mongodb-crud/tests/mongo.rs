@@ -0,0 +1,308 @@
+//! Tests against a real MongoDB deployment, configured like the server through `.env`.
+//!
+//! They are ignored by default. Start MongoDB with `make mongo_in_docker` and run them with
+//! `cargo test --test mongo -- --ignored`. Each test works in a database of its own, which it
+//! drops first. The transaction tests need a replica set, the commit test also a server started
+//! with `--setParameter enableTestCommands=1`. The retry logic of transactions is also covered
+//! without a server by `tests/transactions.rs`.
+
+use chrono::{Duration, Utc};
+use dotenv::dotenv;
+use mongodb::bson::{doc, oid::ObjectId};
+use rust_mongodb_crud::audit::AUDIT_COLLECTION;
+use rust_mongodb_crud::db::DB;
+use rust_mongodb_crud::errors::Error::{DuplicateTitleError, InvalidShareError, MongoQueryError};
+use rust_mongodb_crud::migrations::{migrations, MIGRATIONS_COLLECTION};
+use rust_mongodb_crud::model::TitleScope;
+use rust_mongodb_crud::schema::{CreateNoteSchema, CreateWorkspaceSchema};
+use rust_mongodb_crud::workspaces::WORKSPACES_COLLECTION;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Connects to an empty database named `name`, with titles unique within `title_scope`.
+async fn connect(name: &str, title_scope: TitleScope) -> DB {
+    dotenv().ok();
+    let db = DB::init().await.unwrap();
+
+    let database = db.client.database(name);
+    database.drop(None).await.unwrap();
+    let db = DB {
+        note_collection: database.collection("notes"),
+        collection: database.collection("notes"),
+        attachments: database.gridfs_bucket(None),
+        workspaces: database.collection(WORKSPACES_COLLECTION),
+        audit_log: database.collection(AUDIT_COLLECTION),
+        database,
+        title_scope,
+        ..db
+    };
+    db.migrator().migrate().await.unwrap();
+    db.sync_title_index().await.unwrap();
+
+    db
+}
+
+/// Builds the body of a note titled `title` in `category`.
+fn note(title: &str, category: &str) -> CreateNoteSchema {
+    CreateNoteSchema {
+        title: title.to_string(),
+        content: "Created by the MongoDB tests".to_string(),
+        category: Some(category.to_string()),
+        published: None,
+        publish_at: None,
+    }
+}
+
+/// Creates a note owned by alice and returns its ID.
+async fn create(db: &DB, title: &str, category: &str) -> ObjectId {
+    let created = db
+        .create_note(&note(title, category), Some("alice"))
+        .await
+        .unwrap()
+        .unwrap();
+    ObjectId::parse_str(created.data.note.id).unwrap()
+}
+
+/// Returns the category of the note with ID `id`.
+async fn category(db: &DB, id: ObjectId) -> String {
+    let note = db
+        .collection
+        .find_one(doc! {"_id": id}, None)
+        .await
+        .unwrap()
+        .unwrap();
+    note.get_str("category").unwrap().to_string()
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB server"]
+async fn concurrent_creates_get_distinct_slugs() {
+    let db = connect("notes_test_slugs", TitleScope::Owner).await;
+
+    // Every create derives the same free slug before any of them is inserted
+    let creates: Vec<_> = (0..4)
+        .map(|n| {
+            let db = db.clone();
+            tokio::spawn(async move {
+                let owner = format!("user-{}", n);
+                db.create_note(&note("Race", "drafts"), Some(&owner)).await
+            })
+        })
+        .collect();
+
+    let mut slugs = Vec::new();
+    for create in creates {
+        let created = create.await.unwrap().unwrap().unwrap();
+        slugs.push(created.data.note.slug.unwrap());
+    }
+    slugs.sort();
+    slugs.dedup();
+    assert_eq!(slugs.len(), 4);
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB server"]
+async fn concurrent_migrations_are_recorded_once() {
+    let db = connect("notes_test_migrations", TitleScope::Global).await;
+    db.database
+        .collection::<mongodb::bson::Document>(MIGRATIONS_COLLECTION)
+        .delete_many(doc! {}, None)
+        .await
+        .unwrap();
+
+    // Instances starting together all find every migration pending
+    let runs: Vec<_> = (0..4)
+        .map(|_| {
+            let migrator = db.migrator();
+            tokio::spawn(async move { migrator.migrate().await })
+        })
+        .collect();
+
+    let mut versions = Vec::new();
+    for run in runs {
+        versions.extend(run.await.unwrap().unwrap());
+    }
+    versions.sort();
+    let known: Vec<_> = migrations().iter().map(|m| m.version).collect();
+    assert_eq!(versions, known);
+
+    // The index on the tags notes do not have is gone
+    let names = db.note_collection.list_index_names().await.unwrap();
+    assert!(!names.iter().any(|name| name == "tags"));
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB server"]
+async fn notes_of_archived_workspaces_are_not_published() {
+    let db = connect("notes_test_publishing", TitleScope::Global).await;
+    let workspace = CreateWorkspaceSchema {
+        id: "acme".to_string(),
+        ..Default::default()
+    };
+    db.create_workspace(&workspace).await.unwrap();
+    let id = create(&db.workspace("acme").await.unwrap(), "Launch", "drafts").await;
+    db.archive_workspace("acme").await.unwrap();
+
+    // Schedule the note in the past, as if the workspace was archived while it was pending
+    let now = Utc::now();
+    db.collection
+        .update_one(
+            doc! {"_id": id},
+            doc! {"$set": {"publishAt": now - Duration::minutes(1)}},
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(db.publish_due_notes(now).await.unwrap(), 0);
+    let note = db
+        .collection
+        .find_one(doc! {"_id": id}, None)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(note.get("publishAt").is_some());
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB server"]
+async fn moves_with_a_taken_title_move_nothing() {
+    let db = connect("notes_test_moves", TitleScope::Category).await;
+    let first = create(&db, "First", "drafts").await;
+    let plan = create(&db, "Plan", "drafts").await;
+    create(&db, "Plan", "archive").await;
+
+    // Holds with and without transactions
+    let result = db.move_category("drafts", "archive", Some("alice")).await;
+    assert!(matches!(
+        result,
+        Err(DuplicateTitleError(TitleScope::Category))
+    ));
+    assert_eq!(category(&db, first).await, "drafts");
+    assert_eq!(category(&db, plan).await, "drafts");
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB replica set"]
+async fn failed_transactions_are_aborted() {
+    let db = connect("notes_test_abort", TitleScope::Global).await;
+    assert!(
+        db.transactions,
+        "transactions are not supported by this deployment"
+    );
+    let id = create(&db, "First", "drafts").await;
+
+    let result: Result<(), _> = db
+        .transaction(&id, |db, id, session| {
+            Box::pin(async move {
+                let update = doc! {"$set": {"category": "archive"}};
+                session
+                    .find_one_and_update(&db.note_collection, doc! {"_id": *id}, update, None)
+                    .await
+                    .map_err(MongoQueryError)?;
+                Err(InvalidShareError("stop".to_string()))
+            })
+        })
+        .await;
+
+    assert!(matches!(result, Err(InvalidShareError(_))));
+    assert_eq!(category(&db, id).await, "drafts");
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB replica set"]
+async fn transient_errors_run_the_transaction_again() {
+    let db = connect("notes_test_retry", TitleScope::Global).await;
+    assert!(
+        db.transactions,
+        "transactions are not supported by this deployment"
+    );
+    let id = create(&db, "First", "drafts").await;
+    let attempts = AtomicUsize::new(0);
+
+    db.transaction(&(id, &attempts), |db, (id, attempts), session| {
+        Box::pin(async move {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+            session
+                .find_one(&db.note_collection, doc! {"_id": *id})
+                .await
+                .map_err(MongoQueryError)?;
+
+            // Another writer changes the note after the transaction read it, so the first
+            // attempt fails with a write conflict
+            if attempt == 1 {
+                db.collection
+                    .update_one(
+                        doc! {"_id": *id},
+                        doc! {"$set": {"content": "outside"}},
+                        None,
+                    )
+                    .await
+                    .map_err(MongoQueryError)?;
+            }
+
+            let update = doc! {"$set": {"category": "archive"}};
+            session
+                .find_one_and_update(&db.note_collection, doc! {"_id": *id}, update, None)
+                .await
+                .map_err(MongoQueryError)?;
+            Ok(())
+        })
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    assert_eq!(category(&db, id).await, "archive");
+}
+
+#[tokio::test]
+#[ignore = "needs a MongoDB replica set with test commands enabled"]
+async fn commits_with_an_unknown_result_are_retried() {
+    let db = connect("notes_test_commit", TitleScope::Global).await;
+    assert!(
+        db.transactions,
+        "transactions are not supported by this deployment"
+    );
+    let id = create(&db, "First", "drafts").await;
+
+    // Fail more commits than the driver retries on its own
+    let admin = db.client.database("admin");
+    admin
+        .run_command(
+            doc! {
+                "configureFailPoint": "failCommand",
+                "mode": {"times": 3},
+                "data": {
+                    "failCommands": ["commitTransaction"],
+                    "errorCode": 91,
+                    "errorLabels": ["UnknownTransactionCommitResult"],
+                },
+            },
+            None,
+        )
+        .await
+        .unwrap();
+
+    let result = db
+        .transaction(&id, |db, id, session| {
+            Box::pin(async move {
+                let update = doc! {"$set": {"category": "archive"}};
+                session
+                    .find_one_and_update(&db.note_collection, doc! {"_id": *id}, update, None)
+                    .await
+                    .map_err(MongoQueryError)?;
+                Ok(())
+            })
+        })
+        .await;
+
+    admin
+        .run_command(
+            doc! {"configureFailPoint": "failCommand", "mode": "off"},
+            None,
+        )
+        .await
+        .unwrap();
+    result.unwrap();
+    assert_eq!(category(&db, id).await, "archive");
+}
@@ -0,0 +1,204 @@
+mod common;
+
+use common::TestServer;
+use serde_json::{json, Value};
+
+/// Creates a categorized note and returns its URL.
+async fn categorized_note(server: &TestServer) -> String {
+    let response = server
+        .client
+        .post(server.url("/api/notes"))
+        .json(&json!({"title": "Groceries", "content": "Milk", "category": "home", "published": true}))
+        .send()
+        .await
+        .unwrap();
+    let body: Value = response.json().await.unwrap();
+
+    server.url(&format!(
+        "/api/notes/{}",
+        body["data"]["note"]["id"].as_str().unwrap()
+    ))
+}
+
+/// Sends a PATCH request with the given content type.
+async fn patch(
+    server: &TestServer,
+    url: &str,
+    content_type: &str,
+    body: Value,
+) -> reqwest::Response {
+    server
+        .client
+        .patch(url)
+        .header("content-type", content_type)
+        .body(body.to_string())
+        .send()
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn put_replaces_the_whole_note() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = server
+        .client
+        .put(&url)
+        .json(&json!({"title": "Shopping", "content": "Bread"}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    let note = &body["data"]["note"];
+    assert_eq!(note["title"], "Shopping");
+    assert_eq!(note["content"], "Bread");
+    assert_eq!(note["category"], "");
+    assert_eq!(note["published"], false);
+}
+
+#[tokio::test]
+async fn put_requires_a_complete_note() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = server
+        .client
+        .put(&url)
+        .json(&json!({"title": "Only a title"}))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn merge_patch_removes_null_fields() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = patch(
+        &server,
+        &url,
+        "application/merge-patch+json",
+        json!({"category": null, "content": "Milk and eggs"}),
+    )
+    .await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    let note = &body["data"]["note"];
+    assert_eq!(note["title"], "Groceries");
+    assert_eq!(note["content"], "Milk and eggs");
+    assert_eq!(note["category"], "");
+    assert_eq!(note["published"], true);
+}
+
+#[tokio::test]
+async fn merge_patch_cannot_remove_required_fields() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = patch(
+        &server,
+        &url,
+        "application/merge-patch+json",
+        json!({"title": null}),
+    )
+    .await;
+    assert_eq!(response.status(), 422);
+
+    let response = patch(
+        &server,
+        &url,
+        "application/merge-patch+json",
+        json!({"owner": "bob"}),
+    )
+    .await;
+    assert_eq!(response.status(), 422);
+}
+
+#[tokio::test]
+async fn json_patch_applies_all_operations() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = patch(
+        &server,
+        &url,
+        "application/json-patch+json",
+        json!([
+            {"op": "test", "path": "/title", "value": "Groceries"},
+            {"op": "replace", "path": "/title", "value": "Shopping"},
+            {"op": "remove", "path": "/category"},
+            {"op": "add", "path": "/published", "value": false},
+        ]),
+    )
+    .await;
+
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    let note = &body["data"]["note"];
+    assert_eq!(note["title"], "Shopping");
+    assert_eq!(note["category"], "");
+    assert_eq!(note["published"], false);
+}
+
+#[tokio::test]
+async fn json_patch_is_all_or_nothing() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = patch(
+        &server,
+        &url,
+        "application/json-patch+json",
+        json!([
+            {"op": "replace", "path": "/title", "value": "Shopping"},
+            {"op": "test", "path": "/content", "value": "Bread"},
+        ]),
+    )
+    .await;
+    assert_eq!(response.status(), 422);
+
+    // The replace operation before the failed test was not applied
+    let body: Value = server
+        .client
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(body["data"]["note"]["title"], "Groceries");
+}
+
+#[tokio::test]
+async fn json_patch_rejects_unsupported_operations() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = patch(
+        &server,
+        &url,
+        "application/json-patch+json",
+        json!([{"op": "copy", "from": "/title", "path": "/content"}]),
+    )
+    .await;
+
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn patch_rejects_unknown_content_types() {
+    let server = TestServer::spawn();
+    let url = categorized_note(&server).await;
+
+    let response = patch(&server, &url, "text/plain", json!({"title": "Shopping"})).await;
+
+    assert_eq!(response.status(), 415);
+}
@@ -0,0 +1,237 @@
+mod common;
+
+use chrono::{Duration as ChronoDuration, Utc};
+use common::{limits, MemoryStore, TestServer};
+use mongodb::bson::{doc, from_document};
+use mongodb::error::{Error as MongoError, ErrorKind, WriteError, WriteFailure};
+use rust_mongodb_crud::errors::Error::{MongoDuplicateError, MongoQueryError};
+use rust_mongodb_crud::publishing::{is_slug_conflict, spawn_publisher, SLUG_INDEX};
+use rust_mongodb_crud::store::Store;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Creates a note from a JSON body and returns the created note.
+async fn create(server: &TestServer, body: Value) -> Value {
+    let response = server
+        .client
+        .post(server.url("/api/notes"))
+        .json(&body)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+
+    let body: Value = response.json().await.unwrap();
+    body["data"]["note"].clone()
+}
+
+#[tokio::test]
+async fn slugs_are_derived_from_titles() {
+    let server = TestServer::spawn();
+
+    let first = create(&server, json!({"title": "Hello, World!", "content": "a"})).await;
+    let second = create(&server, json!({"title": "hello world?", "content": "b"})).await;
+
+    assert_eq!(first["slug"], "hello-world");
+    assert_eq!(second["slug"], "hello-world-2");
+}
+
+#[tokio::test]
+async fn public_endpoint_only_serves_published_notes() {
+    let server = TestServer::spawn();
+    let note = create(&server, json!({"title": "Draft", "content": "**soon**"})).await;
+    let url = server.url("/api/public/notes/draft");
+
+    let response = server.client.get(&url).send().await.unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Publishing the note makes it readable without a user
+    let response = server
+        .client
+        .patch(server.url(&format!("/api/notes/{}", note["id"].as_str().unwrap())))
+        .json(&json!({"published": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let response = server
+        .client
+        .get(format!("{}?render=html", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["title"], "Draft");
+    assert_eq!(
+        body["data"]["note"]["contentHtml"],
+        "<p><strong>soon</strong></p>\n"
+    );
+
+    let response = server
+        .client
+        .get(server.url("/api/public/notes/missing"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn public_endpoint_hides_the_owner() {
+    let server = TestServer::spawn();
+    let response = server
+        .client
+        .post(server.url("/api/notes"))
+        .header("x-user-id", "alice")
+        .json(&json!({"title": "Announcement", "content": "Hi", "published": true}))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 201);
+
+    let response = server
+        .client
+        .get(server.url("/api/public/notes/announcement"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["title"], "Announcement");
+    assert!(body["data"]["note"].get("owner").is_none());
+}
+
+#[tokio::test]
+async fn scheduled_notes_are_published_when_due() {
+    let store = Arc::new(MemoryStore::default());
+    let server = TestServer::spawn_with(store.clone(), limits(1000, 1000));
+    let now = Utc::now();
+
+    let due = create(
+        &server,
+        json!({"title": "Due", "content": "a", "publishAt": now - ChronoDuration::minutes(1)}),
+    )
+    .await;
+    let later = create(
+        &server,
+        json!({"title": "Later", "content": "b", "publishAt": now + ChronoDuration::days(1)}),
+    )
+    .await;
+    assert_eq!(due["published"], false);
+    assert!(later["publishAt"].is_string());
+
+    let response = server
+        .client
+        .get(server.url("/api/public/notes/due"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+
+    // Only the note whose time has passed is published, and its schedule is cleared
+    let store: Store = store;
+    assert_eq!(store.publish_due_notes(now).await.unwrap(), 1);
+
+    let response = server
+        .client
+        .get(server.url("/api/public/notes/due"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["published"], true);
+    assert!(body["data"]["note"].get("publishAt").is_none());
+
+    let response = server
+        .client
+        .get(server.url("/api/public/notes/later"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn background_publisher_publishes_scheduled_notes() {
+    let store: Store = Arc::new(MemoryStore::default());
+    let server = TestServer::spawn_with(store.clone(), limits(1000, 1000));
+    create(
+        &server,
+        json!({"title": "Scheduled", "content": "a", "publishAt": Utc::now()}),
+    )
+    .await;
+
+    let publisher = spawn_publisher(store, Duration::from_millis(10));
+
+    // Wait for a few runs of the publisher
+    let mut status = 0;
+    for _ in 0..50 {
+        let response = server
+            .client
+            .get(server.url("/api/public/notes/scheduled"))
+            .send()
+            .await
+            .unwrap();
+        status = response.status().as_u16();
+        if status == 200 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    publisher.abort();
+
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn schedules_can_be_patched_away() {
+    let server = TestServer::spawn();
+    let note = create(
+        &server,
+        json!({"title": "Cancelled", "content": "a", "publishAt": Utc::now() + ChronoDuration::days(1)}),
+    )
+    .await;
+
+    let response = server
+        .client
+        .patch(server.url(&format!("/api/notes/{}", note["id"].as_str().unwrap())))
+        .header("content-type", "application/merge-patch+json")
+        .body(json!({"publishAt": null}).to_string())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body: Value = response.json().await.unwrap();
+    assert!(body["data"]["note"].get("publishAt").is_none());
+    assert_eq!(body["data"]["note"]["slug"], "cancelled");
+}
+
+/// Builds the error of a write rejected by the server with `code` and `message`.
+fn write_error(code: i32, message: &str) -> MongoError {
+    let write_error: WriteError = from_document(doc! {"code": code, "errmsg": message}).unwrap();
+    ErrorKind::Write(WriteFailure::WriteError(write_error)).into()
+}
+
+#[test]
+fn slug_conflicts_are_told_apart_by_their_error_code_and_index() {
+    let slug = format!(
+        "E11000 duplicate key error collection: app.notes index: {} dup key: {{ slug: \"plan\" }}",
+        SLUG_INDEX
+    );
+    assert!(is_slug_conflict(&MongoDuplicateError(write_error(
+        11000, &slug
+    ))));
+
+    // Another unique index, or another error mentioning the slug index, is not a slug conflict
+    let title = "E11000 duplicate key error collection: app.notes index: title_unique_global \
+        dup key: { title: \"slug_unique\" }";
+    assert!(!is_slug_conflict(&MongoDuplicateError(write_error(
+        11000, title
+    ))));
+    assert!(!is_slug_conflict(&MongoQueryError(write_error(121, &slug))));
+}
@@ -0,0 +1,123 @@
+mod common;
+
+use common::TestServer;
+use serde_json::{json, Value};
+
+#[tokio::test]
+async fn renders_sanitized_html_on_request() {
+    let server = TestServer::spawn();
+    let id = server
+        .create_note(
+            "Markdown",
+            "# Plan\n\n- [x] Write\n- [ ] Ship\n\n| a | b |\n|---|---|\n| 1 | 2 |\n\n[link](javascript:alert(1)) <img src=x onerror=alert(1)>\n\n<script>alert(1)</script>",
+        )
+        .await;
+    let url = server.url(&format!("/api/notes/{}", id));
+
+    // Only rendered when requested
+    let body: Value = server
+        .client
+        .get(&url)
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert!(body["data"]["note"].get("contentHtml").is_none());
+
+    let response = server
+        .client
+        .get(format!("{}?render=html", url))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    let html = body["data"]["note"]["contentHtml"].as_str().unwrap();
+
+    assert!(html.contains("<h1>Plan</h1>"));
+    assert!(html.contains(r#"<input disabled="" type="checkbox" checked="">"#));
+    assert!(html.contains("<table>"));
+    assert!(html.contains("<td>2</td>"));
+    assert!(!html.contains("<script"));
+    assert!(html.contains("<a rel=\"noopener noreferrer\">link</a>"));
+    assert!(!html.contains("javascript:"));
+    assert!(!html.contains("onerror"));
+}
+
+#[tokio::test]
+async fn rendered_html_follows_updates() {
+    let server = TestServer::spawn();
+    let id = server.create_note("Cached", "*first*").await;
+    let url = server.url(&format!("/api/notes/{}", id));
+
+    let body: Value = server
+        .client
+        .get(format!("{}?render=html", url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        body["data"]["note"]["contentHtml"],
+        "<p><em>first</em></p>\n"
+    );
+
+    server
+        .client
+        .patch(&url)
+        .json(&json!({"content": "*second*"}))
+        .send()
+        .await
+        .unwrap();
+
+    let body: Value = server
+        .client
+        .get(format!("{}?render=html", url))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(
+        body["data"]["note"]["contentHtml"],
+        "<p><em>second</em></p>\n"
+    );
+}
+
+#[tokio::test]
+async fn renders_listed_notes() {
+    let server = TestServer::spawn();
+    server.create_note("Listed", "~~done~~").await;
+
+    let body: Value = server
+        .client
+        .get(server.url("/api/notes?render=html"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+
+    assert_eq!(body["notes"][0]["contentHtml"], "<p><del>done</del></p>\n");
+}
+
+#[tokio::test]
+async fn rejects_unknown_render_formats() {
+    let server = TestServer::spawn();
+    let id = server.create_note("Plain", "text").await;
+
+    let response = server
+        .client
+        .get(server.url(&format!("/api/notes/{}?render=pdf", id)))
+        .send()
+        .await
+        .unwrap();
+
+    assert_eq!(response.status(), 400);
+}
@@ -0,0 +1,233 @@
+mod common;
+
+use common::TestServer;
+use reqwest::{Method, Response};
+use serde_json::{json, Value};
+
+/// Shares a note of alice with `grantee`.
+async fn grant(server: &TestServer, id: &str, grantee: &str, role: &str) -> Response {
+    server
+        .request(
+            Method::PUT,
+            &format!("/api/notes/{}/acl/{}", id, grantee),
+            Some("alice"),
+            Some(json!({"role": role})),
+        )
+        .await
+}
+
+#[tokio::test]
+async fn owned_notes_are_hidden_from_other_users() {
+    let server = TestServer::spawn();
+    let id = server.create_owned_note("alice", "Private").await;
+    let path = format!("/api/notes/{}", id);
+
+    for user in [None, Some("bob")] {
+        let response = server.request(Method::GET, &path, user, None).await;
+        assert_eq!(response.status(), 404);
+
+        let response = server.request(Method::GET, "/api/notes", user, None).await;
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["results"], 0);
+    }
+
+    let response = server
+        .request(Method::GET, "/api/notes", Some("alice"), None)
+        .await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 1);
+}
+
+#[tokio::test]
+async fn roles_limit_what_collaborators_can_do() {
+    let server = TestServer::spawn();
+    let id = server.create_owned_note("alice", "Shared").await;
+    let path = format!("/api/notes/{}", id);
+    assert_eq!(grant(&server, &id, "bob", "viewer").await.status(), 200);
+
+    // Viewers can read but not edit
+    let response = server.request(Method::GET, &path, Some("bob"), None).await;
+    assert_eq!(response.status(), 200);
+    let edit = json!({"content": "changed"});
+    let response = server
+        .request(Method::PATCH, &path, Some("bob"), Some(edit.clone()))
+        .await;
+    assert_eq!(response.status(), 403);
+
+    // Editors can edit but neither delete nor reshare the note
+    let response = grant(&server, &id, "bob", "editor").await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["acl"], json!([{"user": "bob", "role": "editor"}]));
+
+    let response = server
+        .request(Method::PATCH, &path, Some("bob"), Some(edit))
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = server
+        .request(Method::DELETE, &path, Some("bob"), None)
+        .await;
+    assert_eq!(response.status(), 403);
+    let acl = format!("{}/acl", path);
+    let response = server.request(Method::GET, &acl, Some("bob"), None).await;
+    assert_eq!(response.status(), 403);
+
+    // Revoking access hides the note again
+    let response = server
+        .request(Method::DELETE, &format!("{}/bob", acl), Some("alice"), None)
+        .await;
+    assert_eq!(response.status(), 200);
+    let response = server.request(Method::GET, &path, Some("bob"), None).await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn shared_with_me_lists_notes_of_other_users() {
+    let server = TestServer::spawn();
+    let shared = server.create_owned_note("alice", "Shared").await;
+    server.create_owned_note("alice", "Private").await;
+    server.create_owned_note("bob", "Own").await;
+    grant(&server, &shared, "bob", "viewer").await;
+
+    let response = server
+        .request(Method::GET, "/api/notes/shared-with-me", Some("bob"), None)
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 1);
+    assert_eq!(body["notes"][0]["title"], "Shared");
+
+    // Pages are counted from 1
+    let response = server
+        .request(
+            Method::GET,
+            "/api/notes/shared-with-me?page=0",
+            Some("bob"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+
+    // Listing shared notes requires a user
+    let response = server
+        .request(Method::GET, "/api/notes/shared-with-me", None, None)
+        .await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn share_links_grant_read_access_until_revoked() {
+    let server = TestServer::spawn();
+    let id = server.create_owned_note("alice", "Linked").await;
+    let links = format!("/api/notes/{}/links", id);
+
+    let response = server
+        .request(
+            Method::POST,
+            &links,
+            Some("alice"),
+            Some(json!({"expiresIn": 0})),
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+
+    let response = server
+        .request(Method::POST, &links, Some("alice"), Some(json!({})))
+        .await;
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let link = &body["data"]["link"];
+    let token = link["token"].as_str().unwrap();
+
+    let response = server
+        .request(Method::GET, &format!("/api/links/{}", token), None, None)
+        .await;
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["note"]["title"], "Linked");
+
+    // Tokens are only returned when the link is created
+    let acl = format!("/api/notes/{}/acl", id);
+    let response = server.request(Method::GET, &acl, Some("alice"), None).await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["links"][0]["id"], link["id"]);
+    assert!(body["links"][0].get("token").is_none());
+
+    let response = server
+        .request(
+            Method::DELETE,
+            &format!("{}/{}", links, link["id"].as_str().unwrap()),
+            Some("alice"),
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), 204);
+
+    let response = server
+        .request(Method::GET, &format!("/api/links/{}", token), None, None)
+        .await;
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn invalid_grants_are_rejected() {
+    let server = TestServer::spawn();
+    let id = server.create_owned_note("alice", "Mine").await;
+
+    // The owner cannot be granted a role, and only viewer and editor roles exist
+    assert_eq!(grant(&server, &id, "alice", "viewer").await.status(), 400);
+    assert_eq!(grant(&server, &id, "bob", "owner").await.status(), 400);
+
+    // Notes without an owner are accessible to everyone already
+    let response = server
+        .request(
+            Method::POST,
+            "/api/notes",
+            None,
+            Some(json!({"title": "Public", "content": "shared"})),
+        )
+        .await;
+    let body: Value = response.json().await.unwrap();
+    let response = server
+        .request(
+            Method::PUT,
+            &format!(
+                "/api/notes/{}/acl/bob",
+                body["data"]["note"]["id"].as_str().unwrap()
+            ),
+            None,
+            Some(json!({"role": "viewer"})),
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+}
+
+#[tokio::test]
+async fn notes_without_an_owner_have_no_sharing_settings() {
+    let server = TestServer::spawn();
+    let response = server
+        .request(
+            Method::POST,
+            "/api/notes",
+            None,
+            Some(json!({"title": "Public", "content": "shared"})),
+        )
+        .await;
+    let body: Value = response.json().await.unwrap();
+    let id = body["data"]["note"]["id"].as_str().unwrap().to_string();
+
+    // Nobody may hand out links or edit the access control list of a note anyone can edit
+    for user in [None, Some("bob")] {
+        for (method, path, body) in [
+            (Method::GET, format!("/api/notes/{}/acl", id), None),
+            (Method::DELETE, format!("/api/notes/{}/acl/bob", id), None),
+            (
+                Method::POST,
+                format!("/api/notes/{}/links", id),
+                Some(json!({"expiresIn": 60})),
+            ),
+        ] {
+            let response = server.request(method, &path, user, body).await;
+            assert_eq!(response.status(), 400, "{:?} on {}", user, path);
+        }
+    }
+}
@@ -0,0 +1,272 @@
+mod common;
+
+use async_trait::async_trait;
+use common::{limits, MemoryStore, TestServer, ADMIN};
+use mongodb::bson::{doc, from_document};
+use mongodb::error::{
+    Error as MongoError, ErrorKind, WriteConcernError, WriteFailure, TRANSIENT_TRANSACTION_ERROR,
+    UNKNOWN_TRANSACTION_COMMIT_RESULT,
+};
+use reqwest::{Method, Response};
+use rust_mongodb_crud::errors::Error::{InvalidQueryError, MongoQueryError};
+use rust_mongodb_crud::model::TitleScope;
+use rust_mongodb_crud::transactions::{self, Transaction, TRANSACTION_ATTEMPTS};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Creates a note owned by `owner` in `category` and returns its ID.
+async fn create(server: &TestServer, owner: &str, title: &str, category: &str) -> String {
+    let response = server
+        .request(
+            Method::POST,
+            "/api/notes",
+            Some(owner),
+            Some(json!({"title": title, "content": "draft", "category": category})),
+        )
+        .await;
+    assert_eq!(response.status(), 201);
+
+    let body: Value = response.json().await.unwrap();
+    body["data"]["note"]["id"].as_str().unwrap().to_string()
+}
+
+/// Moves the notes of `from` to `to` as `user`.
+async fn move_category(server: &TestServer, user: &str, from: &str, to: &str) -> Response {
+    server
+        .request(
+            Method::POST,
+            "/api/notes/move-category",
+            Some(user),
+            Some(json!({"from": from, "to": to})),
+        )
+        .await
+}
+
+/// Returns the category of the note with ID `id`, as seen by `user`.
+async fn category(server: &TestServer, user: &str, id: &str) -> Value {
+    let path = format!("/api/notes/{}", id);
+    let response = server.request(Method::GET, &path, Some(user), None).await;
+    let body: Value = response.json().await.unwrap();
+    body["data"]["note"]["category"].clone()
+}
+
+#[tokio::test]
+async fn notes_move_between_categories() {
+    let server = TestServer::spawn();
+    let first = create(&server, "alice", "First", "drafts").await;
+    let second = create(&server, "alice", "Second", "drafts").await;
+    let other = create(&server, "alice", "Other", "ideas").await;
+
+    let response = move_category(&server, "alice", "drafts", "archive").await;
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["status"], "success");
+    assert_eq!(body["moved"], 2);
+
+    assert_eq!(category(&server, "alice", &first).await, "archive");
+    assert_eq!(category(&server, "alice", &second).await, "archive");
+    assert_eq!(category(&server, "alice", &other).await, "ideas");
+}
+
+#[tokio::test]
+async fn only_writable_notes_are_moved() {
+    let server = TestServer::spawn();
+    let own = create(&server, "alice", "Own", "drafts").await;
+    let viewed = create(&server, "bob", "Viewed", "drafts").await;
+    let edited = create(&server, "carol", "Edited", "drafts").await;
+    for (owner, id, role) in [("bob", &viewed, "viewer"), ("carol", &edited, "editor")] {
+        let path = format!("/api/notes/{}/acl/alice", id);
+        let body = json!({"role": role});
+        let response = server
+            .request(Method::PUT, &path, Some(owner), Some(body))
+            .await;
+        assert_eq!(response.status(), 200);
+    }
+
+    let response = move_category(&server, "alice", "drafts", "archive").await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["moved"], 2);
+
+    assert_eq!(category(&server, "alice", &own).await, "archive");
+    assert_eq!(category(&server, "bob", &viewed).await, "drafts");
+    assert_eq!(category(&server, "carol", &edited).await, "archive");
+}
+
+#[tokio::test]
+async fn moves_are_all_or_nothing() {
+    let store = Arc::new(MemoryStore::with_scope(TitleScope::Category));
+    let server = TestServer::spawn_with(store, limits(1000, 1000));
+    let first = create(&server, "alice", "First", "drafts").await;
+    let plan = create(&server, "alice", "Plan", "drafts").await;
+    create(&server, "alice", "Plan", "archive").await;
+
+    // The second note would share its title with a note of the target category
+    let response = move_category(&server, "alice", "drafts", "archive").await;
+    assert_eq!(response.status(), 409);
+
+    assert_eq!(category(&server, "alice", &first).await, "drafts");
+    assert_eq!(category(&server, "alice", &plan).await, "drafts");
+}
+
+#[tokio::test]
+async fn moves_are_recorded_in_the_audit_log() {
+    let server = TestServer::spawn_with_admin();
+    let id = create(&server, "alice", "First", "drafts").await;
+
+    let response = move_category(&server, "alice", "drafts", "archive").await;
+    assert_eq!(response.status(), 200);
+
+    let path = format!("/api/audit?note={}", id);
+    let response = server.request(Method::GET, &path, Some(ADMIN), None).await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 2);
+    assert_eq!(body["records"][0]["action"], "update");
+    assert_eq!(body["records"][0]["actor"], "alice");
+    assert_eq!(body["records"][0]["before"], json!({"category": "drafts"}));
+    assert_eq!(body["records"][0]["after"], json!({"category": "archive"}));
+}
+
+#[tokio::test]
+async fn moving_to_the_same_category_moves_nothing() {
+    let server = TestServer::spawn();
+    create(&server, "alice", "First", "drafts").await;
+
+    let response = move_category(&server, "alice", "drafts", "drafts").await;
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["moved"], 0);
+
+    let response = server
+        .request(
+            Method::POST,
+            "/api/notes/move-category",
+            Some("alice"),
+            Some(json!({"from": "drafts"})),
+        )
+        .await;
+    assert_eq!(response.status(), 400);
+}
+
+/// Builds a server error carrying the error `label`.
+fn labelled(label: &str) -> MongoError {
+    let error: WriteConcernError = from_document(doc! {
+        "code": 64,
+        "codeName": "WriteConcernFailed",
+        "errmsg": "injected by the test",
+        "errorLabels": [label],
+    })
+    .unwrap();
+    ErrorKind::Write(WriteFailure::WriteConcernError(error)).into()
+}
+
+/// Transaction recording its lifecycle and failing its commits with the queued errors.
+#[derive(Default)]
+struct FakeTransaction {
+    starts: usize,
+    commits: usize,
+    aborts: usize,
+    commit_errors: VecDeque<MongoError>,
+}
+
+#[async_trait]
+impl Transaction for FakeTransaction {
+    async fn start_transaction(&mut self) -> mongodb::error::Result<()> {
+        self.starts += 1;
+        Ok(())
+    }
+
+    async fn commit_transaction(&mut self) -> mongodb::error::Result<()> {
+        self.commits += 1;
+        match self.commit_errors.pop_front() {
+            Some(e) => Err(e),
+            None => Ok(()),
+        }
+    }
+
+    async fn abort_transaction(&mut self) -> mongodb::error::Result<()> {
+        self.aborts += 1;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn transient_errors_run_the_work_again() {
+    let mut transaction = FakeTransaction::default();
+    let failures = AtomicUsize::new(2);
+
+    let result = transactions::run(&mut transaction, &failures, |failures, _| {
+        Box::pin(async move {
+            let left = failures.load(Ordering::SeqCst);
+            if left == 0 {
+                return Ok(42);
+            }
+            failures.store(left - 1, Ordering::SeqCst);
+            Err(MongoQueryError(labelled(TRANSIENT_TRANSACTION_ERROR)))
+        })
+    })
+    .await;
+
+    assert_eq!(result.unwrap(), 42);
+    assert_eq!(
+        (transaction.starts, transaction.aborts, transaction.commits),
+        (3, 2, 1)
+    );
+}
+
+#[tokio::test]
+async fn transient_errors_are_returned_after_the_last_attempt() {
+    let mut transaction = FakeTransaction::default();
+
+    let result: rust_mongodb_crud::Result<()> = transactions::run(&mut transaction, &(), |_, _| {
+        Box::pin(async { Err(MongoQueryError(labelled(TRANSIENT_TRANSACTION_ERROR))) })
+    })
+    .await;
+
+    assert!(result.unwrap_err().is_transient());
+    assert_eq!(transaction.starts, TRANSACTION_ATTEMPTS);
+    assert_eq!(transaction.aborts, TRANSACTION_ATTEMPTS);
+    assert_eq!(transaction.commits, 0);
+}
+
+#[tokio::test]
+async fn other_errors_abort_the_transaction_at_once() {
+    let mut transaction = FakeTransaction::default();
+
+    let result: rust_mongodb_crud::Result<()> = transactions::run(&mut transaction, &(), |_, _| {
+        Box::pin(async { Err(InvalidQueryError("injected by the test".to_string())) })
+    })
+    .await;
+
+    assert!(matches!(result, Err(InvalidQueryError(_))));
+    assert_eq!((transaction.starts, transaction.aborts), (1, 1));
+}
+
+#[tokio::test]
+async fn commits_with_an_unknown_result_are_retried_alone() {
+    let mut transaction = FakeTransaction {
+        commit_errors: VecDeque::from([
+            labelled(UNKNOWN_TRANSACTION_COMMIT_RESULT),
+            labelled(UNKNOWN_TRANSACTION_COMMIT_RESULT),
+        ]),
+        ..Default::default()
+    };
+
+    let result = transactions::run(&mut transaction, &(), |_, _| Box::pin(async { Ok(()) })).await;
+
+    assert!(result.is_ok());
+    assert_eq!((transaction.starts, transaction.commits), (1, 3));
+}
+
+#[tokio::test]
+async fn transient_commit_errors_run_the_work_again() {
+    let mut transaction = FakeTransaction {
+        commit_errors: VecDeque::from([labelled(TRANSIENT_TRANSACTION_ERROR)]),
+        ..Default::default()
+    };
+
+    let result = transactions::run(&mut transaction, &(), |_, _| Box::pin(async { Ok(()) })).await;
+
+    assert!(result.is_ok());
+    assert_eq!((transaction.starts, transaction.commits), (2, 2));
+}
@@ -0,0 +1,336 @@
+mod common;
+
+use common::{TestServer, ADMIN};
+use reqwest::{Method, RequestBuilder};
+use serde_json::{json, Value};
+
+/// Member of the workspaces created by the tests.
+const MEMBER: &str = "alice";
+
+/// Builds a request to `path` as `user`, optionally selecting a workspace through the header.
+fn request(
+    server: &TestServer,
+    method: Method,
+    path: &str,
+    user: Option<&str>,
+    workspace: Option<&str>,
+) -> RequestBuilder {
+    let mut request = server.client.request(method, server.url(path));
+    if let Some(user) = user {
+        request = request.header("x-user-id", user);
+    }
+    match workspace {
+        Some(workspace) => request.header("x-workspace-id", workspace),
+        None => request,
+    }
+}
+
+/// Creates a workspace as the administrator, with [`MEMBER`] as its only member.
+async fn create_workspace(server: &TestServer, id: &str) {
+    let response = request(
+        server,
+        Method::POST,
+        "/api/admin/workspaces",
+        Some(ADMIN),
+        None,
+    )
+    .json(&json!({"id": id, "members": [MEMBER]}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+}
+
+/// Returns the number of notes listed at `path` for [`MEMBER`].
+async fn count(server: &TestServer, path: &str, workspace: Option<&str>) -> u64 {
+    let response = request(server, Method::GET, path, Some(MEMBER), workspace)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 200);
+
+    let body: Value = response.json().await.unwrap();
+    body["results"].as_u64().unwrap()
+}
+
+#[tokio::test]
+async fn workspaces_isolate_their_notes() {
+    let server = TestServer::spawn_with_admin();
+    create_workspace(&server, "acme").await;
+    create_workspace(&server, "beta").await;
+
+    let response = request(
+        &server,
+        Method::POST,
+        "/api/notes",
+        Some(MEMBER),
+        Some("acme"),
+    )
+    .json(&json!({"title": "Roadmap", "content": "Ship it"}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let id = body["data"]["note"]["id"].as_str().unwrap().to_string();
+
+    // The header and the path prefix select the same workspace
+    assert_eq!(count(&server, "/api/notes", Some("acme")).await, 1);
+    assert_eq!(count(&server, "/api/workspaces/acme/notes", None).await, 1);
+    assert_eq!(count(&server, "/api/notes", Some("beta")).await, 0);
+    assert_eq!(count(&server, "/api/notes", None).await, 0);
+
+    let path = format!("/api/notes/{}", id);
+    let response = request(&server, Method::GET, &path, Some(MEMBER), Some("beta"))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+    let response = request(
+        &server,
+        Method::GET,
+        &format!("/api/workspaces/acme/notes/{}", id),
+        Some(MEMBER),
+        None,
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+
+    // Titles only need to be unique within a workspace
+    let response = request(
+        &server,
+        Method::POST,
+        "/api/workspaces/beta/notes",
+        Some(MEMBER),
+        None,
+    )
+    .json(&json!({"title": "Roadmap", "content": "Other"}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+}
+
+#[tokio::test]
+async fn public_notes_are_served_per_workspace() {
+    let server = TestServer::spawn_with_admin();
+    create_workspace(&server, "acme").await;
+
+    let response = request(
+        &server,
+        Method::POST,
+        "/api/notes",
+        Some(MEMBER),
+        Some("acme"),
+    )
+    .json(&json!({"title": "Launch", "content": "Today", "published": true}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/workspaces/acme/public/notes/launch",
+        None,
+        None,
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+    let response = request(&server, Method::GET, "/api/public/notes/launch", None, None)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), 404);
+}
+
+#[tokio::test]
+async fn invalid_workspace_selections_are_rejected() {
+    let server = TestServer::spawn_with_admin();
+    create_workspace(&server, "acme").await;
+
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/workspaces/acme/notes",
+        Some(MEMBER),
+        Some("beta"),
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/notes",
+        Some(MEMBER),
+        Some("Not Valid"),
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 400);
+
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/workspaces/missing/notes",
+        Some(MEMBER),
+        None,
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 404);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["message"], "Workspace not found");
+}
+
+#[tokio::test]
+async fn archived_workspaces_are_gone() {
+    let server = TestServer::spawn_with_admin();
+    create_workspace(&server, "acme").await;
+
+    let archive = |id: &str| {
+        request(
+            &server,
+            Method::POST,
+            &format!("/api/admin/workspaces/{}/archive", id),
+            Some(ADMIN),
+            None,
+        )
+        .send()
+    };
+    let response = archive("acme").await.unwrap();
+    assert_eq!(response.status(), 200);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["workspace"]["archived"], true);
+    assert_eq!(archive("missing").await.unwrap().status(), 404);
+
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/notes",
+        Some(MEMBER),
+        Some("acme"),
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 410);
+
+    // Archived workspaces stay listed
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/admin/workspaces",
+        Some(ADMIN),
+        None,
+    )
+    .send()
+    .await
+    .unwrap();
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["results"], 1);
+    assert_eq!(body["workspaces"][0]["id"], "acme");
+}
+
+#[tokio::test]
+async fn workspace_administration_is_restricted() {
+    let server = TestServer::spawn_with_admin();
+
+    for user in [None, Some("bob")] {
+        let response = request(&server, Method::POST, "/api/admin/workspaces", user, None)
+            .json(&json!({"id": "acme"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403);
+    }
+
+    create_workspace(&server, "acme").await;
+    let create = |id: &'static str| {
+        request(
+            &server,
+            Method::POST,
+            "/api/admin/workspaces",
+            Some(ADMIN),
+            None,
+        )
+        .json(&json!({"id": id, "name": "Acme"}))
+        .send()
+    };
+    assert_eq!(create("acme").await.unwrap().status(), 409);
+    assert_eq!(create("Acme Corp").await.unwrap().status(), 400);
+}
+
+#[tokio::test]
+async fn workspaces_are_reserved_to_their_members() {
+    let server = TestServer::spawn_with_admin();
+    create_workspace(&server, "acme").await;
+    let response = request(
+        &server,
+        Method::POST,
+        "/api/admin/workspaces",
+        Some(ADMIN),
+        None,
+    )
+    .json(&json!({"id": "beta", "members": ["bob"]}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["data"]["workspace"]["members"], json!(["bob"]));
+
+    let response = request(
+        &server,
+        Method::POST,
+        "/api/notes",
+        Some(MEMBER),
+        Some("acme"),
+    )
+    .json(&json!({"title": "Roadmap", "content": "Ship it"}))
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 201);
+    let body: Value = response.json().await.unwrap();
+    let id = body["data"]["note"]["id"].as_str().unwrap().to_string();
+
+    // Neither the header nor the path prefix let other users in
+    for (user, workspace, path) in [
+        (Some("bob"), Some("acme"), "/api/notes".to_string()),
+        (Some("bob"), None, "/api/workspaces/acme/notes".to_string()),
+        (Some("bob"), Some("acme"), format!("/api/notes/{}", id)),
+        (None, None, format!("/api/workspaces/acme/notes/{}", id)),
+        (Some(ADMIN), Some("acme"), "/api/notes".to_string()),
+    ] {
+        let response = request(&server, Method::GET, &path, user, workspace)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), 403, "{:?} reading {}", user, path);
+        let body: Value = response.json().await.unwrap();
+        assert_eq!(body["message"], "You are not a member of this workspace");
+    }
+
+    let response = request(
+        &server,
+        Method::GET,
+        "/api/notes",
+        Some("bob"),
+        Some("beta"),
+    )
+    .send()
+    .await
+    .unwrap();
+    assert_eq!(response.status(), 200);
+}
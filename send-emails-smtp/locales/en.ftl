@@ -0,0 +1,20 @@
+# Messages of the emails in English, the default locale.
+
+-company = RAprogramm INC.
+
+greeting = Hi, { $name },
+sign-off = Good luck! { -company }
+
+verification-code-subject = Your account verification code
+verification-code-intro = Please verify your account to be able to login
+verification-code-action = Verify your account
+
+reset-password-subject = Your password reset token (valid for only { $minutes } { $minutes ->
+        [one] minute
+       *[other] minutes
+    })
+reset-password-intro =
+    Forgot password? Send a PATCH request to with your password and
+    passwordConfirm to { $url }
+reset-password-action = Reset password
+reset-password-ignore = If you didn't forget your password, please ignore this email
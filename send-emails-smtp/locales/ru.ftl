@@ -0,0 +1,21 @@
+# Messages of the emails in Russian.
+
+-company = RAprogramm INC.
+
+greeting = Здравствуйте, { $name }!
+sign-off = Удачи! { -company }
+
+verification-code-subject = Код подтверждения вашей учётной записи
+verification-code-intro = Подтвердите свою учётную запись, чтобы войти
+verification-code-action = Подтвердить учётную запись
+
+reset-password-subject = Ваш токен для сброса пароля (действителен только { $minutes } { $minutes ->
+        [one] минуту
+        [few] минуты
+       *[many] минут
+    })
+reset-password-intro =
+    Забыли пароль? Отправьте PATCH-запрос с паролем и его подтверждением
+    (passwordConfirm) на { $url }
+reset-password-action = Сбросить пароль
+reset-password-ignore = Если вы не забывали пароль, просто проигнорируйте это письмо
@@ -1,4 +1,4 @@
-use std::env::var;
+use std::{env::var, num::NonZeroU32};
 
 /// Represents configuration details required for SMTP (Simple Mail Transfer Protocol) settings.
 #[derive(Debug, Clone)]
@@ -11,38 +11,115 @@ pub struct Config {
     pub smtp_user: String,
     /// Password for authentication with the SMTP server.
     pub smtp_pass: String,
+    /// Maximum number of connections kept open to the SMTP server, at least 1.
+    pub smtp_pool_size: u32,
+    /// Number of seconds an unused SMTP connection stays open.
+    pub smtp_pool_idle_secs: u64,
     /// Sender's email address.
     pub smtp_from: String,
     /// Receiver's email address.
     pub smtp_to: String,
+    /// Mail transport used to deliver emails: `smtp`, `file`, `stdout` or `memory`.
+    pub mail_transport: String,
+    /// Directory the `file` transport writes `.eml` files to.
+    pub mail_dir: String,
+    /// Directory holding templates replacing the ones embedded in the binary, if any.
+    pub templates_dir: Option<String>,
+    /// Whether templates are reloaded when they change, to iterate on them without restarting.
+    pub dev_mode: bool,
+    /// Locale of the messages used when a translation is missing in the user's locale.
+    pub default_locale: String,
+    /// Directory of the outbound queue, persisting emails until they are delivered.
+    pub queue_dir: String,
+    /// Number of delivery attempts after which an email is moved to the dead-letter store.
+    pub queue_max_attempts: u32,
+    /// Number of seconds before the first retry of an email, doubled for every following one.
+    pub queue_retry_secs: u64,
 }
 
 impl Config {
     /// Initializes and constructs a `Config` object based on environment variables.
     ///
+    /// `MAIL_TRANSPORT` selects the mail transport (`smtp` by default) and `MAIL_DIR` the
+    /// directory of the `file` transport (`./emails` by default). `SMTP_POOL_SIZE` (10 by
+    /// default) and `SMTP_POOL_IDLE_SECS` (60 by default) size the SMTP connection pool.
+    /// `TEMPLATES_DIR` is an optional directory of templates replacing the embedded ones.
+    /// `DEV_MODE=true` reloads the templates when they change, from `./templates` unless
+    /// `TEMPLATES_DIR` is set. `DEFAULT_LOCALE` is the locale translations fall back to (`en`
+    /// by default). `QUEUE_DIR` is the directory of the outbound queue (`./queue` by default),
+    /// whose emails are attempted `QUEUE_MAX_ATTEMPTS` times (8 by default), first retried
+    /// after `QUEUE_RETRY_SECS` seconds (30 by default).
+    ///
     /// # Panics
     ///
-    /// Panics if any of the required environment variables (`SMTP_HOST`, `SMTP_PORT`, `SMTP_USER`,
-    /// `SMTP_PASS`, `SMTP_FROM`, `SMTP_TO`) are not set or if `SMTP_PORT` is not a valid `u16`.
+    /// Panics if any of the required environment variables (`SMTP_FROM`, `SMTP_TO`, and
+    /// `SMTP_HOST`, `SMTP_PORT`, `SMTP_USER`, `SMTP_PASS` for the `smtp` transport) are not set
+    /// or if `SMTP_PORT`, `SMTP_POOL_SIZE`, `SMTP_POOL_IDLE_SECS`, `QUEUE_MAX_ATTEMPTS` or
+    /// `QUEUE_RETRY_SECS` is not a valid number, or `SMTP_POOL_SIZE` is 0.
     ///
     /// # Returns
     ///
     /// A `Config` object initialized with values from the environment variables.
     pub fn init() -> Config {
-        let smtp_host = var("SMTP_HOST").expect("SMTP_HOST must be set");
-        let smtp_port = var("SMTP_PORT").expect("SMTP_PORT must be set");
-        let smtp_user = var("SMTP_USER").expect("SMTP_USER must be set");
-        let smtp_pass = var("SMTP_PASS").expect("SMTP_PASS must be set");
+        let mail_transport = var("MAIL_TRANSPORT").unwrap_or_else(|_| "smtp".to_string());
+        let mail_dir = var("MAIL_DIR").unwrap_or_else(|_| "./emails".to_string());
+        let default_locale = var("DEFAULT_LOCALE").unwrap_or_else(|_| "en".to_string());
+        let dev_mode = var("DEV_MODE").is_ok_and(|dev_mode| dev_mode == "true");
+        let templates_dir = match var("TEMPLATES_DIR") {
+            Ok(dir) => Some(dir),
+            // Watch the templates of the crate while developing
+            Err(_) if dev_mode => Some("./templates".to_string()),
+            Err(_) => None,
+        };
+
+        // The SMTP server settings are only needed to send through SMTP
+        let smtp_var = |name: &str| match mail_transport.as_str() {
+            "smtp" => var(name).unwrap_or_else(|_| panic!("{} must be set", name)),
+            _ => var(name).unwrap_or_default(),
+        };
+        let smtp_host = smtp_var("SMTP_HOST");
+        let smtp_port = smtp_var("SMTP_PORT");
+        let smtp_user = smtp_var("SMTP_USER");
+        let smtp_pass = smtp_var("SMTP_PASS");
+        // An empty pool would make every send wait for a connection forever
+        let smtp_pool_size = var("SMTP_POOL_SIZE").map_or(10, |size| {
+            size.parse::<NonZeroU32>()
+                .expect("Invalid SMTP_POOL_SIZE, expected a number of at least 1")
+                .get()
+        });
+        let smtp_pool_idle_secs = var("SMTP_POOL_IDLE_SECS").map_or(60, |secs| {
+            secs.parse::<u64>().expect("Invalid SMTP_POOL_IDLE_SECS")
+        });
+        let queue_dir = var("QUEUE_DIR").unwrap_or_else(|_| "./queue".to_string());
+        let queue_max_attempts = var("QUEUE_MAX_ATTEMPTS").map_or(8, |attempts| {
+            attempts.parse::<u32>().expect("Invalid QUEUE_MAX_ATTEMPTS")
+        });
+        let queue_retry_secs = var("QUEUE_RETRY_SECS").map_or(30, |secs| {
+            secs.parse::<u64>().expect("Invalid QUEUE_RETRY_SECS")
+        });
         let smtp_from = var("SMTP_FROM").expect("SMTP_FROM must be set");
         let smtp_to = var("SMTP_TO").expect("SMTP_TO must be set");
 
         Config {
             smtp_host,
-            smtp_port: smtp_port.parse::<u16>().expect("Invalid SMTP_PORT"),
+            smtp_port: match smtp_port.as_str() {
+                "" => 587,
+                port => port.parse::<u16>().expect("Invalid SMTP_PORT"),
+            },
             smtp_user,
             smtp_pass,
+            smtp_pool_size,
+            smtp_pool_idle_secs,
             smtp_from,
             smtp_to,
+            mail_transport,
+            mail_dir,
+            templates_dir,
+            dev_mode,
+            default_locale,
+            queue_dir,
+            queue_max_attempts,
+            queue_retry_secs,
         }
     }
 }
@@ -1,309 +1,236 @@
-use handlebars::Handlebars;
-use lettre::{
-    message::header::ContentType, transport::smtp::authentication::Credentials, AsyncSmtpTransport,
-    AsyncTransport, Message, Tokio1Executor,
+use std::sync::Arc;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::{
+    config::Config,
+    i18n::{Localizer, TranslationError},
+    inline,
+    mailer::{Mailer, MailerError},
+    queue::{Queue, QueuedEmail},
+    templates::TemplateRegistry,
+    text, User,
 };
 
-use crate::{config::Config, User};
+/// An email type, rendered from a template with its own typed context.
+///
+/// The fields of the implementing struct are the data of the template, which can reference
+/// them by name, along with `subject` and `locale`. Adding an email only requires a struct
+/// implementing this trait, its template and the messages of its subject and text.
+///
+/// # Examples
+///
+/// ```rust
+/// use serde::Serialize;
+/// use send_emails_smtp::{email::EmailTemplate, i18n::{Localizer, TranslationError}};
+///
+/// #[derive(Serialize)]
+/// struct Welcome {
+///     first_name: String,
+/// }
+///
+/// impl EmailTemplate for Welcome {
+///     const TEMPLATE_NAME: &'static str = "welcome";
+///
+///     fn subject(&self, localizer: &Localizer) -> Result<String, TranslationError> {
+///         localizer.message_with("welcome-subject", &serde_json::json!({"name": self.first_name}))
+///     }
+/// }
+/// ```
+pub trait EmailTemplate: Serialize {
+    /// Name of the template the email is rendered from, e.g. `verification_code`.
+    const TEMPLATE_NAME: &'static str;
+
+    /// Returns the subject line of the email, in the language of `localizer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message of the subject cannot be formatted.
+    fn subject(&self, localizer: &Localizer) -> Result<String, TranslationError>;
+}
 
-/// Represents an email to be sent to users for various actions.
+/// Renders emails from the compiled templates and sends them through a mail transport, or
+/// enqueues them into an outbound queue.
 pub struct Email {
-    /// Represents the user associated with the email.
-    user: User,
-    /// Contains the URL relevant to the email context.
-    url: String,
     /// Represents the sender's email address.
     from: String,
-    /// Holds configurations related to SMTP (Simple Mail Transfer Protocol).
-    config: Config,
+    /// Mail transport delivering the emails.
+    mailer: Arc<dyn Mailer>,
+    /// Compiled templates the emails are rendered from.
+    templates: Arc<TemplateRegistry>,
+    /// Outbound queue the emails are enqueued into instead of being sent right away, if any.
+    queue: Option<Arc<Queue>>,
 }
 
 impl Email {
-    /// Creates a new `Email` instance with the provided user, URL, and configuration.
+    /// Creates a new `Email` instance with the provided configuration, mail transport and
+    /// templates.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// use your_project::{Email, User, Config};
+    /// ```rust,no_run
+    /// use send_emails_smtp::{config::Config, email::Email, mailer};
+    /// use send_emails_smtp::{i18n::Translations, templates::TemplateRegistry};
+    /// use std::sync::Arc;
     ///
-    /// // Assume these values are instantiated properly in your code.
-    /// let user = User::new("John", "john@example.com");
-    /// let url = String::from("https://example.com");
-    /// let config = Config::load();
+    /// # fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// let config = Config::init();
+    /// let mailer = mailer::from_config(&config)?;
+    /// let translations = Arc::new(Translations::embedded(&config.default_locale)?);
+    /// let templates = Arc::new(TemplateRegistry::load(config.templates_dir.as_ref(), translations)?);
     ///
     /// // Create a new Email instance
-    /// let email = Email::new(user, url, config);
+    /// let email = Email::new(&config, mailer, templates);
+    /// # Ok(())
+    /// # }
     /// ```
-    pub fn new(user: User, url: String, config: Config) -> Self {
+    pub fn new(config: &Config, mailer: Arc<dyn Mailer>, templates: Arc<TemplateRegistry>) -> Self {
         // Construct the sender's email address using the configured SMTP settings.
         let from = format!("Sender <{}>", config.smtp_from.to_owned());
 
         Email {
-            user,
-            url,
             from,
-            config,
+            mailer,
+            templates,
+            queue: None,
         }
     }
 
-    /// Creates a new SMTP transport for sending emails based on the provided configuration.
-    ///
-    /// # Errors
+    /// Enqueues the emails into `queue` instead of sending them right away.
     ///
-    /// Returns an error if the SMTP transport configuration or setup fails.
+    /// The emails are then delivered by a [`Worker`](crate::queue::Worker) of the queue, which
+    /// retries them when delivery fails, so they are not lost.
     ///
     /// # Examples
     ///
-    /// ```rust
-    /// use your_project::{Email, Config};
-    ///
-    /// // Assume Config is properly instantiated in your code.
-    /// let config = Config::load();
-    ///
-    /// // Assume email_instance is an Email object with appropriate data.
-    /// let email_instance = Email::new(/* user, url, and config */);
-    ///
-    /// // Create a new SMTP transport for sending emails
-    /// let transport_result = email_instance.new_transport();
-    ///
-    /// // Handle the Result appropriately
-    /// match transport_result {
-    ///     Ok(transport) => {
-    ///         // SMTP transport successfully created
-    ///     }
-    ///     Err(err) => {
-    ///         // Handle the error
-    ///         eprintln!("Error creating SMTP transport: {}", err);
-    ///     }
-    /// }
+    /// ```rust,no_run
+    /// use send_emails_smtp::{config::Config, email::Email, mailer::MemoryMailer, queue::Queue};
+    /// use send_emails_smtp::{i18n::Translations, templates::TemplateRegistry};
+    /// use std::sync::Arc;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    /// # let config = Config::init();
+    /// # let mailer = Arc::new(MemoryMailer::default());
+    /// # let templates = Arc::new(TemplateRegistry::embedded(Arc::new(Translations::embedded("en")?))?);
+    /// let queue = Arc::new(Queue::open(&config.queue_dir)?);
+    /// let email = Email::new(&config, mailer, templates).with_queue(queue);
+    /// # Ok(())
+    /// # }
     /// ```
-    fn new_transport(
-        &self,
-    ) -> Result<AsyncSmtpTransport<Tokio1Executor>, lettre::transport::smtp::Error> {
-        // Create credentials for authentication on the SMTP server
-        let creds = Credentials::new(
-            self.config.smtp_user.to_owned(),
-            self.config.smtp_pass.to_owned(),
-        );
-
-        // Create an SMTP transport using TLS (starttls_relay) based on the configuration settings
-        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(
-            &self.config.smtp_host.to_owned(),
-        )?
-        // Set the SMTP server port
-        .port(self.config.smtp_port)
-        // Pass credentials for authentication
-        .credentials(creds)
-        // Finalize the transport setup
-        .build();
-
-        // Return the result: Ok(transport) or Err in case of an error
-        Ok(transport)
+    pub fn with_queue(mut self, queue: Arc<Queue>) -> Self {
+        self.queue = Some(queue);
+        self
     }
 
-    /// Renders an email template using Handlebars templates and user-specific data.
-    ///
-    /// # Arguments
-    ///
-    /// * `template_name` - The name of the template to render.
-    ///
-    /// # Returns
-    ///
-    /// A Result containing the rendered template string on success, or a RenderError on failure.
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use your_project::Email;
+    /// Renders the plain-text version of an email.
     ///
-    /// // Assume 'email_instance' is an Email object with proper data.
-    /// let email_instance = Email::new(/* user, url, and config */);
+    /// The `{template_name}.txt` template, from a `.txt.hbs` file, is used if there is one.
+    /// Otherwise the text is converted from the rendered HTML, keeping the targets of the links.
     ///
-    /// // Render a template named 'verification_code'
-    /// let rendered_template = email_instance.render_template("verification_code");
+    /// # Arguments
     ///
-    /// // Handle the Result appropriately
-    /// match rendered_template {
-    ///     Ok(template) => {
-    ///         // Use the rendered template
-    ///     }
-    ///     Err(err) => {
-    ///         // Handle the error
-    ///         eprintln!("Error rendering template: {}", err);
-    ///     }
-    /// }
-    /// ```
-    fn render_template(&self, template_name: &str) -> Result<String, handlebars::RenderError> {
-        // Create a new Handlebars instance
-        let mut handlebars = Handlebars::new();
-
-        // Register the main template and necessary partials
-        handlebars
-            .register_template_file(template_name, &format!("./templates/{}.hbs", template_name))?;
-        handlebars.register_template_file("styles", "./templates/partials/styles.hbs")?;
-        handlebars.register_template_file("base", "./templates/layouts/base.hbs")?;
-
-        // Prepare data to be passed to the template
-        let data = serde_json::json!({
-            "first_name": &self.user.name.split_whitespace().next().unwrap_or_default(),
-            "subject": &template_name,
-            "url": &self.url
-        });
-
-        // Render the template using the prepared data
-        let content_template = handlebars.render(template_name, &data)?;
+    /// * `template_name` - The name of the HTML template of the email.
+    /// * `data` - The data the HTML template was rendered with.
+    /// * `html` - The rendered HTML of the email.
+    fn render_text(
+        &self,
+        template_name: &str,
+        data: &Value,
+        html: &str,
+    ) -> Result<String, handlebars::RenderError> {
+        let text_template = format!("{}.txt", template_name);
+        if !self.templates.has_template(&text_template) {
+            return Ok(text::html_to_text(html));
+        }
 
-        // Return the rendered template
-        Ok(content_template)
+        // Templates escape values for HTML, which plain text does not need
+        let text = self.templates.render(&text_template, data)?;
+        Ok(text::unescape(&text))
     }
 
-    /// Asynchronously sends an email using rendered HTML content from a template.
+    /// Asynchronously renders an email from its template and sends it to a user, or enqueues
+    /// it if the instance has a queue.
+    ///
+    /// The template is rendered with the fields of `email`, its `subject` and the `locale` of
+    /// the user, falling back to the default locale for missing translations. The email is
+    /// sent as `multipart/alternative`, with a plain-text version of the HTML content for
+    /// clients that do not display HTML. The CSS of the HTML content is inlined into the
+    /// `style` attributes of its elements, since many clients strip `<style>` elements, and
+    /// the result is minified.
     ///
     /// # Arguments
     ///
-    /// * `template_name` - The name of the template used for rendering the email content.
-    /// * `subject` - The subject line of the email.
+    /// * `to` - The user the email is sent to.
+    /// * `email` - The email to render, carrying the data of its template.
     ///
     /// # Returns
     ///
-    /// A Result indicating success (()) on successful email transmission, or a Box<dyn Error>
+    /// A Result indicating success (()) on successful email transmission, or a `MailerError`
     /// containing the error information in case of failure.
     ///
     /// # Errors
     ///
-    /// Returns an error if rendering the template or sending the email fails.
+    /// Returns an error if the email cannot be serialized, a message is not translated in any
+    /// locale of the fallback chain, rendering the template fails, for example because it
+    /// references a field the email does not have, its CSS cannot be inlined, or sending or
+    /// enqueuing fails.
     ///
     /// # Examples
     ///
-    /// ```
-    /// use your_project::Email;
-    ///
-    /// // Assume 'email_instance' is an Email object with proper data.
-    /// let email_instance = Email::new(/* user, url, and config */);
+    /// ```rust,no_run
+    /// use send_emails_smtp::{email::Email, emails::VerificationCode, User};
     ///
-    /// // Send an email with a subject and content from the 'verification_code' template
-    /// let send_result = email_instance.send_email("verification_code", "Subject Line").await;
+    /// # async fn send(email_instance: Email, user: User) {
+    /// // Assume 'email_instance' is an Email object and 'user' a User.
+    /// let verification = VerificationCode::new(&user, "https://example.com/verify/code");
     ///
     /// // Handle the Result appropriately
-    /// match send_result {
-    ///     Ok(()) => {
-    ///         // Email sent successfully
-    ///     }
-    ///     Err(err) => {
-    ///         // Handle the error
-    ///         eprintln!("Error sending email: {}", err);
-    ///     }
+    /// if let Err(err) = email_instance.send(&user, &verification).await {
+    ///     eprintln!("Error sending verification code: {}", err);
     /// }
+    /// # }
     /// ```
-    async fn send_email(
-        &self,
-        template_name: &str,
-        subject: &str,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        // Render the HTML content for the email using the specified template
-        let html_template = self.render_template(template_name)?;
+    pub async fn send<T: EmailTemplate>(&self, to: &User, email: &T) -> Result<(), MailerError> {
+        // Write the email in the language of the user
+        let localizer = self.templates.translations().localizer(&to.locale);
+        let subject = email.subject(&localizer)?;
+
+        // Prepare the data of the template: the fields of the email, its subject and locale
+        let mut data = serde_json::to_value(email)?;
+        let Value::Object(fields) = &mut data else {
+            return Err(format!("{} does not serialize to a map", T::TEMPLATE_NAME).into());
+        };
+        fields.insert("subject".to_string(), Value::String(subject.clone()));
+        fields.insert("locale".to_string(), Value::String(localizer.locale()));
+
+        // Render the HTML content for the email using its template, and its text version
+        let html_template = self.templates.render(T::TEMPLATE_NAME, &data)?;
+        let text_template = self.render_text(T::TEMPLATE_NAME, &data, &html_template)?;
+
+        // Inline the styles for clients that strip the head, then minify the result
+        let html_template = inline::minify_html(&inline::inline_css(&html_template)?);
+
+        // Build the email message, checking the addresses before enqueuing it
+        let queued = QueuedEmail::new(
+            self.from.clone(),
+            format!("{} <{}>", to.name, to.email),
+            subject,
+            text_template,
+            html_template,
+        );
+        let message = queued.message()?;
 
-        // Build the email message
-        let email = Message::builder()
-            .to(
-                format!("{} <{}>", self.user.name.as_str(), self.user.email.as_str())
-                    .parse()
-                    .unwrap(),
-            )
-            .reply_to(self.from.as_str().parse().unwrap())
-            .from(self.from.as_str().parse().unwrap())
-            .subject(subject)
-            .header(ContentType::TEXT_HTML)
-            .body(html_template)?;
+        // Keep the email in the queue until a worker delivers it
+        if let Some(queue) = &self.queue {
+            queue.enqueue(&queued)?;
+            return Ok(());
+        }
 
-        // Create a new SMTP transport and send the email asynchronously
-        let transport = self.new_transport()?;
-        transport.send(email).await?;
+        // Send the email asynchronously through the configured mail transport
+        self.mailer.send(message).await?;
 
         // Return success if the email is sent successfully
         Ok(())
     }
-
-    /// Sends an account verification code to the user's email.
-    ///
-    /// # Returns
-    ///
-    /// A Result indicating success (()) on successful email transmission, or a Box<dyn Error>
-    /// containing the error information in case of failure.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if sending the verification code email fails.
-    ///
-    /// # Examples
-    ///
-    /// ```rust
-    /// use your_project::Email;
-    ///
-    /// // Assume 'email_instance' is an Email object with proper data.
-    /// let email_instance = Email::new(/* user, url, and config */);
-    ///
-    /// // Send a verification code email
-    /// let send_result = email_instance.send_verification_code().await;
-    ///
-    /// // Handle the Result appropriately
-    /// match send_result {
-    ///     Ok(()) => {
-    ///         // Verification code email sent successfully
-    ///     }
-    ///     Err(err) => {
-    ///         // Handle the error
-    ///         eprintln!("Error sending verification code: {}", err);
-    ///     }
-    /// }
-    /// ```
-    pub async fn send_verification_code(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // Send an email using the 'verification_code' template with the subject line
-        // 'Your account verification code'
-        self.send_email("verification_code", "Your account verification code")
-            .await
-    }
-
-    /// Sends a password reset token to the user's email.
-    ///
-    /// # Returns
-    ///
-    /// A Result indicating success (()) on successful email transmission, or a Box<dyn Error>
-    /// containing the error information in case of failure.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if sending the password reset token email fails.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use your_project::Email;
-    ///
-    /// // Assume 'email_instance' is an Email object with proper data.
-    /// let email_instance = Email::new(/* user, url, and config */);
-    ///
-    /// // Send a password reset token email
-    /// let send_result = email_instance.send_password_reset_token().await;
-    ///
-    /// // Handle the Result appropriately
-    /// match send_result {
-    ///     Ok(()) => {
-    ///         // Password reset token email sent successfully
-    ///     }
-    ///     Err(err) => {
-    ///         // Handle the error
-    ///         eprintln!("Error sending password reset token: {}", err);
-    ///     }
-    /// }
-    /// ```
-    pub async fn send_password_reset_token(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // Send an email using the 'reset_password' template with the subject line
-        // 'Your password reset token (valid for only 10 minutes)'
-        self.send_email(
-            "reset_password",
-            "Your password reset token (valid for only 10 minutes)",
-        )
-        .await
-    }
 }
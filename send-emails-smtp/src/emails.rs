@@ -0,0 +1,85 @@
+//! Emails sent by the application, one type per template.
+//!
+//! Each type carries the data of its template and implements [`EmailTemplate`], so it can be
+//! sent with [`Email::send`](crate::email::Email::send).
+
+use serde::Serialize;
+use serde_json::json;
+
+use crate::{
+    email::EmailTemplate,
+    i18n::{Localizer, TranslationError},
+    User,
+};
+
+/// Number of minutes a password reset token is valid for.
+pub const RESET_TOKEN_VALIDITY_MINUTES: u32 = 10;
+
+/// Returns the first name of a user, used to greet them.
+fn first_name(user: &User) -> String {
+    user.name
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Email asking a user to verify their account.
+#[derive(Debug, Serialize)]
+pub struct VerificationCode {
+    /// First name of the user.
+    pub first_name: String,
+    /// URL verifying the account.
+    pub url: String,
+}
+
+impl VerificationCode {
+    /// Creates the verification email of `user`, linking to `url`.
+    pub fn new(user: &User, url: impl Into<String>) -> Self {
+        VerificationCode {
+            first_name: first_name(user),
+            url: url.into(),
+        }
+    }
+}
+
+impl EmailTemplate for VerificationCode {
+    const TEMPLATE_NAME: &'static str = "verification_code";
+
+    fn subject(&self, localizer: &Localizer) -> Result<String, TranslationError> {
+        localizer.message("verification-code-subject")
+    }
+}
+
+/// Email sending a password reset token to a user.
+#[derive(Debug, Serialize)]
+pub struct PasswordReset {
+    /// First name of the user.
+    pub first_name: String,
+    /// URL resetting the password.
+    pub url: String,
+    /// Number of minutes the token of the URL is valid for.
+    pub valid_minutes: u32,
+}
+
+impl PasswordReset {
+    /// Creates the password reset email of `user`, linking to `url`.
+    pub fn new(user: &User, url: impl Into<String>) -> Self {
+        PasswordReset {
+            first_name: first_name(user),
+            url: url.into(),
+            valid_minutes: RESET_TOKEN_VALIDITY_MINUTES,
+        }
+    }
+}
+
+impl EmailTemplate for PasswordReset {
+    const TEMPLATE_NAME: &'static str = "reset_password";
+
+    fn subject(&self, localizer: &Localizer) -> Result<String, TranslationError> {
+        localizer.message_with(
+            "reset-password-subject",
+            &json!({"minutes": self.valid_minutes}),
+        )
+    }
+}
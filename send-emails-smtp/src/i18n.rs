@@ -0,0 +1,435 @@
+use std::{error::Error, fmt, sync::Arc};
+
+use fluent_bundle::{
+    concurrent::FluentBundle,
+    resolver::{errors::ReferenceKind, ResolverError},
+    FluentArgs, FluentError, FluentResource, FluentValue,
+};
+use handlebars::{
+    Context, Handlebars, Helper, HelperDef, RenderContext, RenderError, RenderErrorReason,
+    ScopedJson,
+};
+use serde_json::Value;
+use unic_langid::LanguageIdentifier;
+
+/// Message catalogs compiled into the binary, by locale.
+const EMBEDDED_CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("ru", include_str!("../locales/ru.ftl")),
+];
+
+/// Error raised while loading message catalogs or formatting a message.
+#[derive(Debug)]
+pub enum TranslationError {
+    /// A catalog is not valid Fluent, or defines a message twice.
+    Parse {
+        /// Locale of the catalog.
+        locale: String,
+        /// Line of the catalog the error is on, from 1.
+        line: usize,
+        /// Description of the problem.
+        message: String,
+    },
+    /// A locale is not a valid language identifier, e.g. `en-GB`.
+    InvalidLocale(String),
+    /// There is no catalog for the default locale.
+    UnknownLocale(String),
+    /// No catalog of the fallback chain of the locale has the message.
+    MissingMessage {
+        /// Requested locale.
+        locale: String,
+        /// Identifier of the message.
+        id: String,
+    },
+    /// A message references a variable that was not provided.
+    MissingVariable {
+        /// Identifier of the message.
+        id: String,
+        /// Name of the variable, without the `$`.
+        name: String,
+    },
+    /// Messages reference each other in a cycle.
+    Cycle(String),
+    /// A message cannot be formatted, e.g. it references an unknown term or function.
+    Format {
+        /// Identifier of the message.
+        id: String,
+        /// Description of the problem.
+        message: String,
+    },
+}
+
+impl fmt::Display for TranslationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslationError::Parse {
+                locale,
+                line,
+                message,
+            } => write!(
+                f,
+                "Invalid {} catalog at line {}: {}",
+                locale, line, message
+            ),
+            TranslationError::InvalidLocale(locale) => write!(f, "Invalid locale {}", locale),
+            TranslationError::UnknownLocale(locale) => {
+                write!(f, "No message catalog for the locale {}", locale)
+            }
+            TranslationError::MissingMessage { locale, id } => {
+                write!(f, "No translation of {} for the locale {}", id, locale)
+            }
+            TranslationError::MissingVariable { id, name } => {
+                write!(f, "Missing variable ${} of the message {}", name, id)
+            }
+            TranslationError::Cycle(id) => write!(f, "The message {} references itself", id),
+            TranslationError::Format { id, message } => {
+                write!(f, "Cannot format the message {}: {}", id, message)
+            }
+        }
+    }
+}
+
+impl Error for TranslationError {}
+
+/// Localised messages, loaded from [Fluent](https://projectfluent.org) catalogs.
+///
+/// Catalogs are `.ftl` files of the `locales` directory of the crate, one per locale, compiled
+/// into the binary. They are formatted by [`fluent_bundle`], so the whole Fluent syntax is
+/// supported, including selectors choosing the plural form of a number in the catalog's
+/// language (`{ $count -> [one] minute *[other] minutes }`).
+///
+/// Messages are looked up along a fallback chain: the requested locale, its language, then the
+/// default locale. A message missing from the `pt-BR` catalog is taken from the `pt` one, then
+/// from the default one. Terms and messages referenced by a message are looked up the same way.
+///
+/// # Examples
+///
+/// ```rust
+/// use send_emails_smtp::i18n::Translations;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let translations = Translations::new("en", &[("en", "greeting = Hi, { $name },")])?;
+///
+/// let greeting = translations
+///     .localizer("en-GB")
+///     .message_with("greeting", &serde_json::json!({"name": "John"}))?;
+///
+/// assert_eq!(greeting, "Hi, John,");
+/// # Ok(())
+/// # }
+/// ```
+pub struct Translations {
+    /// Locales having a catalog.
+    locales: Vec<LanguageIdentifier>,
+    /// Messages and terms of the catalog of each locale, merged with the ones of its fallback
+    /// chain it does not define.
+    bundles: Vec<FluentBundle<Arc<FluentResource>>>,
+    /// Locale used when a message is not translated in the requested one.
+    default_locale: LanguageIdentifier,
+}
+
+impl fmt::Debug for Translations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Translations")
+            .field("locales", &self.locales)
+            .field("default_locale", &self.default_locale)
+            .finish()
+    }
+}
+
+impl Translations {
+    /// Parses the catalogs of `catalogs`, given as pairs of locale and Fluent source.
+    ///
+    /// # Arguments
+    ///
+    /// * `default_locale` - The locale of the messages used when a translation is missing.
+    /// * `catalogs` - The locales and Fluent sources of the catalogs.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a locale or catalog is invalid, or there is no catalog for
+    /// `default_locale`.
+    pub fn new(default_locale: &str, catalogs: &[(&str, &str)]) -> Result<Self, TranslationError> {
+        let resources = catalogs
+            .iter()
+            .map(|(locale, source)| Ok((parse_locale(locale)?, parse(locale, source)?)))
+            .collect::<Result<Vec<_>, TranslationError>>()?;
+
+        let default_locale = parse_locale(default_locale)?;
+        if !resources
+            .iter()
+            .any(|(locale, _)| *locale == default_locale)
+        {
+            return Err(TranslationError::UnknownLocale(default_locale.to_string()));
+        }
+
+        let mut translations = Translations {
+            locales: resources.iter().map(|(locale, _)| locale.clone()).collect(),
+            bundles: Vec::new(),
+            default_locale,
+        };
+        for (locale, source) in translations.locales.iter().zip(catalogs) {
+            let chain = translations.chain(locale);
+            let mut bundle = FluentBundle::new_concurrent(
+                chain
+                    .iter()
+                    .map(|&index| resources[index].0.clone())
+                    .collect(),
+            );
+            bundle.set_use_isolating(false);
+            for (position, index) in chain.into_iter().enumerate() {
+                // Entries already defined by a more specific catalog are skipped on purpose
+                let added = bundle.add_resource(resources[index].1.clone());
+                if let (0, Err(errors)) = (position, added) {
+                    return Err(duplicate_error(locale, source.1, &errors));
+                }
+            }
+            translations.bundles.push(bundle);
+        }
+
+        Ok(translations)
+    }
+
+    /// Parses the catalogs embedded in the binary.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there is no catalog for `default_locale`.
+    pub fn embedded(default_locale: &str) -> Result<Self, TranslationError> {
+        Self::new(default_locale, &EMBEDDED_CATALOGS)
+    }
+
+    /// Returns the locales messages in `locale` are looked up in, most specific first.
+    ///
+    /// Only locales with a catalog are returned, so the chain always ends with the default
+    /// locale. Locales are in their canonical form, e.g. `pt-BR` for `pt_br`.
+    pub fn fallback_chain(&self, locale: &str) -> Vec<String> {
+        self.chain(&self.parse_requested(locale))
+            .into_iter()
+            .map(|index| self.locales[index].to_string())
+            .collect()
+    }
+
+    /// Returns a localizer formatting the messages in `locale`.
+    ///
+    /// Invalid locales are formatted in the default locale.
+    pub fn localizer(&self, locale: &str) -> Localizer<'_> {
+        // The catalog of the most specific locale is merged with the rest of the chain
+        let index = self.chain(&self.parse_requested(locale))[0];
+        Localizer {
+            locale: &self.locales[index],
+            bundle: &self.bundles[index],
+        }
+    }
+
+    /// Parses a requested locale, replacing an invalid one with the default locale.
+    fn parse_requested(&self, locale: &str) -> LanguageIdentifier {
+        parse_locale(locale).unwrap_or_else(|_| self.default_locale.clone())
+    }
+
+    /// Returns the indices of the locales of the fallback chain of `locale`.
+    fn chain(&self, locale: &LanguageIdentifier) -> Vec<usize> {
+        let mut chain = Vec::new();
+        for locale in [locale, &self.default_locale] {
+            // Try the full locale, then its language alone
+            let language = LanguageIdentifier::from_parts(locale.language, None, None, &[]);
+            for candidate in [locale, &language] {
+                let found = self.locales.iter().position(|other| other == candidate);
+                if let Some(index) = found.filter(|index| !chain.contains(index)) {
+                    chain.push(index);
+                }
+            }
+        }
+
+        chain
+    }
+}
+
+/// Formats messages in one locale, following its fallback chain.
+pub struct Localizer<'a> {
+    /// Most specific locale of the fallback chain having a catalog.
+    locale: &'a LanguageIdentifier,
+    /// Messages of the catalog of the locale, merged with its fallback chain.
+    bundle: &'a FluentBundle<Arc<FluentResource>>,
+}
+
+impl Localizer<'_> {
+    /// Returns the most specific locale of the fallback chain having a catalog.
+    pub fn locale(&self) -> String {
+        self.locale.to_string()
+    }
+
+    /// Formats the message `id`, which must not use variables.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no catalog of the fallback chain has the message, or it uses a
+    /// variable.
+    pub fn message(&self, id: &str) -> Result<String, TranslationError> {
+        self.format(id, None)
+    }
+
+    /// Formats the message `id` with the variables of `args`, a JSON object.
+    ///
+    /// Numbers select the plural form of the message in the locale, strings are inserted as
+    /// they are.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no catalog of the fallback chain has the message, or it uses a
+    /// variable missing from `args`.
+    pub fn message_with(&self, id: &str, args: &Value) -> Result<String, TranslationError> {
+        let mut fluent_args = FluentArgs::new();
+        if let Value::Object(args) = args {
+            for (name, value) in args {
+                if let Some(value) = fluent_value(value) {
+                    fluent_args.set(name.as_str(), value);
+                }
+            }
+        }
+
+        self.format(id, Some(&fluent_args))
+    }
+
+    /// Formats the value of the message `id` with `args`.
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> Result<String, TranslationError> {
+        let bundle = self.bundle;
+        let Some(pattern) = bundle.get_message(id).and_then(|message| message.value()) else {
+            return Err(TranslationError::MissingMessage {
+                locale: self.locale(),
+                id: id.to_string(),
+            });
+        };
+
+        let mut errors = Vec::new();
+        let message = bundle.format_pattern(pattern, args, &mut errors);
+        match errors.into_iter().next() {
+            None => Ok(message.into_owned()),
+            Some(FluentError::ResolverError(ResolverError::Reference(
+                ReferenceKind::Variable { id: name },
+            ))) => Err(TranslationError::MissingVariable {
+                id: id.to_string(),
+                name,
+            }),
+            Some(FluentError::ResolverError(ResolverError::Cyclic)) => {
+                Err(TranslationError::Cycle(id.to_string()))
+            }
+            Some(err) => Err(TranslationError::Format {
+                id: id.to_string(),
+                message: err.to_string(),
+            }),
+        }
+    }
+}
+
+/// Handlebars helper translating a message into the locale of the rendered email.
+///
+/// Used as `{{t "greeting" name=first_name}}`: the first parameter is the identifier of the
+/// message, and the hash parameters are its variables. The locale is the `locale` value of the
+/// template data, or the default locale if there is none. The result is escaped like any other
+/// value.
+pub(crate) struct TranslateHelper(pub(crate) Arc<Translations>);
+
+impl HelperDef for TranslateHelper {
+    fn call_inner<'reg: 'rc, 'rc>(
+        &self,
+        h: &Helper<'rc>,
+        _: &'reg Handlebars<'reg>,
+        ctx: &'rc Context,
+        _: &mut RenderContext<'reg, 'rc>,
+    ) -> Result<ScopedJson<'rc>, RenderError> {
+        let id = h
+            .param(0)
+            .and_then(|id| id.value().as_str())
+            .ok_or(RenderErrorReason::ParamNotFoundForIndex("t", 0))?;
+        let locale = ctx.data().get("locale").and_then(Value::as_str);
+        let localizer = match locale {
+            Some(locale) => self.0.localizer(locale),
+            None => self.0.localizer(&self.0.default_locale.to_string()),
+        };
+
+        let mut args = FluentArgs::new();
+        for (name, value) in h.hash() {
+            if let Some(value) = fluent_value(value.value()) {
+                args.set(*name, value);
+            }
+        }
+        let message = localizer
+            .format(id, Some(&args))
+            .map_err(|err| RenderErrorReason::Other(err.to_string()))?;
+
+        Ok(ScopedJson::Derived(Value::String(message)))
+    }
+}
+
+/// Parses a locale, accepting `_` as a separator, e.g. `pt_BR`.
+fn parse_locale(locale: &str) -> Result<LanguageIdentifier, TranslationError> {
+    locale
+        .trim()
+        .parse()
+        .map_err(|_| TranslationError::InvalidLocale(locale.to_string()))
+}
+
+/// Parses the Fluent source of the catalog of `locale`.
+fn parse(locale: &str, source: &str) -> Result<Arc<FluentResource>, TranslationError> {
+    match FluentResource::try_new(source.to_string()) {
+        Ok(resource) => Ok(Arc::new(resource)),
+        Err((_, errors)) => {
+            let err = &errors[0];
+            Err(TranslationError::Parse {
+                locale: locale.to_string(),
+                line: line_of(source, err.pos.start),
+                message: err.to_string(),
+            })
+        }
+    }
+}
+
+/// Returns the error of an entry defined twice in the catalog of `locale`, pointing to its
+/// second definition.
+fn duplicate_error(
+    locale: &LanguageIdentifier,
+    source: &str,
+    errors: &[FluentError],
+) -> TranslationError {
+    let id = match &errors[0] {
+        FluentError::Overriding { id, .. } => id.as_str(),
+        _ => "",
+    };
+    let line = source
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let rest = line.strip_prefix('-').unwrap_or(line);
+            rest.strip_prefix(id)
+                .is_some_and(|rest| rest.trim_start().starts_with('='))
+        })
+        .map(|(index, _)| index + 1)
+        .nth(1)
+        .unwrap_or_default();
+
+    TranslationError::Parse {
+        locale: locale.to_string(),
+        line,
+        message: format!("{} is defined twice", id),
+    }
+}
+
+/// Returns the line of `source` the byte at `offset` is on, from 1.
+fn line_of(source: &str, offset: usize) -> usize {
+    source[..offset.min(source.len())].matches('\n').count() + 1
+}
+
+/// Converts a JSON value into a Fluent variable, or `None` for `null`.
+fn fluent_value(value: &Value) -> Option<FluentValue<'_>> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(FluentValue::from(value.as_str())),
+        // Numbers stay numbers, so they select plural forms
+        Value::Number(number) => Some(match number.as_i64() {
+            Some(number) => FluentValue::from(number),
+            None => FluentValue::from(number.as_f64().unwrap_or_default()),
+        }),
+        value => Some(FluentValue::from(value.to_string())),
+    }
+}
@@ -0,0 +1,183 @@
+use css_inline::{CSSInliner, InlineError};
+
+/// Elements whose surrounding whitespace is rendered, and kept by the minifier.
+const INLINE_ELEMENTS: [&str; 9] = [
+    "a", "b", "em", "font", "i", "img", "small", "span", "strong",
+];
+
+/// Elements whose content is not HTML, and is kept as is by the minifier, except for the
+/// whitespace of stylesheets.
+const RAW_ELEMENTS: [&str; 4] = ["pre", "script", "style", "textarea"];
+
+/// Moves the rules of the `<style>` elements of `html` into the `style` attributes of the
+/// elements they match, since many email clients, such as Gmail, strip `<style>` elements.
+///
+/// Inlining is done by [`css_inline`]. Rules that cannot be inlined, such as `@media` queries
+/// and selectors with pseudo-classes, stay in the `<style>` elements of the head. The existing
+/// `style` attribute of an element wins over the inlined rules. The result is a whole
+/// document, with `<html>`, `<head>` and `<body>` elements added if `html` has none.
+///
+/// # Errors
+///
+/// Returns an error if a stylesheet cannot be parsed.
+///
+/// # Examples
+///
+/// ```rust
+/// use send_emails_smtp::inline::inline_css;
+///
+/// # fn main() -> Result<(), css_inline::InlineError> {
+/// let html = inline_css("<style>p { color: red; }</style><p>Hi</p>")?;
+///
+/// assert!(html.contains(r#"<p style="color: red;">Hi</p>"#));
+/// # Ok(())
+/// # }
+/// ```
+pub fn inline_css(html: &str) -> Result<String, InlineError> {
+    CSSInliner::options()
+        .keep_style_tags(true)
+        .remove_inlined_selectors(true)
+        .build()
+        .inline(html)
+}
+
+/// Minifies `html` by removing comments and collapsing the whitespace of its text.
+///
+/// Whitespace between two tags is removed unless both are inline elements, where it separates
+/// words. Tags, and so their attribute values, are kept as they are, as are conditional
+/// comments for Outlook (`<!--[if mso]>`) and the content of `<pre>`, `<script>` and
+/// `<textarea>` elements. The whitespace of `<style>` elements is collapsed outside of strings,
+/// and empty ones are removed.
+///
+/// # Examples
+///
+/// ```rust
+/// use send_emails_smtp::inline::minify_html;
+///
+/// let html = minify_html("<table>\n  <tr>\n    <td>Hi,   <b>John</b> <i>Doe</i></td>\n  </tr>\n</table>");
+///
+/// assert_eq!(html, "<table><tr><td>Hi, <b>John</b> <i>Doe</i></td></tr></table>");
+/// ```
+pub fn minify_html(html: &str) -> String {
+    let mut minified = String::with_capacity(html.len());
+    let mut previous_tag = String::new();
+    let mut rest = html;
+
+    loop {
+        let start = rest.find('<').unwrap_or(rest.len());
+        let text = &rest[..start];
+        rest = &rest[start..];
+
+        // Whitespace between two tags only matters between inline elements
+        if text.trim().is_empty() {
+            let inline = |name: &str| INLINE_ELEMENTS.contains(&name);
+            if !text.is_empty() && inline(&previous_tag) && inline(&tag_name(rest)) {
+                minified.push(' ');
+            }
+        } else {
+            push_collapsed(&mut minified, text);
+        }
+        if rest.is_empty() {
+            break;
+        }
+
+        // Drop comments, except conditional comments
+        if rest.starts_with("<!--") {
+            let end = rest.find("-->").map_or(rest.len(), |end| end + 3);
+            if rest.starts_with("<!--[if") {
+                minified.push_str(&rest[..end]);
+            }
+            rest = &rest[end..];
+            continue;
+        }
+
+        let name = tag_name(rest);
+        let tag_start = minified.len();
+        let end = tag_end(rest).unwrap_or(rest.len());
+        minified.push_str(&rest[..end]);
+        rest = &rest[end..];
+
+        // The content of raw elements runs until their end tag
+        if RAW_ELEMENTS.contains(&name.as_str()) {
+            let end_tag = format!("</{}", name);
+            let content_end = rest
+                .to_ascii_lowercase()
+                .find(&end_tag)
+                .unwrap_or(rest.len());
+            let content = match name.as_str() {
+                "style" => minify_css(&rest[..content_end]),
+                _ => rest[..content_end].to_string(),
+            };
+            rest = &rest[content_end..];
+
+            // Drop the stylesheets left empty by the inlining
+            if name == "style" && content.is_empty() {
+                minified.truncate(tag_start);
+                rest = &rest[tag_end(rest).unwrap_or(rest.len())..];
+                continue;
+            }
+            minified.push_str(&content);
+        }
+        previous_tag = name.trim_start_matches('/').to_string();
+    }
+
+    minified
+}
+
+/// Collapses the runs of whitespace of a stylesheet into single spaces, except in strings.
+fn minify_css(css: &str) -> String {
+    let mut minified = String::with_capacity(css.len());
+    let mut quote = None;
+    for c in css.trim().chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => {
+                if !minified.ends_with(' ') {
+                    minified.push(' ');
+                }
+                continue;
+            }
+            _ => {}
+        }
+        minified.push(c);
+    }
+
+    minified
+}
+
+/// Returns the length of the tag `html` starts with, quoted `>` included.
+fn tag_end(html: &str) -> Option<usize> {
+    let mut quote = None;
+    for (i, c) in html.char_indices().skip(1) {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(q), c) if c == q => quote = None,
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+    }
+
+    None
+}
+
+/// Returns the lowercase name of the tag `html` starts with, prefixed with `/` for end tags.
+fn tag_name(html: &str) -> String {
+    html.strip_prefix('<')
+        .unwrap_or_default()
+        .split(|c: char| c.is_whitespace() || c == '>' || (c == '/' && !html.starts_with("</")))
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase()
+}
+
+/// Appends `text` with its runs of whitespace collapsed into single spaces.
+fn push_collapsed(html: &mut String, text: &str) {
+    for c in text.chars() {
+        if !c.is_whitespace() {
+            html.push(c);
+        } else if !html.ends_with(' ') {
+            html.push(' ');
+        }
+    }
+}
@@ -0,0 +1,28 @@
+//! Sends verification codes and password reset tokens to users by email.
+//!
+//! The emails of the [`emails`] module are rendered by [`email::Email`] from the Handlebars
+//! templates of a [`templates::TemplateRegistry`] and delivered through one of the transports of
+//! the [`mailer`] module.
+
+pub mod config;
+pub mod email;
+pub mod emails;
+pub mod i18n;
+pub mod inline;
+pub mod mailer;
+pub mod queue;
+pub mod templates;
+pub mod text;
+
+use serde::Serialize;
+
+/// Represents user information including name, email and locale.
+#[derive(Debug, Serialize)]
+pub struct User {
+    /// User's name.
+    pub name: String,
+    /// User's email address.
+    pub email: String,
+    /// Locale the user's emails are written in, e.g. `en` or `pt-BR`.
+    pub locale: String,
+}
@@ -0,0 +1,185 @@
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use lettre::{
+    transport::smtp::{authentication::Credentials, PoolConfig},
+    AsyncFileTransport, AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+};
+use tokio::io::AsyncWriteExt;
+
+use crate::config::Config;
+
+/// Error returned by a mail transport.
+pub type MailerError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Delivers built email messages.
+///
+/// Implemented by every mail transport, so emails can be sent through SMTP in production and
+/// written to files, printed or kept in memory during development and tests.
+///
+/// # Examples
+///
+/// ```rust
+/// use send_emails_smtp::mailer::{Mailer, MemoryMailer};
+///
+/// # #[tokio::main]
+/// # async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// # let message = lettre::Message::builder()
+/// #     .from("sender@example.com".parse()?)
+/// #     .to("john@example.com".parse()?)
+/// #     .body(String::from("Hi"))?;
+/// // Assume 'message' is a lettre Message built elsewhere.
+/// let mailer = MemoryMailer::default();
+/// mailer.send(message).await?;
+///
+/// assert_eq!(mailer.messages().len(), 1);
+/// # Ok(())
+/// # }
+/// ```
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    /// Sends `message` to the recipients in its envelope.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the message cannot be delivered.
+    async fn send(&self, message: Message) -> Result<(), MailerError>;
+}
+
+/// Sends emails through an SMTP server.
+///
+/// Connections are taken from a pool and reused for later emails, so a mailer should be created
+/// once and shared, e.g. behind the `Arc` returned by [`from_config`]. Clones share the pool.
+#[derive(Clone)]
+pub struct SmtpMailer {
+    /// Transport holding the pool of connections to the SMTP server.
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+}
+
+impl SmtpMailer {
+    /// Creates an SMTP mailer using the server, credentials and pool settings of the
+    /// configuration.
+    ///
+    /// No connection is opened until the first email is sent.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the SMTP transport configuration or setup fails.
+    pub fn new(config: &Config) -> Result<Self, lettre::transport::smtp::Error> {
+        // Create credentials for authentication on the SMTP server
+        let creds = Credentials::new(config.smtp_user.to_owned(), config.smtp_pass.to_owned());
+
+        // Keep up to `smtp_pool_size` connections open between sends
+        let pool = PoolConfig::new()
+            .max_size(config.smtp_pool_size)
+            .idle_timeout(Duration::from_secs(config.smtp_pool_idle_secs));
+
+        // Create an SMTP transport using TLS (starttls_relay) based on the configuration settings
+        let transport = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&config.smtp_host)?
+            // Set the SMTP server port
+            .port(config.smtp_port)
+            // Pass credentials for authentication
+            .credentials(creds)
+            // Reuse authenticated connections instead of opening one per email
+            .pool_config(pool)
+            // Finalize the transport setup
+            .build();
+
+        Ok(SmtpMailer { transport })
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, message: Message) -> Result<(), MailerError> {
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Writes emails as `.eml` files to a directory instead of sending them.
+pub struct FileMailer {
+    /// Transport writing one file per message, named after a random UUID.
+    transport: AsyncFileTransport<Tokio1Executor>,
+}
+
+impl FileMailer {
+    /// Creates a mailer writing to `dir`, which must exist.
+    pub fn new(dir: impl AsRef<Path>) -> Self {
+        FileMailer {
+            transport: AsyncFileTransport::new(dir),
+        }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, message: Message) -> Result<(), MailerError> {
+        self.transport.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Prints emails, headers included, to the standard output instead of sending them.
+#[derive(Default)]
+pub struct StdoutMailer;
+
+#[async_trait]
+impl Mailer for StdoutMailer {
+    async fn send(&self, message: Message) -> Result<(), MailerError> {
+        let mut stdout = tokio::io::stdout();
+        stdout.write_all(&message.formatted()).await?;
+        stdout.write_all(b"\n").await?;
+        stdout.flush().await?;
+        Ok(())
+    }
+}
+
+/// Keeps emails in memory instead of sending them, so tests can inspect what was sent.
+///
+/// Clones share the same list of messages.
+#[derive(Clone, Default)]
+pub struct MemoryMailer {
+    /// Messages sent so far, oldest first.
+    messages: Arc<Mutex<Vec<Message>>>,
+}
+
+impl MemoryMailer {
+    /// Returns the messages sent so far, oldest first.
+    pub fn messages(&self) -> Vec<Message> {
+        self.messages.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    async fn send(&self, message: Message) -> Result<(), MailerError> {
+        self.messages.lock().unwrap().push(message);
+        Ok(())
+    }
+}
+
+/// Creates the mailer selected by the `mail_transport` setting of the configuration.
+///
+/// # Errors
+///
+/// Returns an error if the transport is unknown, the SMTP transport cannot be set up or the
+/// directory of the file transport cannot be created.
+pub fn from_config(config: &Config) -> Result<Arc<dyn Mailer>, MailerError> {
+    let mailer: Arc<dyn Mailer> = match config.mail_transport.as_str() {
+        "smtp" => Arc::new(SmtpMailer::new(config)?),
+        "file" => {
+            std::fs::create_dir_all(&config.mail_dir)?;
+            Arc::new(FileMailer::new(&config.mail_dir))
+        }
+        "stdout" => Arc::new(StdoutMailer),
+        "memory" => Arc::new(MemoryMailer::default()),
+        other => return Err(format!("Unknown MAIL_TRANSPORT: {}", other).into()),
+    };
+
+    Ok(mailer)
+}
@@ -1,18 +1,20 @@
-mod config;
-mod email;
+use std::{env, sync::Arc, time::Duration};
 
 use dotenv::dotenv;
-use email::Email;
-use serde::Serialize;
-
-/// Represents user information including name and email.
-#[derive(Debug, Serialize)]
-pub struct User {
-    /// User's name.
-    pub name: String,
-    /// User's email address.
-    pub email: String,
-}
+use send_emails_smtp::{
+    config,
+    email::Email,
+    emails::{PasswordReset, VerificationCode},
+    i18n::Translations,
+    mailer,
+    queue::{Queue, Report, RetryPolicy, Worker},
+    templates::TemplateRegistry,
+    User,
+};
+use tokio::{
+    io::{self, AsyncBufReadExt, BufReader},
+    signal,
+};
 
 #[tokio::main]
 async fn main() {
@@ -21,29 +23,183 @@ async fn main() {
 
     let config = config::Config::init();
 
+    // Open the outbound queue, keeping the emails until they are delivered
+    let queue = match Queue::open(&config.queue_dir) {
+        Ok(queue) => Arc::new(queue),
+        Err(err) => {
+            eprintln!("Failed to open the email queue: {}", err);
+            return;
+        }
+    };
+
+    // Inspect or replay the dead-letter store instead of sending new emails
+    let command = env::args().nth(1);
+    match command.as_deref() {
+        Some("dead-letters") => {
+            match queue.dead_letters() {
+                Ok(emails) => {
+                    for email in emails {
+                        println!(
+                            "{} to {} ({} attempts): {}",
+                            email.id,
+                            email.to,
+                            email.attempts,
+                            email.last_error.unwrap_or_default()
+                        );
+                    }
+                }
+                Err(err) => eprintln!("Failed to read the dead-letter store: {}", err),
+            }
+            return;
+        }
+        Some("replay") => {
+            // Replay the given email, or all of them
+            let ids = match env::args().nth(2) {
+                Some(id) => vec![id],
+                None => match queue.dead_letters() {
+                    Ok(emails) => emails.into_iter().map(|email| email.id).collect(),
+                    Err(err) => {
+                        eprintln!("Failed to read the dead-letter store: {}", err);
+                        return;
+                    }
+                },
+            };
+            for id in ids {
+                match queue.replay(&id) {
+                    Ok(true) => println!("Replayed email {}", id),
+                    Ok(false) => eprintln!("No email {} in the dead-letter store", id),
+                    Err(err) => eprintln!("Failed to replay email {}: {}", id, err),
+                }
+            }
+        }
+        Some(command) => {
+            eprintln!(
+                "Unknown command {}, expected dead-letters or replay [ID]",
+                command
+            );
+            return;
+        }
+        None => {}
+    }
+
     // Create a User instance
     let user = User {
         name: String::from("Recipient"),
         email: config.smtp_to.to_owned(),
+        locale: config.default_locale.to_owned(),
     };
 
     let verification_code = "my_ultra_secure_verification_code";
     let verification_url = format!("http://localhost:3000/verifyemail/{}", verification_code);
 
-    //  Create an Email instance
-    let email = Email::new(user, verification_url, config);
+    // Create the mail transport selected by the configuration
+    let mailer = match mailer::from_config(&config) {
+        Ok(mailer) => mailer,
+        Err(err) => {
+            eprintln!("Failed to create the mail transport: {:?}", err);
+            return;
+        }
+    };
+
+    // Load the message catalogs the templates are translated with
+    let translations = match Translations::embedded(&config.default_locale) {
+        Ok(translations) => Arc::new(translations),
+        Err(err) => {
+            eprintln!("Failed to load the translations: {}", err);
+            return;
+        }
+    };
+
+    // Compile the templates once, refusing to start if one of them is invalid
+    let templates = match TemplateRegistry::load(config.templates_dir.as_ref(), translations) {
+        Ok(templates) => Arc::new(templates),
+        Err(err) => {
+            eprintln!("Failed to load the email templates: {}", err);
+            return;
+        }
+    };
+
+    // Recompile the templates as they are edited while developing
+    if config.dev_mode {
+        templates.clone().watch(Duration::from_millis(500));
+    }
+
+    //  Create an Email instance enqueuing the emails
+    let email = Email::new(&config, mailer.clone(), templates).with_queue(queue.clone());
+
+    // Enqueue the example emails, unless replaying the dead-letter store or resuming the
+    // delivery of the emails of an interrupted run
+    let pending = queue.pending().map_or(0, |emails| emails.len());
+    if command.is_none() && pending == 0 {
+        send_examples(&email, &user, &verification_url).await;
+    } else if pending > 0 {
+        println!("Resuming the delivery of {} queued emails", pending);
+    }
+
+    let worker = Worker::new(queue.clone(), mailer, RetryPolicy::from_config(&config));
+
+    // Keep running while developing, so edited templates can be tried without restarting
+    if config.dev_mode {
+        println!("Watching the templates, press Enter to send the emails again or Ctrl+C to quit");
+        worker.spawn(Duration::from_secs(1));
+
+        let mut lines = BufReader::new(io::stdin()).lines();
+        loop {
+            tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(_)) => send_examples(&email, &user, &verification_url).await,
+                    // Without a terminal, run until interrupted
+                    Ok(None) | Err(_) => {
+                        signal::ctrl_c().await.ok();
+                        break;
+                    }
+                },
+                _ = signal::ctrl_c() => break,
+            }
+        }
+        return;
+    }
+
+    // Deliver the queued emails, retrying failures at their scheduled times, until every
+    // email is delivered or dead-lettered
+    loop {
+        match worker.process_due().await {
+            Ok(report) if report != Report::default() => println!(
+                "Delivered {} emails, {} to retry, {} moved to the dead-letter store",
+                report.delivered, report.retried, report.dead
+            ),
+            Ok(_) => {}
+            Err(err) => eprintln!("Failed to process the email queue: {}", err),
+        }
+        if queue.pending().is_ok_and(|emails| emails.is_empty()) {
+            break;
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(Duration::from_secs(1)) => {}
+            _ = signal::ctrl_c() => {
+                println!("Stopped, the queued emails are delivered on the next run");
+                break;
+            }
+        }
+    }
+}
 
-    // Send a verification code email
-    if let Err(err) = email.send_verification_code().await {
-        eprintln!("Failed to send verification code email: {:?}", err);
+/// Enqueues the example verification code and password reset emails to `user`.
+async fn send_examples(email: &Email, user: &User, url: &str) {
+    // Enqueue a verification code email
+    let verification = VerificationCode::new(user, url);
+    if let Err(err) = email.send(user, &verification).await {
+        eprintln!("Failed to enqueue verification code email: {:?}", err);
     } else {
-        println!("Email verification code sent successfully!");
+        println!("Email verification code enqueued successfully!");
     }
 
-    // Send a password reset token email
-    if let Err(err) = email.send_password_reset_token().await {
-        eprintln!("Failed to send password reset token email: {:?}", err);
+    // Enqueue a password reset token email
+    let password_reset = PasswordReset::new(user, url);
+    if let Err(err) = email.send(user, &password_reset).await {
+        eprintln!("Failed to enqueue password reset token email: {:?}", err);
     } else {
-        println!("Password reset token email sent successfully!");
+        println!("Password reset token email enqueued successfully!");
     }
 }
@@ -0,0 +1,420 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use lettre::{address::AddressError, message::MultiPart, transport::smtp, Message};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+
+use crate::{
+    config::Config,
+    mailer::{Mailer, MailerError},
+};
+
+/// Subdirectory of the queue holding the emails waiting to be delivered.
+const PENDING_DIR: &str = "pending";
+
+/// Subdirectory of the queue holding the emails that could not be delivered.
+const DEAD_DIR: &str = "dead";
+
+/// Subdirectory of the queue holding the files that are not valid emails, e.g. truncated ones.
+const CORRUPT_DIR: &str = "corrupt";
+
+/// Extension of the files of the queued emails.
+const EMAIL_EXTENSION: &str = "json";
+
+/// Number of emails queued by this process, making their identifiers unique.
+static QUEUED: AtomicU64 = AtomicU64::new(0);
+
+/// Rendered email waiting in the queue, with its delivery attempts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedEmail {
+    /// Identifier of the email, ordering the queue by enqueue time: the enqueue time, the ID of
+    /// the process and a counter of the process, e.g. `00000001700000000000-0000004242-000000`.
+    pub id: String,
+    /// Sender's mailbox, e.g. `Sender <sender@test.com>`.
+    pub from: String,
+    /// Recipient's mailbox.
+    pub to: String,
+    /// Subject line.
+    pub subject: String,
+    /// Plain-text part.
+    pub text: String,
+    /// HTML part.
+    pub html: String,
+    /// Number of failed delivery attempts.
+    pub attempts: u32,
+    /// Time of the next delivery attempt, in milliseconds since the Unix epoch.
+    pub next_attempt_at: u64,
+    /// Error of the last failed delivery attempt, if any.
+    pub last_error: Option<String>,
+}
+
+impl QueuedEmail {
+    /// Creates an email ready to be delivered, with a new identifier.
+    ///
+    /// The identifier is unique across the processes sharing the queue, as it includes the ID
+    /// of the process.
+    pub fn new(from: String, to: String, subject: String, text: String, html: String) -> Self {
+        let id = format!(
+            "{:020}-{:010}-{:06}",
+            now(),
+            std::process::id(),
+            QUEUED.fetch_add(1, Ordering::Relaxed)
+        );
+
+        QueuedEmail {
+            id,
+            from,
+            to,
+            subject,
+            text,
+            html,
+            attempts: 0,
+            next_attempt_at: 0,
+            last_error: None,
+        }
+    }
+
+    /// Builds the `multipart/alternative` message of the email.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the sender or the recipient is not a valid mailbox.
+    pub fn message(&self) -> Result<Message, MailerError> {
+        Ok(Message::builder()
+            .to(self.to.parse()?)
+            .reply_to(self.from.parse()?)
+            .from(self.from.parse()?)
+            .subject(self.subject.as_str())
+            .multipart(MultiPart::alternative_plain_html(
+                self.text.clone(),
+                self.html.clone(),
+            ))?)
+    }
+}
+
+/// Outbound queue persisting emails in a directory until they are delivered.
+///
+/// Every email is a JSON file of the `pending` subdirectory, written atomically, so emails
+/// survive restarts and crashes. Emails that cannot be delivered are moved to the `dead`
+/// subdirectory, the dead-letter store, where they can be inspected and replayed. Files that
+/// cannot be read as emails are moved to the `corrupt` subdirectory, so they do not stop the
+/// delivery of the others.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use send_emails_smtp::queue::Queue;
+///
+/// # fn main() -> std::io::Result<()> {
+/// let queue = Queue::open("./queue")?;
+///
+/// // Retry every email that could not be delivered
+/// for email in queue.dead_letters()? {
+///     println!("{} to {}: {:?}", email.id, email.to, email.last_error);
+///     queue.replay(&email.id)?;
+/// }
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Queue {
+    /// Directory of the queue.
+    dir: PathBuf,
+}
+
+impl Queue {
+    /// Opens the queue stored in `dir`, creating the directory if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directories of the queue cannot be created.
+    pub fn open(dir: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(dir.join(PENDING_DIR))?;
+        fs::create_dir_all(dir.join(DEAD_DIR))?;
+        fs::create_dir_all(dir.join(CORRUPT_DIR))?;
+
+        Ok(Queue { dir })
+    }
+
+    /// Adds an email to the queue.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the email cannot be written.
+    pub fn enqueue(&self, email: &QueuedEmail) -> io::Result<()> {
+        self.save(PENDING_DIR, email)
+    }
+
+    /// Returns the emails waiting to be delivered, oldest first.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the queue cannot be read.
+    pub fn pending(&self) -> io::Result<Vec<QueuedEmail>> {
+        self.list(PENDING_DIR)
+    }
+
+    /// Returns the emails that could not be delivered, oldest first, with the error of their
+    /// last attempt.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the dead-letter store cannot be read.
+    pub fn dead_letters(&self) -> io::Result<Vec<QueuedEmail>> {
+        self.list(DEAD_DIR)
+    }
+
+    /// Moves the email `id` from the dead-letter store back to the queue, to be delivered
+    /// again with a fresh set of attempts.
+    ///
+    /// Returns whether there was such an email in the dead-letter store.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `id` is not the identifier of an email, so it cannot name a file
+    /// outside the dead-letter store, or if the email cannot be read or moved.
+    pub fn replay(&self, id: &str) -> io::Result<bool> {
+        // Identifiers are made of numbers separated by dashes
+        let valid = !id.is_empty()
+            && id
+                .split('-')
+                .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+        if !valid {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "not an email identifier",
+            ));
+        }
+
+        let path = self.path(DEAD_DIR, id);
+        if !path.exists() {
+            return Ok(false);
+        }
+
+        let mut email = read(&path)?;
+        email.attempts = 0;
+        email.next_attempt_at = 0;
+        self.save(PENDING_DIR, &email)?;
+        fs::remove_file(path)?;
+
+        Ok(true)
+    }
+
+    /// Removes a delivered email from the queue.
+    fn remove(&self, email: &QueuedEmail) -> io::Result<()> {
+        fs::remove_file(self.path(PENDING_DIR, &email.id))
+    }
+
+    /// Moves an email that cannot be delivered to the dead-letter store.
+    fn bury(&self, email: &QueuedEmail) -> io::Result<()> {
+        self.save(DEAD_DIR, email)?;
+        self.remove(email)
+    }
+
+    /// Returns the path of the email `id` in the subdirectory `state`.
+    fn path(&self, state: &str, id: &str) -> PathBuf {
+        self.dir
+            .join(state)
+            .join(id)
+            .with_extension(EMAIL_EXTENSION)
+    }
+
+    /// Writes an email to the subdirectory `state`, replacing its previous version.
+    fn save(&self, state: &str, email: &QueuedEmail) -> io::Result<()> {
+        let path = self.path(state, &email.id);
+
+        // Write to a temporary file first, so a crash never leaves a truncated email
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_vec_pretty(email)?)?;
+        fs::rename(temporary, path)
+    }
+
+    /// Reads the emails of the subdirectory `state`, ordered by identifier.
+    ///
+    /// Files that cannot be read are logged and moved to the `corrupt` subdirectory.
+    fn list(&self, state: &str) -> io::Result<Vec<QueuedEmail>> {
+        let mut paths = fs::read_dir(self.dir.join(state))?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        paths.retain(|path| path.extension().is_some_and(|ext| ext == EMAIL_EXTENSION));
+        paths.sort();
+
+        let mut emails = Vec::with_capacity(paths.len());
+        for path in paths {
+            match read(&path) {
+                Ok(email) => emails.push(email),
+                // Removed since the directory was listed
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+                Err(err) => {
+                    eprintln!("Moving unreadable email {}: {}", path.display(), err);
+                    if let Some(name) = path.file_name() {
+                        fs::rename(&path, self.dir.join(CORRUPT_DIR).join(name))?;
+                    }
+                }
+            }
+        }
+
+        Ok(emails)
+    }
+}
+
+/// Delays between the delivery attempts of an email.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Number of attempts after which an email is moved to the dead-letter store.
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for every following one.
+    pub base_delay: Duration,
+    /// Longest delay between two attempts.
+    pub max_delay: Duration,
+}
+
+impl RetryPolicy {
+    /// Creates the retry policy of the configuration, with delays of at most an hour.
+    pub fn from_config(config: &Config) -> Self {
+        RetryPolicy {
+            max_attempts: config.queue_max_attempts,
+            base_delay: Duration::from_secs(config.queue_retry_secs),
+            max_delay: Duration::from_secs(60 * 60),
+        }
+    }
+
+    /// Returns the delay before the next attempt of an email that failed `attempts` times.
+    pub fn delay(&self, attempts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempts.saturating_sub(1));
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+}
+
+/// Outcome of a pass of the worker over the queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    /// Number of emails delivered.
+    pub delivered: usize,
+    /// Number of emails that failed and will be retried.
+    pub retried: usize,
+    /// Number of emails moved to the dead-letter store.
+    pub dead: usize,
+}
+
+/// Delivers the emails of a queue through a mail transport, retrying failures with
+/// exponential backoff.
+///
+/// Failures are classified by [`is_permanent`]: permanent ones, such as an SMTP server
+/// rejecting the recipient with a 5xx reply, move the email to the dead-letter store right
+/// away. Transient ones, such as a 4xx reply or a connection error, are retried until the
+/// email has failed `max_attempts` times. A queue must be processed by a single worker.
+pub struct Worker {
+    /// Queue the emails are taken from.
+    queue: Arc<Queue>,
+    /// Mail transport delivering the emails.
+    mailer: Arc<dyn Mailer>,
+    /// Delays between the delivery attempts.
+    policy: RetryPolicy,
+}
+
+impl Worker {
+    /// Creates a worker delivering the emails of `queue` through `mailer`.
+    pub fn new(queue: Arc<Queue>, mailer: Arc<dyn Mailer>, policy: RetryPolicy) -> Self {
+        Worker {
+            queue,
+            mailer,
+            policy,
+        }
+    }
+
+    /// Attempts to deliver every email of the queue that is due, once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the queue cannot be read or updated. Delivery failures are not
+    /// errors: they are recorded on the emails.
+    pub async fn process_due(&self) -> io::Result<Report> {
+        let mut report = Report::default();
+
+        for mut email in self.queue.pending()? {
+            if email.next_attempt_at > now() {
+                continue;
+            }
+
+            let result = match email.message() {
+                Ok(message) => self.mailer.send(message).await,
+                Err(err) => Err(err),
+            };
+            let err = match result {
+                Ok(()) => {
+                    self.queue.remove(&email)?;
+                    report.delivered += 1;
+                    continue;
+                }
+                Err(err) => err,
+            };
+
+            // Record the failure, then retry later or give up
+            email.attempts += 1;
+            email.last_error = Some(err.to_string());
+            if is_permanent(&err) || email.attempts >= self.policy.max_attempts {
+                self.queue.bury(&email)?;
+                report.dead += 1;
+            } else {
+                let delay = self.policy.delay(email.attempts).as_millis() as u64;
+                email.next_attempt_at = now() + delay;
+                self.queue.enqueue(&email)?;
+                report.retried += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Processes the queue in the background every `interval`, until the task is aborted.
+    ///
+    /// Must be called from a Tokio runtime. Errors reading or updating the queue are logged
+    /// and the queue is processed again at the next interval.
+    pub fn spawn(self, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                if let Err(err) = self.process_due().await {
+                    eprintln!("Failed to process the email queue: {}", err);
+                }
+                tokio::time::sleep(interval).await;
+            }
+        })
+    }
+}
+
+/// Returns whether a delivery error is permanent, so retrying the email cannot succeed.
+///
+/// SMTP replies with a 5xx code and invalid addresses are permanent. 4xx replies and other
+/// errors, such as a server that cannot be reached, are transient.
+pub fn is_permanent(err: &MailerError) -> bool {
+    if let Some(err) = err.downcast_ref::<smtp::Error>() {
+        return err.is_permanent();
+    }
+
+    err.is::<AddressError>() || err.is::<lettre::error::Error>()
+}
+
+/// Reads a queued email.
+fn read(path: &Path) -> io::Result<QueuedEmail> {
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+/// Returns the current time, in milliseconds since the Unix epoch.
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
@@ -0,0 +1,290 @@
+use std::{
+    collections::HashSet,
+    fs,
+    io::{Error as IOError, ErrorKind},
+    path::{Path, PathBuf},
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use handlebars::{Handlebars, RenderError, TemplateError};
+use include_dir::{include_dir, Dir, DirEntry, File};
+use serde::Serialize;
+use tokio::task::JoinHandle;
+
+use crate::i18n::{TranslateHelper, Translations};
+
+/// Extension of the template files.
+const TEMPLATE_EXTENSION: &str = "hbs";
+
+/// Templates compiled into the binary: every file of the `templates` directory of the crate.
+static EMBEDDED_TEMPLATES: Dir<'static> = include_dir!("$CARGO_MANIFEST_DIR/templates");
+
+/// Compiled email templates, loaded once at startup and shared by every email.
+///
+/// The templates of the `templates` directory of the crate are compiled into the binary. Every
+/// template is registered under its file stem, so `layouts/base.hbs` is the `base` template and
+/// can be used as a partial by the others. Templates are rendered in strict mode: referencing a
+/// value missing from the data is an error instead of an empty string. Text is translated with
+/// the `t` helper, e.g. `{{t "greeting" name=first_name}}`, into the `locale` of the data.
+///
+/// The registry is `Send` and `Sync`, and is usually shared behind an `Arc`.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use send_emails_smtp::{i18n::Translations, templates::TemplateRegistry};
+/// use std::sync::Arc;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// // Compile the embedded templates, replacing them with the ones under ./custom
+/// let translations = Arc::new(Translations::embedded("en")?);
+/// let templates = TemplateRegistry::load(Some("./custom"), translations)?;
+///
+/// let html = templates.render(
+///     "verification_code",
+///     &serde_json::json!({"first_name": "John", "subject": "Verify", "url": "https://example.com"}),
+/// )?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct TemplateRegistry {
+    /// Handlebars registry holding the compiled templates, replaced when they are reloaded.
+    handlebars: RwLock<Handlebars<'static>>,
+    /// Directory holding the customised templates, if any.
+    overrides: Option<PathBuf>,
+    /// Messages the templates are translated with.
+    translations: Arc<Translations>,
+}
+
+impl TemplateRegistry {
+    /// Compiles the embedded templates, then every template under `overrides`, including its
+    /// subdirectories.
+    ///
+    /// A template of `overrides` replaces the embedded template with the same name, so
+    /// operators can customise the emails without rebuilding. Templates with new names are
+    /// added.
+    ///
+    /// # Arguments
+    ///
+    /// * `overrides` - The directory holding the customised templates, if any.
+    /// * `translations` - The messages the templates are translated with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read, a template has a syntax error, or two
+    /// templates of the directory have the same file stem.
+    pub fn load<P: AsRef<Path>>(
+        overrides: Option<P>,
+        translations: Arc<Translations>,
+    ) -> Result<Self, TemplateError> {
+        let overrides = overrides.map(|dir| dir.as_ref().to_path_buf());
+
+        Ok(TemplateRegistry {
+            handlebars: RwLock::new(compile(overrides.as_deref(), &translations)?),
+            overrides,
+            translations,
+        })
+    }
+
+    /// Compiles the embedded templates only.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an embedded template has a syntax error.
+    pub fn embedded(translations: Arc<Translations>) -> Result<Self, TemplateError> {
+        Self::load(None::<&Path>, translations)
+    }
+
+    /// Returns the messages the templates are translated with.
+    pub fn translations(&self) -> &Translations {
+        &self.translations
+    }
+
+    /// Returns whether a template is registered under `name`.
+    pub fn has_template(&self, name: &str) -> bool {
+        self.handlebars.read().unwrap().has_template(name)
+    }
+
+    /// Renders the template `name` with `data`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the template does not exist or uses a value missing from `data`.
+    pub fn render<T: Serialize>(&self, name: &str, data: &T) -> Result<String, RenderError> {
+        self.handlebars.read().unwrap().render(name, data)
+    }
+
+    /// Compiles the templates again, picking up the changes made to the override directory.
+    ///
+    /// The templates are only replaced if all of them compile, so a failed reload keeps the
+    /// previous templates in use.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read, a template has a syntax error, or two
+    /// templates of the directory have the same file stem.
+    pub fn reload(&self) -> Result<(), TemplateError> {
+        let handlebars = compile(self.overrides.as_deref(), &self.translations)?;
+        *self.handlebars.write().unwrap() = handlebars;
+
+        Ok(())
+    }
+
+    /// Watches the override directory in the background, reloading the templates whenever a
+    /// template file is added, changed or removed.
+    ///
+    /// Meant for development, to iterate on templates and partials without restarting. The
+    /// directory is checked every `interval`. Compile errors are logged and the previous
+    /// templates stay in use until the error is fixed. Must be called from a Tokio runtime;
+    /// does nothing without an override directory.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use send_emails_smtp::{i18n::Translations, templates::TemplateRegistry};
+    /// use std::{sync::Arc, time::Duration};
+    ///
+    /// # #[tokio::main]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let translations = Arc::new(Translations::embedded("en")?);
+    /// let templates = Arc::new(TemplateRegistry::load(Some("./templates"), translations)?);
+    /// let watcher = templates.clone().watch(Duration::from_millis(500));
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn watch(self: Arc<Self>, interval: Duration) -> Option<JoinHandle<()>> {
+        let dir = self.overrides.clone()?;
+
+        // Take the initial state now, so changes made before the task first runs are seen
+        let mut last = fingerprint(&dir);
+        Some(tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+
+                // Compare the modification times and sizes of the template files
+                let current = fingerprint(&dir);
+                if current == last {
+                    continue;
+                }
+                last = current;
+
+                match self.reload() {
+                    Ok(()) => println!("Reloaded the email templates of {}", dir.display()),
+                    Err(err) => eprintln!("Failed to reload the email templates: {}", err),
+                }
+            }
+        }))
+    }
+}
+
+/// Compiles the embedded templates, then the templates under `overrides`, translated with
+/// `translations`.
+fn compile(
+    overrides: Option<&Path>,
+    translations: &Arc<Translations>,
+) -> Result<Handlebars<'static>, TemplateError> {
+    let mut handlebars = Handlebars::new();
+    handlebars.set_strict_mode(true);
+    handlebars.register_helper("t", Box::new(TranslateHelper(translations.clone())));
+
+    for file in embedded_files(&EMBEDDED_TEMPLATES) {
+        let Some(source) = file.contents_utf8() else {
+            let err = IOError::new(ErrorKind::InvalidData, "template is not valid UTF-8");
+            return Err((err, file.path().display().to_string()).into());
+        };
+        handlebars.register_template_string(&template_name(file.path()), source)?;
+    }
+
+    let Some(dir) = overrides else {
+        return Ok(handlebars);
+    };
+
+    let mut overridden = HashSet::new();
+    for path in template_files(dir)? {
+        let name = template_name(&path);
+
+        // Partials are looked up by name, so names must be unique across directories
+        if !overridden.insert(name.clone()) {
+            let err = IOError::new(
+                ErrorKind::AlreadyExists,
+                format!("another template is named {}", name),
+            );
+            return Err((err, path.display().to_string()).into());
+        }
+
+        handlebars.register_template_file(&name, &path)?;
+    }
+
+    Ok(handlebars)
+}
+
+/// Lists the template files under `dir` with their modification time and size, or `None` if
+/// the directory cannot be read.
+fn fingerprint(dir: &Path) -> Option<Vec<(PathBuf, SystemTime, u64)>> {
+    let files = template_files(dir).ok()?;
+
+    Some(
+        files
+            .into_iter()
+            .filter_map(|path| {
+                let metadata = fs::metadata(&path).ok()?;
+                Some((path, metadata.modified().ok()?, metadata.len()))
+            })
+            .collect(),
+    )
+}
+
+/// Returns the name a template file is registered under: its file stem.
+fn template_name(path: &Path) -> String {
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Lists the embedded template files under `dir` and its subdirectories.
+fn embedded_files<'a>(dir: &'a Dir<'a>) -> Vec<&'a File<'a>> {
+    let mut files = Vec::new();
+    for entry in dir.entries() {
+        match entry {
+            DirEntry::Dir(dir) => files.extend(embedded_files(dir)),
+            DirEntry::File(file) => {
+                if file
+                    .path()
+                    .extension()
+                    .is_some_and(|ext| ext == TEMPLATE_EXTENSION)
+                {
+                    files.push(file);
+                }
+            }
+        }
+    }
+
+    files
+}
+
+/// Lists the template files under `dir` and its subdirectories, in a stable order.
+fn template_files(dir: &Path) -> Result<Vec<PathBuf>, TemplateError> {
+    let io_error = |err| TemplateError::from((err, dir.display().to_string()));
+
+    let mut entries = fs::read_dir(dir)
+        .map_err(io_error)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(io_error)?;
+    entries.sort();
+
+    let mut files = Vec::new();
+    for path in entries {
+        if path.is_dir() {
+            files.extend(template_files(&path)?);
+        } else if path
+            .extension()
+            .is_some_and(|ext| ext == TEMPLATE_EXTENSION)
+        {
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
@@ -0,0 +1,204 @@
+/// Elements whose content is not part of the readable text.
+const HIDDEN_ELEMENTS: [&str; 4] = ["head", "script", "style", "title"];
+
+/// Elements that start on a new line.
+const BLOCK_ELEMENTS: [&str; 12] = [
+    "blockquote",
+    "div",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "ol",
+    "table",
+    "tr",
+    "ul",
+];
+
+/// Converts rendered HTML into plain text for the text part of an email.
+///
+/// Styles, scripts and the document head are dropped, paragraphs and other blocks are separated
+/// by blank lines, list items are prefixed with a dash, and links keep their target after their
+/// label, e.g. `Verify your account (https://example.com/verify)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use send_emails_smtp::text::html_to_text;
+///
+/// let text = html_to_text(r#"<p>Hi, John,</p><p><a href="https://example.com">Verify</a></p>"#);
+///
+/// assert_eq!(text, "Hi, John,\n\nVerify (https://example.com)");
+/// ```
+pub fn html_to_text(html: &str) -> String {
+    let mut text = String::new();
+    // Targets of the open links, with the position their label starts at
+    let mut links: Vec<(String, usize)> = Vec::new();
+    let mut rest = html;
+
+    while let Some(start) = rest.find('<') {
+        push_text(&mut text, &rest[..start]);
+        rest = &rest[start..];
+
+        // Skip comments and declarations such as the doctype
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(end) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..end];
+        rest = &rest[end + 1..];
+        if tag.starts_with('!') || tag.starts_with('?') {
+            continue;
+        }
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        match name.as_str() {
+            // Skip everything up to the end of the element
+            name if !closing && HIDDEN_ELEMENTS.contains(&name) => {
+                let close = format!("</{}", name);
+                rest = rest
+                    .to_ascii_lowercase()
+                    .find(&close)
+                    .map_or("", |end| &rest[end..]);
+            }
+            "a" if !closing => links.push((attribute(tag, "href").unwrap_or_default(), text.len())),
+            "a" => {
+                let Some((href, label_start)) = links.pop() else {
+                    continue;
+                };
+                let label = text[label_start..].trim().to_string();
+                if href.is_empty() || href.starts_with('#') || label == href {
+                    continue;
+                }
+                if label.is_empty() {
+                    text.push_str(&href);
+                } else {
+                    text.push_str(&format!(" ({})", href));
+                }
+            }
+            "br" => text.push('\n'),
+            "p" => text.push_str("\n\n"),
+            "li" if !closing => text.push_str("\n- "),
+            "td" | "th" => text.push(' '),
+            name if BLOCK_ELEMENTS.contains(&name) => text.push('\n'),
+            _ => {}
+        }
+    }
+    push_text(&mut text, rest);
+
+    // Trim the lines and keep at most one blank line between blocks
+    let mut lines: Vec<&str> = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if !line.is_empty() || lines.last().is_some_and(|last| !last.is_empty()) {
+            lines.push(line);
+        }
+    }
+    while lines.last().is_some_and(|last| last.is_empty()) {
+        lines.pop();
+    }
+
+    lines.join("\n")
+}
+
+/// Decodes the character references escaped by HTML templates, such as `&amp;` and `&#x3D;`.
+///
+/// Unknown references are kept as they are.
+pub fn unescape(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        decoded.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        let reference = rest[1..]
+            .find(';')
+            .filter(|&end| end <= 8)
+            .and_then(|end| Some((decode_reference(&rest[1..end + 1])?, end + 2)));
+        match reference {
+            Some((c, len)) => {
+                decoded.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+
+    decoded
+}
+
+/// Decodes a character reference without its `&` and `;`, e.g. `amp` or `#x27`.
+fn decode_reference(reference: &str) -> Option<char> {
+    match reference {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = reference.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+/// Appends the text between two tags, decoded and with its whitespace collapsed as browsers do.
+fn push_text(text: &mut String, html: &str) {
+    for c in unescape(html).chars() {
+        if !c.is_whitespace() {
+            text.push(c);
+        } else if !text.ends_with(char::is_whitespace) && !text.is_empty() {
+            text.push(' ');
+        }
+    }
+}
+
+/// Returns the decoded value of the attribute `name` of a tag.
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lowercase = tag.to_ascii_lowercase();
+    let mut search = 0;
+
+    while let Some(found) = lowercase[search..].find(name) {
+        let start = search + found;
+        search = start + name.len();
+
+        // Only match whole attribute names
+        if !lowercase[..start].ends_with(char::is_whitespace) {
+            continue;
+        }
+        let Some(value) = tag[search..].trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or_default(),
+            _ => value.split(char::is_whitespace).next().unwrap_or_default(),
+        };
+
+        return Some(unescape(value));
+    }
+
+    None
+}
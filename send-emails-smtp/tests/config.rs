@@ -0,0 +1,15 @@
+use std::env;
+
+use send_emails_smtp::config::Config;
+
+#[test]
+#[should_panic(expected = "Invalid SMTP_POOL_SIZE")]
+fn empty_smtp_pools_are_rejected() {
+    // The only test of this binary, as it changes the environment of the process
+    env::set_var("MAIL_TRANSPORT", "memory");
+    env::set_var("SMTP_FROM", "sender@test.com");
+    env::set_var("SMTP_TO", "jane@test.com");
+    env::set_var("SMTP_POOL_SIZE", "0");
+
+    Config::init();
+}
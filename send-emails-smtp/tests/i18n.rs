@@ -0,0 +1,178 @@
+use std::sync::Arc;
+
+use send_emails_smtp::{
+    i18n::{TranslationError, Translations},
+    templates::TemplateRegistry,
+};
+use serde_json::json;
+
+/// English catalog of the tests, the default one.
+const EN: &str = r#"
+# Comments are ignored
+-brand = Acme
+
+greeting = Hi, { $name },
+farewell = The { -brand } team
+multiline =
+    First line
+    second line
+literal = Braces: { "{" }
+nested = { greeting } { farewell }
+"#;
+
+/// Portuguese catalog of the tests, translating some of the English messages.
+const PT: &str = "greeting = Olá, { $name },\n-brand = Acme Brasil\n";
+
+/// Brazilian Portuguese catalog of the tests, translating a single message.
+const PT_BR: &str = "greeting = Oi, { $name },\n";
+
+fn translations() -> Translations {
+    Translations::new("en", &[("en", EN), ("pt", PT), ("pt-BR", PT_BR)]).unwrap()
+}
+
+#[test]
+fn messages_are_formatted() {
+    let translations = translations();
+    let en = translations.localizer("en");
+
+    assert_eq!(
+        en.message_with("greeting", &json!({"name": "Jane"}))
+            .unwrap(),
+        "Hi, Jane,"
+    );
+    assert_eq!(en.message("farewell").unwrap(), "The Acme team");
+    assert_eq!(en.message("multiline").unwrap(), "First line\nsecond line");
+    assert_eq!(en.message("literal").unwrap(), "Braces: {");
+    assert_eq!(
+        en.message_with("nested", &json!({"name": 42})).unwrap(),
+        "Hi, 42, The Acme team"
+    );
+}
+
+#[test]
+fn missing_translations_fall_back_to_the_language_then_the_default_locale() {
+    let translations = translations();
+
+    assert_eq!(translations.fallback_chain("pt_BR"), ["pt-BR", "pt", "en"]);
+    assert_eq!(translations.fallback_chain("pt-PT"), ["pt", "en"]);
+    assert_eq!(translations.fallback_chain("fr"), ["en"]);
+
+    let pt_br = translations.localizer("pt-BR");
+    assert_eq!(pt_br.locale(), "pt-BR");
+    assert_eq!(
+        pt_br
+            .message_with("greeting", &json!({"name": "Ana"}))
+            .unwrap(),
+        "Oi, Ana,"
+    );
+    // Terms are looked up along the fallback chain as well
+    assert_eq!(pt_br.message("farewell").unwrap(), "The Acme Brasil team");
+    assert_eq!(
+        translations.localizer("fr").message("farewell").unwrap(),
+        "The Acme team"
+    );
+}
+
+#[test]
+fn formatting_errors_are_reported() {
+    let translations = translations();
+    let en = translations.localizer("en");
+
+    assert!(matches!(
+        en.message("unknown"),
+        Err(TranslationError::MissingMessage { .. })
+    ));
+    assert!(matches!(
+        en.message("greeting"),
+        Err(TranslationError::MissingVariable { name, .. }) if name == "name"
+    ));
+
+    let cycle = Translations::new("en", &[("en", "a = { b }\nb = { a }")]).unwrap();
+    assert!(matches!(
+        cycle.localizer("en").message("a"),
+        Err(TranslationError::Cycle(_))
+    ));
+}
+
+#[test]
+fn invalid_catalogs_are_rejected() {
+    let parse_error = |source: &str| match Translations::new("en", &[("en", source)]) {
+        Err(TranslationError::Parse { line, .. }) => line,
+        other => panic!("expected a parse error, got {:?}", other),
+    };
+
+    assert_eq!(parse_error("a = A\nno equal sign"), 2);
+    assert_eq!(parse_error("a = A\na = B"), 2);
+    assert_eq!(parse_error("\n\nempty ="), 3);
+    assert_eq!(parse_error("a = A\nb = { $n ->\n    [one] One\n    }"), 4);
+
+    assert!(matches!(
+        Translations::new("de", &[("en", EN)]),
+        Err(TranslationError::UnknownLocale(locale)) if locale == "de"
+    ));
+    assert!(matches!(
+        Translations::new("en", &[("en", EN), ("not a locale", PT)]),
+        Err(TranslationError::InvalidLocale(locale)) if locale == "not a locale"
+    ));
+}
+
+#[test]
+fn numbers_select_the_plural_form_of_the_locale() {
+    let minutes = "minutes = { $n } { $n ->\n    [one] minute\n   *[other] minutes\n}";
+    let minutes_ru =
+        "minutes = { $n } { $n ->\n    [one] минута\n    [few] минуты\n   *[many] минут\n}";
+    let translations = Translations::new("en", &[("en", minutes), ("ru", minutes_ru)]).unwrap();
+    let format = |locale: &str, n: u32| {
+        translations
+            .localizer(locale)
+            .message_with("minutes", &json!({ "n": n }))
+            .unwrap()
+    };
+
+    assert_eq!(format("en", 1), "1 minute");
+    assert_eq!(format("en", 2), "2 minutes");
+    assert_eq!(format("ru", 1), "1 минута");
+    assert_eq!(format("ru", 3), "3 минуты");
+    assert_eq!(format("ru", 11), "11 минут");
+    assert_eq!(format("ru", 21), "21 минута");
+
+    // The embedded catalogs use plural forms as well
+    let embedded = Translations::embedded("en").unwrap();
+    assert_eq!(
+        embedded
+            .localizer("ru")
+            .message_with("reset-password-subject", &json!({"minutes": 5}))
+            .unwrap(),
+        "Ваш токен для сброса пароля (действителен только 5 минут)"
+    );
+}
+
+#[test]
+fn templates_are_rendered_in_the_locale_of_the_data() {
+    let translations = Arc::new(Translations::embedded("en").unwrap());
+    let templates = TemplateRegistry::embedded(translations).unwrap();
+    let data = |locale: &str| {
+        json!({
+            "first_name": "<Jane>",
+            "subject": "Subject",
+            "url": "https://example.com",
+            "locale": locale,
+        })
+    };
+
+    let en = templates.render("verification_code", &data("en")).unwrap();
+    assert!(en.contains("Hi, &lt;Jane&gt;,"));
+    assert!(en.contains("Verify your account"));
+
+    let ru = templates
+        .render("verification_code", &data("ru-RU"))
+        .unwrap();
+    assert!(ru.contains("Здравствуйте, &lt;Jane&gt;!"));
+    assert!(ru.contains("Подтвердить учётную запись"));
+
+    // Unknown locales use the default one
+    let fr = templates
+        .render("verification_code.txt", &data("fr"))
+        .unwrap();
+    assert!(fr.starts_with("Hi, &lt;Jane&gt;,"));
+}
@@ -0,0 +1,99 @@
+use std::sync::Arc;
+
+use send_emails_smtp::{
+    i18n::Translations,
+    inline::{inline_css, minify_html},
+    templates::TemplateRegistry,
+};
+use serde_json::json;
+
+#[test]
+fn rules_are_inlined_in_cascade_order() {
+    let html = r#"<html><head><style>
+        /* Comment */
+        p { color: red; margin: 0; }
+        .lead, #intro { font-size: 18px; }
+        p.lead { color: blue !important; }
+        div > p { color: green; }
+        table td p { font-family: "Helvetica"; }
+    </style></head><body><div><p class="lead" style="color: black; margin: 4px">Hi</p></div>
+    <table><tr><td><p id="intro">Bye</p></td></tr></table></body></html>"#;
+
+    let inlined = inline_css(html).unwrap();
+
+    assert!(inlined.contains(
+        r#"<p class="lead" style="font-size: 18px;color: blue !important;margin: 4px">Hi</p>"#
+    ));
+    assert!(inlined.contains(
+        r#"<p id="intro" style="color: red;margin: 0;font-family: 'Helvetica';font-size: 18px;">Bye</p>"#
+    ));
+    // Every rule was inlined, so the stylesheet is left empty and removed by the minifier
+    assert!(!minify_html(&inlined).contains("<style"));
+}
+
+#[test]
+fn media_queries_and_pseudo_classes_stay_in_the_head() {
+    let html = "<head><style>a { color: red; } a:hover { color: blue; }\n\
+        @media only screen and (max-width: 620px) { a { color: green !important; } }</style>\
+        </head><body><a href=\"#\">Link</a><br/><img src=\"a.png\"><a>Other</a></body>";
+
+    assert_eq!(
+        minify_html(&inline_css(html).unwrap()),
+        "<html><head><style>a:hover { color: blue; } @media only screen and (max-width: 620px) \
+         { a { color: green !important; } }</style></head><body><a href=\"#\" style=\"color: \
+         red;\">Link</a><br><img src=\"a.png\"><a style=\"color: red;\">Other</a></body></html>"
+    );
+}
+
+#[test]
+fn values_with_semicolons_are_inlined_whole() {
+    let html = r#"<style>p { background: url(data:image/png;base64,AAAA); font-family: "A;B"; }</style><p>Hi</p>"#;
+
+    assert!(inline_css(html).unwrap().contains(
+        r#"<p style="background: url(data:image/png;base64,AAAA);font-family: 'A;B';">Hi</p>"#
+    ));
+}
+
+#[test]
+fn html_is_minified() {
+    let html = "<!DOCTYPE html>\n<html>\n<head>\n  <style>\n    .a ,\n .b { content: \"a  b\"; }\n  </style>\n\
+        </head>\n<body>\n  <!-- Comment -->\n  <!--[if mso]><table><![endif]-->\n  <p title=\"Two  spaces\">\n    Hi,\n    \
+        <b>John</b> <i>Doe</i>\n  </p>\n  <pre>  keep\n  this</pre>\n</body>\n</html>\n";
+
+    assert_eq!(
+        minify_html(html),
+        "<!DOCTYPE html><html><head><style>.a , .b { content: \"a  b\"; }</style></head><body>\
+         <!--[if mso]><table><![endif]--><p title=\"Two  spaces\"> Hi, <b>John</b> <i>Doe</i></p>\
+         <pre>  keep\n  this</pre></body></html>"
+    );
+}
+
+#[test]
+fn long_documents_are_minified_in_one_pass() {
+    // Looking for an end tag at every tag would take quadratic time on this document
+    let html = "<p>\n  Hi\n</p>\n".repeat(20_000);
+
+    assert_eq!(minify_html(&html), "<p> Hi </p>".repeat(20_000));
+}
+
+#[test]
+fn rendered_templates_are_inlined() {
+    let templates =
+        TemplateRegistry::embedded(Arc::new(Translations::embedded("en").unwrap())).unwrap();
+    let html = templates
+        .render(
+            "verification_code",
+            &json!({"first_name": "John", "subject": "Verify", "url": "https://example.com"}),
+        )
+        .unwrap();
+
+    let inlined = minify_html(&inline_css(&html).unwrap());
+
+    // The rules that cannot be inlined are left in the head
+    assert_eq!(inlined.matches("<style>").count(), 1);
+    assert!(inlined.contains("@media only screen"));
+    assert!(inlined.contains(r#"<body style="background-color: #f6f6f6;"#));
+    assert!(inlined.contains(r#"href="https://example.com""#));
+    assert!(!inlined.contains("\n"));
+    assert!(!inlined.contains("<!--"));
+}
@@ -0,0 +1,223 @@
+use std::sync::Arc;
+
+use send_emails_smtp::{
+    config::Config,
+    email::{Email, EmailTemplate},
+    emails::{PasswordReset, VerificationCode},
+    i18n::{Localizer, TranslationError, Translations},
+    mailer::{self, FileMailer, MemoryMailer},
+    templates::TemplateRegistry,
+    User,
+};
+use serde::Serialize;
+
+/// Builds a configuration delivering emails through `transport`, without an SMTP server.
+fn config(transport: &str, mail_dir: &str) -> Config {
+    Config {
+        smtp_host: String::new(),
+        smtp_port: 587,
+        smtp_user: String::new(),
+        smtp_pass: String::new(),
+        smtp_pool_size: 2,
+        smtp_pool_idle_secs: 5,
+        smtp_from: "sender@test.com".to_string(),
+        smtp_to: "recipient@test.com".to_string(),
+        mail_transport: transport.to_string(),
+        mail_dir: mail_dir.to_string(),
+        templates_dir: None,
+        dev_mode: false,
+        default_locale: "en".to_string(),
+        queue_dir: String::new(),
+        queue_max_attempts: 3,
+        queue_retry_secs: 1,
+    }
+}
+
+/// Creates an email sender delivering through `mailer`.
+fn email(mailer: Arc<dyn mailer::Mailer>) -> Email {
+    let config = config("memory", "");
+    let translations = Arc::new(Translations::embedded(&config.default_locale).unwrap());
+    let templates =
+        Arc::new(TemplateRegistry::load(config.templates_dir.as_ref(), translations).unwrap());
+
+    Email::new(&config, mailer, templates)
+}
+
+/// Returns the test user.
+fn user() -> User {
+    User {
+        name: "Jane Doe".to_string(),
+        email: "jane@test.com".to_string(),
+        locale: "en-US".to_string(),
+    }
+}
+
+/// URL linked to by the test emails.
+const URL: &str = "http://localhost:3000/verifyemail/code";
+
+/// Sends the verification code and the password reset emails to the test user.
+async fn send_both(email: &Email) {
+    let user = user();
+    email
+        .send(&user, &VerificationCode::new(&user, URL))
+        .await
+        .unwrap();
+    email
+        .send(&user, &PasswordReset::new(&user, URL))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn memory_mailer_records_sent_emails() {
+    let mailer = MemoryMailer::default();
+    let email = email(Arc::new(mailer.clone()));
+
+    send_both(&email).await;
+
+    let messages = mailer.messages();
+    assert_eq!(messages.len(), 2);
+    let first = String::from_utf8(messages[0].formatted()).unwrap();
+    assert!(first.contains("Subject: Your account verification code"));
+    assert!(first.contains("To: \"Jane Doe\" <jane@test.com>"));
+    assert!(first.contains("http://localhost:3000/verifyemail/code"));
+}
+
+#[tokio::test]
+async fn emails_have_text_and_html_alternatives() {
+    let mailer = MemoryMailer::default();
+    let email = email(Arc::new(mailer.clone()));
+
+    send_both(&email).await;
+
+    let messages = mailer.messages();
+    for message in &messages {
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("Content-Type: multipart/alternative"));
+        assert!(formatted.contains("Content-Type: text/plain; charset=utf-8"));
+        assert!(formatted.contains("Content-Type: text/html; charset=utf-8"));
+    }
+
+    // The verification code has a text template, the password reset is converted from HTML
+    let verification = String::from_utf8(messages[0].formatted()).unwrap();
+    assert!(verification.contains("Please verify your account to be able to login:"));
+    let reset = String::from_utf8(messages[1].formatted()).unwrap();
+    assert!(reset.contains("Reset password (http://localhost:3000/verifyemail/code)"));
+}
+
+#[tokio::test]
+async fn file_mailer_writes_eml_files() {
+    let dir = std::env::temp_dir().join(format!("send-emails-smtp-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let user = user();
+    email(Arc::new(FileMailer::new(&dir)))
+        .send(&user, &VerificationCode::new(&user, URL))
+        .await
+        .unwrap();
+
+    let files: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    std::fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].extension().unwrap(), "eml");
+}
+
+#[tokio::test]
+async fn transports_are_selected_by_configuration() {
+    assert!(mailer::from_config(&config("memory", "")).is_ok());
+    assert!(mailer::from_config(&config("stdout", "")).is_ok());
+
+    // The SMTP pool connects lazily, so no server is needed to create it within a runtime
+    let mut smtp = config("smtp", "");
+    smtp.smtp_host = "localhost".to_string();
+    assert!(mailer::from_config(&smtp).is_ok());
+
+    let err = mailer::from_config(&config("pigeon", "")).err().unwrap();
+    assert_eq!(err.to_string(), "Unknown MAIL_TRANSPORT: pigeon");
+}
+
+#[tokio::test]
+async fn subjects_are_translated_into_the_locale_of_the_user() {
+    let mailer = MemoryMailer::default();
+    let user = User {
+        locale: "ru".to_string(),
+        ..user()
+    };
+
+    email(Arc::new(mailer.clone()))
+        .send(&user, &PasswordReset::new(&user, URL))
+        .await
+        .unwrap();
+
+    let messages = mailer.messages();
+    assert_eq!(
+        messages[0].headers().get_raw("Subject"),
+        Some("Ваш токен для сброса пароля (действителен только 10 минут)")
+    );
+}
+
+/// Email type defined outside of the crate, reusing the verification template.
+#[derive(Serialize)]
+struct Welcome {
+    first_name: String,
+    url: String,
+}
+
+impl EmailTemplate for Welcome {
+    const TEMPLATE_NAME: &'static str = "verification_code";
+
+    fn subject(&self, _: &Localizer) -> Result<String, TranslationError> {
+        Ok(format!("Welcome, {}", self.first_name))
+    }
+}
+
+/// Email type missing a value its template references.
+#[derive(Serialize)]
+struct Incomplete {
+    first_name: String,
+}
+
+impl EmailTemplate for Incomplete {
+    const TEMPLATE_NAME: &'static str = "verification_code";
+
+    fn subject(&self, _: &Localizer) -> Result<String, TranslationError> {
+        Ok(String::from("Incomplete"))
+    }
+}
+
+#[tokio::test]
+async fn new_email_types_are_sent_with_their_context() {
+    let mailer = MemoryMailer::default();
+    let welcome = Welcome {
+        first_name: "Jane".to_string(),
+        url: "https://example.com/welcome".to_string(),
+    };
+
+    email(Arc::new(mailer.clone()))
+        .send(&user(), &welcome)
+        .await
+        .unwrap();
+
+    let message = String::from_utf8(mailer.messages()[0].formatted()).unwrap();
+    assert!(message.contains("Subject: Welcome, Jane"));
+    assert!(message.contains("<title>Welcome, Jane</title>"));
+    assert!(message.contains("https://example.com/welcome"));
+}
+
+#[tokio::test]
+async fn missing_template_values_are_errors() {
+    let mailer = MemoryMailer::default();
+    let incomplete = Incomplete {
+        first_name: "Jane".to_string(),
+    };
+
+    let result = email(Arc::new(mailer.clone()))
+        .send(&user(), &incomplete)
+        .await;
+
+    assert!(result.is_err());
+    assert!(mailer.messages().is_empty());
+}
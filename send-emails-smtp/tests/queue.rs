@@ -0,0 +1,316 @@
+use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use send_emails_smtp::{
+    config::Config,
+    email::Email,
+    emails::VerificationCode,
+    i18n::Translations,
+    mailer::{Mailer, MailerError, MemoryMailer},
+    queue::{Queue, QueuedEmail, Report, RetryPolicy, Worker},
+    templates::TemplateRegistry,
+    User,
+};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+/// Opens an empty queue for a test.
+fn queue(test: &str) -> (Arc<Queue>, PathBuf) {
+    let dir = std::env::temp_dir().join(format!(
+        "send-emails-smtp-queue-{}-{}",
+        test,
+        std::process::id()
+    ));
+    let _ = fs::remove_dir_all(&dir);
+    (Arc::new(Queue::open(&dir).unwrap()), dir)
+}
+
+/// Builds a configuration enqueuing emails, without an SMTP server.
+fn config() -> Config {
+    Config {
+        smtp_host: String::new(),
+        smtp_port: 587,
+        smtp_user: String::new(),
+        smtp_pass: String::new(),
+        smtp_pool_size: 2,
+        smtp_pool_idle_secs: 5,
+        smtp_from: "sender@test.com".to_string(),
+        smtp_to: String::new(),
+        mail_transport: "memory".to_string(),
+        mail_dir: String::new(),
+        templates_dir: None,
+        dev_mode: false,
+        default_locale: "en".to_string(),
+        queue_dir: String::new(),
+        queue_max_attempts: 3,
+        queue_retry_secs: 0,
+    }
+}
+
+/// Creates a queued email from the sender to the test user.
+fn queued_email() -> QueuedEmail {
+    QueuedEmail::new(
+        "Sender <sender@test.com>".to_string(),
+        "Jane Doe <jane@test.com>".to_string(),
+        "Subject".to_string(),
+        "Text".to_string(),
+        "<p>HTML</p>".to_string(),
+    )
+}
+
+/// Retries right away, giving up after three attempts.
+fn policy() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+    }
+}
+
+/// Mail transport sending to a local SMTP server without TLS.
+struct LocalSmtp(AsyncSmtpTransport<Tokio1Executor>);
+
+#[async_trait]
+impl Mailer for LocalSmtp {
+    async fn send(&self, message: Message) -> Result<(), MailerError> {
+        self.0.send(message).await?;
+        Ok(())
+    }
+}
+
+/// Starts an SMTP server rejecting every sender with `reply`, e.g. `550 Rejected`, and
+/// returns a transport sending to it.
+async fn rejecting_server(reply: &'static str) -> LocalSmtp {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let port = listener.local_addr().unwrap().port();
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(async move {
+                let (reader, mut writer) = stream.into_split();
+                let mut lines = BufReader::new(reader).lines();
+                writer.write_all(b"220 localhost\r\n").await.unwrap();
+
+                while let Ok(Some(line)) = lines.next_line().await {
+                    let response = match line.to_ascii_uppercase().get(..4) {
+                        Some("MAIL") => format!("{}\r\n", reply),
+                        Some("QUIT") => "221 Bye\r\n".to_string(),
+                        _ => "250 OK\r\n".to_string(),
+                    };
+                    if writer.write_all(response.as_bytes()).await.is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+    });
+
+    LocalSmtp(
+        AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+            .port(port)
+            .build(),
+    )
+}
+
+#[tokio::test]
+async fn emails_are_enqueued_then_delivered() {
+    let (queue, dir) = queue("deliver");
+    let mailer = MemoryMailer::default();
+
+    // Enqueue an email rendered by Email
+    let config = config();
+    let translations = Arc::new(Translations::embedded("en").unwrap());
+    let templates = Arc::new(TemplateRegistry::embedded(translations).unwrap());
+    let email = Email::new(&config, Arc::new(mailer.clone()), templates).with_queue(queue.clone());
+    let user = User {
+        name: "Jane Doe".to_string(),
+        email: "jane@test.com".to_string(),
+        locale: "en".to_string(),
+    };
+    email
+        .send(&user, &VerificationCode::new(&user, "https://example.com"))
+        .await
+        .unwrap();
+
+    // The email is only sent by the worker, and survives reopening the queue
+    assert!(mailer.messages().is_empty());
+    let reopened = Arc::new(Queue::open(&dir).unwrap());
+    assert_eq!(reopened.pending().unwrap().len(), 1);
+
+    let worker = Worker::new(reopened, Arc::new(mailer.clone()), policy());
+    let report = worker.process_due().await.unwrap();
+
+    let pending = queue.pending().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(
+        report,
+        Report {
+            delivered: 1,
+            retried: 0,
+            dead: 0
+        }
+    );
+    assert!(pending.is_empty());
+    let message = String::from_utf8(mailer.messages()[0].formatted()).unwrap();
+    assert!(message.contains("Subject: Your account verification code"));
+}
+
+#[tokio::test]
+async fn transient_failures_are_retried_with_backoff() {
+    let (queue, dir) = queue("transient");
+    queue.enqueue(&queued_email()).unwrap();
+
+    let policy = RetryPolicy {
+        base_delay: Duration::from_secs(60),
+        max_delay: Duration::from_secs(3600),
+        ..policy()
+    };
+    let worker = Worker::new(
+        queue.clone(),
+        Arc::new(rejecting_server("451 Try again later").await),
+        policy,
+    );
+
+    // The email is retried later, and not attempted again before then
+    assert_eq!(worker.process_due().await.unwrap().retried, 1);
+    assert_eq!(worker.process_due().await.unwrap(), Report::default());
+
+    let pending = queue.pending().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(pending[0].attempts, 1);
+    assert!(pending[0].last_error.as_ref().unwrap().contains("451"));
+    assert!(pending[0].next_attempt_at > 0);
+}
+
+#[tokio::test]
+async fn emails_are_dead_lettered_after_the_last_attempt() {
+    let (queue, dir) = queue("attempts");
+    queue.enqueue(&queued_email()).unwrap();
+    let worker = Worker::new(
+        queue.clone(),
+        Arc::new(rejecting_server("421 Service not available").await),
+        policy(),
+    );
+
+    let mut reports = Vec::new();
+    for _ in 0..3 {
+        reports.push(worker.process_due().await.unwrap());
+    }
+
+    let dead = queue.dead_letters().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(reports[0].retried, 1);
+    assert_eq!(reports[1].retried, 1);
+    assert_eq!(reports[2].dead, 1);
+    assert_eq!(dead[0].attempts, 3);
+}
+
+#[tokio::test]
+async fn permanent_failures_are_dead_lettered_and_can_be_replayed() {
+    let (queue, dir) = queue("permanent");
+    let email = queued_email();
+    queue.enqueue(&email).unwrap();
+
+    let rejecting = Worker::new(
+        queue.clone(),
+        Arc::new(rejecting_server("550 Mailbox unavailable").await),
+        policy(),
+    );
+    assert_eq!(rejecting.process_due().await.unwrap().dead, 1);
+
+    // The dead letter keeps the error of its last attempt
+    assert!(queue.pending().unwrap().is_empty());
+    let dead = queue.dead_letters().unwrap();
+    assert_eq!(dead[0].id, email.id);
+    assert_eq!(dead[0].attempts, 1);
+    assert!(dead[0].last_error.as_ref().unwrap().contains("550"));
+
+    // Replaying moves it back to the queue, to be delivered with a fresh set of attempts
+    assert!(queue.replay(&email.id).unwrap());
+    assert!(!queue.replay(&email.id).unwrap());
+    assert_eq!(queue.pending().unwrap()[0].attempts, 0);
+
+    let mailer = MemoryMailer::default();
+    let worker = Worker::new(queue.clone(), Arc::new(mailer.clone()), policy());
+    assert_eq!(worker.process_due().await.unwrap().delivered, 1);
+
+    let dead = queue.dead_letters().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(dead.is_empty());
+    assert_eq!(mailer.messages().len(), 1);
+}
+
+#[tokio::test]
+async fn corrupt_emails_are_set_aside_without_stopping_the_queue() {
+    let (queue, dir) = queue("corrupt");
+    let email = queued_email();
+    queue.enqueue(&email).unwrap();
+    fs::write(
+        dir.join("pending").join("0-truncated.json"),
+        "{\"id\": \"0-tru",
+    )
+    .unwrap();
+
+    let mailer = MemoryMailer::default();
+    let worker = Worker::new(queue.clone(), Arc::new(mailer.clone()), policy());
+    let report = worker.process_due().await.unwrap();
+
+    let pending = queue.pending().unwrap();
+    let corrupt = fs::read_dir(dir.join("corrupt")).unwrap().count();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(report.delivered, 1);
+    assert!(pending.is_empty());
+    assert_eq!(corrupt, 1);
+}
+
+#[test]
+fn email_ids_are_unique_across_processes() {
+    let ids: Vec<_> = (0..100).map(|_| queued_email().id).collect();
+
+    let mut unique = ids.clone();
+    unique.sort();
+    unique.dedup();
+    assert_eq!(unique.len(), ids.len());
+    // Another process enqueuing in the same millisecond gets other identifiers
+    let pid = format!("-{:010}-", std::process::id());
+    assert!(ids.iter().all(|id| id.contains(&pid)));
+}
+
+#[test]
+fn replaying_rejects_paths_outside_the_dead_letter_store() {
+    let (queue, dir) = queue("replay-path");
+    let email = queued_email();
+    queue.enqueue(&email).unwrap();
+
+    // The pending email must not be reachable through a relative path
+    let pending = format!("../pending/{}", email.id);
+    let results: Vec<_> = [pending.as_str(), "", "1--2", "/etc/passwd"]
+        .into_iter()
+        .map(|id| queue.replay(id))
+        .collect();
+
+    let pending = queue.pending().unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    for result in results {
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidInput);
+    }
+    assert_eq!(pending.len(), 1);
+}
+
+#[test]
+fn retry_delays_double_up_to_the_maximum() {
+    let policy = RetryPolicy {
+        max_attempts: 10,
+        base_delay: Duration::from_secs(30),
+        max_delay: Duration::from_secs(300),
+    };
+
+    let delays: Vec<_> = (1..=6)
+        .map(|attempts| policy.delay(attempts).as_secs())
+        .collect();
+    assert_eq!(delays, [30, 60, 120, 240, 300, 300]);
+    assert_eq!(policy.delay(u32::MAX), Duration::from_secs(300));
+}
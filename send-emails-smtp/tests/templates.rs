@@ -0,0 +1,160 @@
+use std::{fs, path::PathBuf, sync::Arc, time::Duration};
+
+use send_emails_smtp::{i18n::Translations, templates::TemplateRegistry};
+use serde_json::json;
+
+/// Loads the embedded translations, falling back to English.
+fn translations() -> Arc<Translations> {
+    Arc::new(Translations::embedded("en").unwrap())
+}
+
+/// Creates an empty directory for the templates of a test.
+fn template_dir(test: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("send-emails-smtp-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("layouts")).unwrap();
+    dir
+}
+
+#[test]
+fn templates_are_embedded_in_the_binary() {
+    // Templates do not depend on the working directory
+    std::env::set_current_dir(std::env::temp_dir()).unwrap();
+    let templates = TemplateRegistry::embedded(translations()).unwrap();
+
+    let html = templates
+        .render(
+            "verification_code",
+            &json!({"first_name": "Jane", "subject": "Verify", "url": "https://example.com/v"}),
+        )
+        .unwrap();
+
+    // The layout and the styles partial are embedded as well
+    assert!(html.contains("<title>Verify</title>"));
+    assert!(html.contains("<style>"));
+    assert!(html.contains("Hi, Jane,"));
+    assert!(html.contains("https://example.com/v"));
+}
+
+#[test]
+fn missing_values_fail_in_strict_mode() {
+    let templates = TemplateRegistry::embedded(translations()).unwrap();
+
+    let err = templates
+        .render("verification_code", &json!({"first_name": "Jane"}))
+        .unwrap_err();
+    assert!(err.to_string().contains("subject"));
+}
+
+#[test]
+fn syntax_errors_fail_when_loading() {
+    let dir = template_dir("syntax");
+    fs::write(dir.join("layouts/base.hbs"), "<p>{{#if title}}</p>").unwrap();
+
+    let err = TemplateRegistry::load(Some(&dir), translations())
+        .err()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert_eq!(err.name().map(String::as_str), Some("base"));
+}
+
+#[test]
+fn template_names_are_unique() {
+    let dir = template_dir("duplicate");
+    fs::write(dir.join("base.hbs"), "one").unwrap();
+    fs::write(dir.join("layouts/base.hbs"), "two").unwrap();
+    fs::write(dir.join("notes.txt"), "{{#if}}").unwrap();
+
+    let err = TemplateRegistry::load(Some(&dir), translations())
+        .err()
+        .unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(err.to_string().contains("another template is named base"));
+}
+
+#[test]
+fn override_directory_replaces_embedded_templates() {
+    let dir = template_dir("override");
+    fs::write(
+        dir.join("verification_code.hbs"),
+        "Custom code for {{first_name}}",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("layouts/welcome.hbs"),
+        "{{#> base}}Welcome{{/base}}",
+    )
+    .unwrap();
+
+    let templates = TemplateRegistry::load(Some(&dir), translations()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    let data = json!({"first_name": "Jane", "subject": "Hello", "url": "https://example.com"});
+
+    assert_eq!(
+        templates.render("verification_code", &data).unwrap(),
+        "Custom code for Jane"
+    );
+    assert!(templates
+        .render("reset_password", &data)
+        .unwrap()
+        .contains("Reset"));
+    assert!(templates
+        .render("welcome", &data)
+        .unwrap()
+        .contains("<title>Hello</title>"));
+}
+
+/// Renders the verification code template with fixed data.
+fn verification_code(templates: &TemplateRegistry) -> String {
+    let data = json!({"first_name": "Jane", "subject": "Hello", "url": "https://example.com"});
+    templates.render("verification_code", &data).unwrap()
+}
+
+/// Waits for the rendered verification code template to become `expected`.
+async fn wait_for(templates: &TemplateRegistry, expected: &str) {
+    for _ in 0..100 {
+        if verification_code(templates) == expected {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(verification_code(templates), expected);
+}
+
+#[tokio::test]
+async fn watched_templates_are_reloaded_when_they_change() {
+    let dir = template_dir("watch");
+    let template = dir.join("verification_code.hbs");
+    fs::write(&template, "First {{first_name}}").unwrap();
+
+    let templates = Arc::new(TemplateRegistry::load(Some(&dir), translations()).unwrap());
+    let watcher = templates.clone().watch(Duration::from_millis(10)).unwrap();
+
+    fs::write(&template, "Second version for {{first_name}}").unwrap();
+    wait_for(&templates, "Second version for Jane").await;
+
+    // Broken templates are reported and the previous version stays in use
+    fs::write(&template, "{{#if first_name}} unclosed").unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(verification_code(&templates), "Second version for Jane");
+
+    // Removing the override falls back to the embedded template
+    fs::remove_file(&template).unwrap();
+    for _ in 0..100 {
+        if verification_code(&templates).contains("Hi, Jane,") {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert!(verification_code(&templates).contains("Hi, Jane,"));
+
+    watcher.abort();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn embedded_templates_are_not_watched() {
+    let templates = Arc::new(TemplateRegistry::embedded(translations()).unwrap());
+    assert!(templates.watch(Duration::from_millis(10)).is_none());
+}
@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use send_emails_smtp::{
+    i18n::Translations,
+    templates::TemplateRegistry,
+    text::{html_to_text, unescape},
+};
+use serde_json::json;
+
+#[test]
+fn blocks_are_separated_and_whitespace_collapsed() {
+    let html = "<div>\n  <p>Hi,\n   John,</p>\n<p>Line<br>break</p><ul><li>One</li><li>Two</li></ul></div>";
+
+    assert_eq!(
+        html_to_text(html),
+        "Hi, John,\n\nLine\nbreak\n\n- One\n- Two"
+    );
+}
+
+#[test]
+fn links_keep_their_target() {
+    let html = r##"<p>Open <a href="https://example.com/a?b&#x3D;c" target="_blank">the page</a>,
+        <a href='https://example.com'>https://example.com</a> or <a href="#top">top</a>.</p>"##;
+
+    assert_eq!(
+        html_to_text(html),
+        "Open the page (https://example.com/a?b=c), https://example.com or top."
+    );
+}
+
+#[test]
+fn head_styles_and_comments_are_dropped() {
+    let html = "<!DOCTYPE html><html><head><title>Subject</title><STYLE>p { color: red; }</STYLE>\
+        </head><body><!-- START -->Tom &amp; Jerry&nbsp;&lt;3</body></html>";
+
+    assert_eq!(html_to_text(html), "Tom & Jerry <3");
+}
+
+#[test]
+fn escaped_references_are_decoded() {
+    assert_eq!(
+        unescape("a &amp; b &#x27;c&#39; &quot;d&quot; &unknown; & e"),
+        "a & b 'c' \"d\" &unknown; & e"
+    );
+}
+
+#[test]
+fn embedded_templates_convert_to_text() {
+    let templates =
+        TemplateRegistry::embedded(Arc::new(Translations::embedded("en").unwrap())).unwrap();
+    let data =
+        json!({"first_name": "Jane", "subject": "Reset", "url": "https://example.com/reset"});
+
+    let text = html_to_text(&templates.render("reset_password", &data).unwrap());
+
+    assert!(text.starts_with("Hi, Jane,"));
+    assert!(text.contains("Reset password (https://example.com/reset)"));
+    assert!(!text.contains("{"));
+}